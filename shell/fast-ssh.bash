@@ -0,0 +1,11 @@
+# Fast-SSH key binding for bash, load it with `eval "$(fast-ssh init bash)"`
+# Alt+S inserts the host picked in Fast-SSH at the cursor
+
+__fast_ssh_pick() {
+  local host
+  host="$(fast-ssh pick)" || return
+  READLINE_LINE="${READLINE_LINE:0:$READLINE_POINT}${host}${READLINE_LINE:$READLINE_POINT}"
+  READLINE_POINT=$((READLINE_POINT + ${#host}))
+}
+
+bind -x '"\es": __fast_ssh_pick'
@@ -0,0 +1,10 @@
+# Fast-SSH key binding for fish, load it with `fast-ssh init fish | source`
+# Alt+S inserts the host picked in Fast-SSH at the cursor
+
+function __fast_ssh_pick
+    set -l host (fast-ssh pick)
+    and commandline --insert -- $host
+    commandline --function repaint
+end
+
+bind \es __fast_ssh_pick
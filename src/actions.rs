@@ -0,0 +1,235 @@
+use serde::Deserialize;
+use ssh_cfg::SshOptionKey;
+use std::process::Command;
+
+use fast_ssh::SshGroupItem;
+
+// Placeholders that can be used in the command of an action
+const PLACEHOLDERS: [&str; 5] = ["alias", "hostname", "user", "port", "group"];
+
+// Port used by ssh when the host does not set one
+const DEFAULT_SSH_PORT: &str = "22";
+
+/// A command defined in the `actions` section of the config, e.g.
+/// `mosh {alias}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomAction {
+    pub name: String,
+    /// Key launching the action from the hosts list
+    #[serde(default)]
+    pub key: Option<String>,
+    pub command: String,
+    /// The command needs the terminal, Fast-SSH is closed while it runs
+    #[serde(default = "default_interactive")]
+    pub interactive: bool,
+    /// Come back to the TUI once an interactive command exits
+    #[serde(default)]
+    pub return_to_tui: bool,
+}
+
+fn default_interactive() -> bool {
+    true
+}
+
+/// Checks the names and command templates of the actions.
+pub fn validate_actions(actions: &[CustomAction]) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for (idx, action) in actions.iter().enumerate() {
+        if action.name.trim().is_empty() {
+            errors.push(format!("Action #{} has no name", idx + 1));
+        } else if actions[..idx].iter().any(|other| other.name == action.name) {
+            errors.push(format!("Action `{}` is defined twice", action.name));
+        }
+
+        if let Err(error) = validate_template(&action.command) {
+            errors.push(format!("{} in the command of `{}`", error, action.name));
+        }
+    }
+
+    errors
+}
+
+fn validate_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("Empty command".to_string());
+    }
+
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            return Err("Unclosed `{`".to_string());
+        };
+
+        let name = &rest[start + 1..start + end];
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(format!("Unknown placeholder `{{{}}}`", name));
+        }
+        rest = &rest[start + end + 1..];
+    }
+
+    Ok(())
+}
+
+/// Replaces the placeholders of the template with the values of the host,
+/// quoted for the shell. Each placeholder is replaced once, braces in the
+/// values are kept as they are.
+pub fn render_command(template: &str, item: &SshGroupItem) -> String {
+    let option = |key: SshOptionKey| item.host_config.get(&key).map(String::as_str);
+    let ssh_name = item.ssh_name();
+
+    let values: [(&str, &str); PLACEHOLDERS.len()] = [
+        ("alias", &ssh_name),
+        (
+            "hostname",
+            option(SshOptionKey::Hostname).unwrap_or(&ssh_name),
+        ),
+        ("user", option(SshOptionKey::User).unwrap_or_default()),
+        (
+            "port",
+            option(SshOptionKey::Port).unwrap_or(DEFAULT_SSH_PORT),
+        ),
+        ("group", item.group_name()),
+    ];
+    let value_of = |name: &str| {
+        values
+            .iter()
+            .find(|(placeholder, _)| *placeholder == name)
+            .map(|(_, value)| *value)
+    };
+
+    let mut command = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        command.push_str(&rest[..start]);
+
+        let placeholder = rest[start..].find('}').and_then(|end| {
+            value_of(&rest[start + 1..start + end]).map(|value| (end, value))
+        });
+        match placeholder {
+            Some((end, value)) => {
+                command.push_str(&shell_quote(value));
+                rest = &rest[start + end + 1..];
+            }
+            // Not a placeholder, kept as is
+            None => {
+                command.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    command.push_str(rest);
+
+    command
+}
+
+/// Builds the shell command running the action against the host.
+pub fn build_command(action: &CustomAction, item: &SshGroupItem) -> Command {
+    let command_line = render_command(&action.command, item);
+
+    if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(command_line);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(command_line);
+        command
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./-_~".contains(c);
+
+    if !value.is_empty() && value.chars().all(is_safe) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(name: &str, command: &str) -> CustomAction {
+        CustomAction {
+            name: name.to_string(),
+            key: None,
+            command: command.to_string(),
+            interactive: true,
+            return_to_tui: false,
+        }
+    }
+
+    #[test]
+    fn test_render_command() {
+        let item = SshGroupItem::new(
+            "prod/web",
+            &[
+                (SshOptionKey::Hostname, "10.0.0.1"),
+                (SshOptionKey::User, "deploy"),
+            ],
+        );
+
+        assert_eq!(
+            render_command(
+                "rsync -av . {user}@{hostname}:/srv -e 'ssh -p {port}'",
+                &item
+            ),
+            "rsync -av . deploy@10.0.0.1:/srv -e 'ssh -p 22'"
+        );
+        assert_eq!(
+            render_command("tmux new-window -n {group} ssh {alias}", &item),
+            "tmux new-window -n prod ssh prod/web"
+        );
+
+        // Values are quoted for the shell
+        let item = SshGroupItem::new("it's", &[]);
+        assert_eq!(render_command("mosh {alias}", &item), "mosh 'it'\\''s'");
+        assert_eq!(render_command("echo {user}", &item), "echo ''");
+    }
+
+    #[test]
+    fn test_render_command_multi_pattern_host() {
+        let item = SshGroupItem::new(
+            "{user}/web web.example.com",
+            &[(SshOptionKey::User, "{port}")],
+        );
+
+        // ssh is given the first pattern, and values are not expanded again
+        assert_eq!(
+            render_command("ssh {alias} -l {user} -p {port} # {group}", &item),
+            "ssh '{user}/web' -l '{port}' -p 22 # '{user}'"
+        );
+        assert_eq!(render_command("ping {hostname}", &item), "ping '{user}/web'");
+    }
+
+    #[test]
+    fn test_validate_actions() {
+        assert!(validate_actions(&[action("mosh", "mosh {alias}")]).is_empty());
+
+        assert_eq!(
+            validate_actions(&[
+                action("mosh", "mosh {host}"),
+                action("mosh", "mosh {alias"),
+                action("", "true"),
+            ]),
+            vec![
+                "Unknown placeholder `{host}` in the command of `mosh`",
+                "Action `mosh` is defined twice",
+                "Unclosed `{` in the command of `mosh`",
+                "Action #3 has no name",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deserialize_defaults() {
+        let action: CustomAction =
+            serde_yaml::from_str("name: mosh\ncommand: mosh {alias}").unwrap();
+        assert!(action.interactive);
+        assert!(!action.return_to_tui);
+        assert_eq!(action.key, None);
+    }
+}
@@ -1,11 +1,33 @@
 use anyhow::{format_err, Context, Result};
+use fast_ssh::{
+    database::{HistoryEntry, HistoryFilter},
+    ssh_config_loader::{LoadedSshConfig, SshConfigLoader},
+    ssh_config_store::{FAVORITES_GROUP, RECENTS_GROUP},
+    stale_entries::{find_stale_entries, StaleEntry},
+    FileDatabase, SshConfigStore, SshGroup, SshGroupItem,
+};
+use ssh_cfg::SshOptionKey;
+use std::collections::HashSet;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::SystemTime;
 use tui::widgets::TableState;
 
 use crate::{
-    database::FileDatabase,
+    actions::CustomAction,
+    command_palette::CommandPalette,
+    config,
+    file_watcher::FileWatcher,
+    get_config,
+    host_editor::{self, HostEditor},
+    keybindings::KeyChord,
+    launcher::{self, LaunchOutcome, Launcher, ProcessLauncher},
+    parallel_run::{self, ParallelRun},
+    reachability::ReachabilityChecker,
+    tunnels::{TunnelManager, TunnelStatus},
     searcher::Searcher,
-    ssh_config_store::{SshConfigStore, SshGroup, SshGroupItem},
+    set_config,
 };
 
 // Default number of items to scroll when using half-page navigation
@@ -14,6 +36,8 @@ const DEFAULT_HALF_PAGE_SIZE: usize = 10;
 pub enum ConfigDisplayMode {
     Global,
     Selected,
+    /// Options of the selected host once wildcard and `Match` blocks apply
+    Resolved,
 }
 
 pub enum AppState {
@@ -21,6 +45,42 @@ pub enum AppState {
     Normal,
 }
 
+/// Command to run against the selected host once the TUI is closed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Launch {
+    Ssh,
+    CopySshKey,
+    CopyFiles,
+    /// Index of the action in the `actions` section of the config
+    Custom(usize),
+}
+
+impl Launch {
+    pub fn name(&self) -> String {
+        match self {
+            Launch::Ssh => "ssh".to_string(),
+            Launch::CopySshKey => "ssh-copy-id".to_string(),
+            Launch::CopyFiles => "sftp".to_string(),
+            Launch::Custom(_) => self.custom_action().map(|action| action.name).unwrap_or_default(),
+        }
+    }
+
+    pub fn custom_action(&self) -> Option<CustomAction> {
+        match self {
+            Launch::Custom(idx) => get_config().actions.get(*idx).cloned(),
+            _ => None,
+        }
+    }
+}
+
+/// Paths overriding the default locations, usually given on the command line.
+#[derive(Debug, Default)]
+pub struct AppOptions {
+    pub ssh_config_path: Option<PathBuf>,
+    pub db_path: Option<PathBuf>,
+    pub app_config_path: Option<PathBuf>,
+}
+
 pub struct App {
     pub state: AppState,
     pub searcher: Searcher,
@@ -29,40 +89,745 @@ pub struct App {
     pub scs: SshConfigStore,
     pub config_display_mode: ConfigDisplayMode,
     pub should_quit: bool,
-    pub should_spawn_ssh: bool,
-    pub should_copy_ssh_key: bool,
-    pub should_copy_files: bool,
+    pub launch: Option<Launch>,
+    /// Runs the launches, once the TUI is closed for the interactive ones
+    pub launcher: Box<dyn Launcher>,
 
     pub config_paragraph_offset: u16,
     pub db: FileDatabase,
     pub show_help: bool,
-    pub pending_g: bool, // Track if 'g' was just pressed for 'gg' detection
+    pub show_history: bool,
+    /// Launches of the history panel and the host they were loaded for,
+    /// `None` once they must be loaded again
+    history: Option<(Option<String>, Vec<HistoryEntry>)>,
+    pub show_group_tree: bool,
+    /// Groups whose subgroups are shown
+    pub expanded_groups: HashSet<String>,
+    pub pending_keys: Vec<KeyChord>, // Keys typed so far of a sequence such as `g g`
+    pub status_message: Option<String>,
+    pub host_editor: Option<HostEditor>,
+    pub palette: Option<CommandPalette>,
+    /// Host waiting for the user to confirm its deletion
+    pub pending_delete: Option<String>,
+    /// Missing favorite and the host it may have been renamed to, waiting
+    /// for the user to confirm
+    pub pending_rename: Option<(String, String)>,
+    ignored_renames: Vec<String>,
+    pub reachability: ReachabilityChecker,
+    /// Hosts picked to run a command on
+    pub selected_hosts: HashSet<String>,
+    /// Command being typed, to run on the picked hosts
+    pub run_prompt: Option<String>,
+    pub parallel_run: Option<ParallelRun>,
+    pub show_run_results: bool,
+    /// Where the results of the command runs are exported
+    runs_dir: PathBuf,
+    pub tunnels: TunnelManager,
+    /// Selected tunnel of the tunnels panel, when it is open
+    pub tunnels_panel: Option<TableState>,
+    /// Database entries of hosts no longer in the config, listed in the
+    /// clean-up panel
+    pub stale_entries: Vec<StaleEntry>,
+    /// Selected entry of the clean-up panel, when it is open
+    pub prune_panel: Option<TableState>,
+    ssh_config_path: Option<PathBuf>,
+    app_config_path: Option<PathBuf>,
+    /// Watches the SSH config files and the app config
+    watcher: Option<FileWatcher>,
+    /// SSH config being loaded in the background after a change
+    background_reload: Option<(Receiver<Result<LoadedSshConfig>>, SystemTime)>,
+    /// When the SSH config was last read, older changes are already loaded
+    scs_loaded_at: SystemTime,
 }
 
 impl App {
-    pub async fn new() -> Result<App> {
-        let db = App::create_or_get_db_file()?;
-        let scs = SshConfigStore::new(&db).await?;
+    pub fn new(options: &AppOptions) -> Result<App> {
+        let scs_loaded_at = SystemTime::now();
+        let db = App::create_or_get_db_file(options.db_path.as_deref())?;
+        let scs = SshConfigStore::new(&db, options.ssh_config_path.as_deref())?;
+        let data_dir = App::data_dir(options.db_path.as_deref());
+
+        let mut app = App::from_store(scs, db, data_dir);
+        app.ssh_config_path = options.ssh_config_path.clone();
+        app.launcher = Box::new(ProcessLauncher::new(options.ssh_config_path.clone()));
+        app.app_config_path = config::config_file_path(options.app_config_path.as_deref());
+        app.watcher = FileWatcher::new(app.watched_files());
+        app.scs_loaded_at = scs_loaded_at;
+        Ok(app)
+    }
 
-        Ok(App {
+    /// App over an SSH config and a database that are already loaded, e.g.
+    /// in memory. No file is watched for changes. The PID files of the
+    /// tunnels and the exported results are kept in `data_dir`.
+    pub fn from_store(scs: SshConfigStore, db: FileDatabase, data_dir: PathBuf) -> App {
+        // Problems met while loading are shown at startup, such as a
+        // recovered or upgraded database
+        let mut warnings = db.warnings.clone();
+        if !scs.warnings.is_empty() {
+            warnings.push(format!("SSH config loaded with warnings: {}", scs.warnings.join(", ")));
+        }
+        let status_message = (!warnings.is_empty()).then(|| warnings.join(". "));
+        // The tree is only worth its room when there are subgroups
+        let show_group_tree = scs.groups.iter().any(|group| group.depth() > 0);
+
+        let mut app = App {
             state: AppState::Normal,
             selected_group: 0,
             config_paragraph_offset: 0,
             scs,
             host_state: TableState::default(),
             should_quit: false,
-            should_spawn_ssh: false,
-            should_copy_ssh_key: false,
-            should_copy_files: false,
+            launch: None,
+            launcher: Box::new(ProcessLauncher::new(None)),
             config_display_mode: ConfigDisplayMode::Selected,
             db,
             searcher: Searcher::new(),
             show_help: false,
-            pending_g: false,
-        })
+            show_history: false,
+            history: None,
+            show_group_tree,
+            expanded_groups: HashSet::new(),
+            pending_keys: Vec::new(),
+            status_message,
+            host_editor: None,
+            palette: None,
+            pending_delete: None,
+            pending_rename: None,
+            ignored_renames: Vec::new(),
+            reachability: ReachabilityChecker::new(&get_config().reachability),
+            selected_hosts: HashSet::new(),
+            run_prompt: None,
+            parallel_run: None,
+            show_run_results: false,
+            tunnels: TunnelManager::new(data_dir.join("tunnels")),
+            runs_dir: data_dir.join("runs"),
+            tunnels_panel: None,
+            stale_entries: Vec::new(),
+            prune_panel: None,
+            ssh_config_path: None,
+            app_config_path: None,
+            watcher: None,
+            background_reload: None,
+            scs_loaded_at: SystemTime::now(),
+        };
+
+        app.check_renamed_favorites();
+        app
+    }
+
+    /// Rebuilds the config store (e.g. to pick up the counts updated by a
+    /// launch), keeping the selected group and host when they still exist.
+    pub fn reload(&mut self) -> Result<()> {
+        let loaded_at = SystemTime::now();
+        let scs = SshConfigStore::new(&self.db, self.ssh_config_path.as_deref())?;
+        self.replace_store(scs, loaded_at);
+        Ok(())
     }
 
-    pub fn create_or_get_db_file() -> Result<FileDatabase> {
+    /// Adds the warnings met while loading the config store to a status
+    /// message, since they can't be printed over the TUI.
+    pub fn with_store_warnings(&self, message: String) -> String {
+        match self.scs.warnings.is_empty() {
+            true => message,
+            false => format!("{} (warnings: {})", message, self.scs.warnings.join(", ")),
+        }
+    }
+
+    fn replace_store(&mut self, scs: SshConfigStore, loaded_at: SystemTime) {
+        let selected_group_name = self.get_selected_group().map(|group| group.name.clone());
+        let selected_item_name = self.get_selected_item().map(|item| item.full_name.clone());
+
+        self.scs = scs;
+        self.scs_loaded_at = loaded_at;
+        self.searcher.invalidate();
+        if let Some(watcher) = &self.watcher {
+            watcher.watch(self.watched_files());
+        }
+
+        self.selected_group = selected_group_name
+            .and_then(|name| self.scs.groups.iter().position(|group| group.name == name))
+            .unwrap_or(0);
+
+        let selected_item = selected_item_name.and_then(|name| {
+            self.get_items_based_on_mode()
+                .iter()
+                .position(|item| item.full_name == name)
+        });
+        self.host_state.select(Some(selected_item.unwrap_or(0)));
+
+        // Hosts may have been renamed along with their history
+        self.refresh_history();
+        self.check_renamed_favorites();
+    }
+
+    /// Files of the SSH config, the directories of its `Include` patterns so
+    /// that new files are picked up, and the app config.
+    fn watched_files(&self) -> Vec<PathBuf> {
+        self.scs
+            .files
+            .iter()
+            .chain(&self.scs.include_dirs)
+            .chain(&self.app_config_path)
+            .cloned()
+            .collect()
+    }
+
+    /// Picks up the changes made to the watched files by another program:
+    /// the SSH config is loaded again in the background, the app config
+    /// right away. Errors are shown in the status line.
+    pub fn check_file_changes(&mut self) {
+        let changed = match &self.watcher {
+            Some(watcher) => watcher.changed_files(),
+            None => Vec::new(),
+        };
+
+        if self.app_config_path.as_ref().is_some_and(|path| changed.contains(path)) {
+            self.reload_app_config();
+        }
+        // Files written by Fast-SSH itself were loaded again right away
+        let is_newer = |file: &PathBuf| {
+            fs::metadata(file)
+                .and_then(|metadata| metadata.modified())
+                .map_or(true, |modified| modified > self.scs_loaded_at)
+        };
+        let is_ssh_config =
+            |file: &PathBuf| self.scs.files.contains(file) || self.scs.include_dirs.contains(file);
+        if changed
+            .iter()
+            .any(|file| is_ssh_config(file) && is_newer(file))
+        {
+            self.start_background_reload();
+        }
+
+        let Some((receiver, loaded_at)) = &self.background_reload else {
+            return;
+        };
+        let loaded_at = *loaded_at;
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(format_err!("The loading was interrupted")),
+        };
+        self.background_reload = None;
+
+        let store = result.and_then(|loaded| SshConfigStore::from_loaded(loaded, &self.db));
+        self.status_message = Some(match store {
+            Ok(scs) => {
+                self.replace_store(scs, loaded_at);
+                self.with_store_warnings("SSH config reloaded".to_string())
+            }
+            Err(e) => format!("Failed to reload the SSH config: {:#}", e),
+        });
+    }
+
+    /// Loads the SSH config on a background thread, the result is applied
+    /// by `check_file_changes`.
+    fn start_background_reload(&mut self) {
+        let Some(root_file) = self.scs.files.first().cloned() else {
+            return;
+        };
+        let (sender, receiver) = mpsc::channel();
+        let loaded_at = SystemTime::now();
+
+        // A newer load replaces the one still running, if any
+        tokio::task::spawn_blocking(move || {
+            let loaded = SshConfigLoader::new().and_then(|loader| loader.load(&root_file));
+            let _ = sender.send(loaded);
+        });
+        self.background_reload = Some((receiver, loaded_at));
+    }
+
+    fn reload_app_config(&mut self) {
+        let Some(path) = &self.app_config_path else {
+            return;
+        };
+
+        self.status_message = Some(match config::parse_config_file(path) {
+            Ok(new_config) => {
+                let old_config = get_config();
+                set_config(new_config);
+                let config = get_config();
+                self.reachability = ReachabilityChecker::new(&config.reachability);
+
+                // The panel may list fewer tunnels now
+                if let Some(state) = &mut self.tunnels_panel {
+                    let len = config.tunnels.len();
+                    state.select((len > 0).then(|| state.selected().unwrap_or(0).min(len - 1)));
+                }
+
+                let tunnels = self.tunnels.apply_config(
+                    &old_config.tunnels,
+                    &config.tunnels,
+                    &self.scs,
+                    self.ssh_config_path.as_deref(),
+                );
+                match tunnels {
+                    Ok(changed) if changed.is_empty() => "Configuration reloaded".to_string(),
+                    Ok(changed) => format!(
+                        "Configuration reloaded, tunnels updated: {}",
+                        changed.join(", ")
+                    ),
+                    Err(e) => format!("Configuration reloaded, but not its tunnels: {}", e),
+                }
+            }
+            Err(e) => format!("Configuration not reloaded: {:#}", e),
+        });
+    }
+
+    /// Asks the user whether a missing favorite was renamed, if one seems to
+    /// have been.
+    fn check_renamed_favorites(&mut self) {
+        if self.pending_rename.is_some() {
+            return;
+        }
+
+        self.pending_rename = self.scs.find_renamed_favorite(&self.ignored_renames);
+        if let Some((old_name, new_name)) = &self.pending_rename {
+            self.status_message = Some(format!(
+                "Favorite {} is missing, was it renamed to {}? (y/n)",
+                old_name, new_name
+            ));
+        }
+    }
+
+    /// Moves the favorite waiting for confirmation to its new name, or stops
+    /// asking about it.
+    pub fn confirm_rename(&mut self, accepted: bool) {
+        let Some((old_name, new_name)) = self.pending_rename.take() else {
+            return;
+        };
+        self.status_message = None;
+
+        if !accepted {
+            self.ignored_renames.push(old_name);
+            self.check_renamed_favorites();
+            return;
+        }
+
+        match self.db.rename_host(&old_name, &new_name) {
+            Ok(()) => self.reload_after_edit(
+                &new_name,
+                format!("Favorite {} renamed to {}", old_name, new_name),
+            ),
+            Err(e) => {
+                self.ignored_renames.push(old_name.clone());
+                self.status_message = Some(format!("Failed to rename {}: {}", old_name, e));
+            }
+        }
+    }
+
+    /// Stars or unstars the selected host.
+    pub fn toggle_favorite(&mut self) {
+        let Some(item) = self.get_selected_item() else {
+            return;
+        };
+        let host = item.full_name.clone();
+        let hostname = item.host_config.get(&SshOptionKey::Hostname).cloned();
+
+        let message = match self.db.toggle_favorite(&host, hostname.as_deref()) {
+            Ok(true) => format!("Added {} to the favorites", host),
+            Ok(false) => format!("Removed {} from the favorites", host),
+            Err(e) => format!("Failed to update the favorites: {}", e),
+        };
+
+        // Stay on the host, unless it just left the displayed favorites
+        let in_favorites = self
+            .get_selected_group()
+            .is_some_and(|group| group.name == FAVORITES_GROUP);
+        let selected = self.host_state.selected();
+        self.reload_after_edit(&host, message);
+        let left_favorites = in_favorites && !self.scs.is_favorite(&host);
+        if left_favorites && self.select_group_by_name(FAVORITES_GROUP) {
+            self.host_state.select(selected.map(|idx| idx.saturating_sub(1)));
+        }
+    }
+
+    /// Moves the selected favorite up or down in the Favorites group.
+    pub fn move_favorite(&mut self, up: bool) {
+        let in_favorites = self
+            .get_selected_group()
+            .is_some_and(|group| group.name == FAVORITES_GROUP);
+        let Some(host) = self.get_selected_item().map(|item| item.full_name.clone()) else {
+            return;
+        };
+
+        if !in_favorites || !matches!(self.state, AppState::Normal) {
+            self.status_message =
+                Some("Favorites are reordered from the Favorites group".to_string());
+            return;
+        }
+
+        match self.db.move_favorite(&host, up) {
+            // The reload keeps the Favorites group and the moved host selected
+            Ok(true) => {
+                self.status_message = Some(match self.reload() {
+                    Ok(()) => self.with_store_warnings(format!("Moved {}", host)),
+                    Err(e) => format!("Failed to reload the SSH config: {}", e),
+                });
+            }
+            Ok(false) => {}
+            Err(e) => self.status_message = Some(format!("Failed to move {}: {}", host, e)),
+        }
+    }
+
+    /// Picks the highlighted host to run a command on, or drops it, then
+    /// moves to the next host.
+    pub fn toggle_host_selection(&mut self) {
+        let Some(host) = self.get_selected_item().map(|item| item.full_name.clone()) else {
+            return;
+        };
+
+        if !self.selected_hosts.remove(&host) {
+            self.selected_hosts.insert(host);
+        }
+
+        let items_len = self.get_items_based_on_mode().len();
+        if let Some(idx) = self.host_state.selected().filter(|idx| idx + 1 < items_len) {
+            self.host_state.select(Some(idx + 1));
+        }
+    }
+
+    /// Picks every listed host (the group or the search results), or drops
+    /// them when they are all picked already.
+    pub fn toggle_listed_hosts_selection(&mut self) {
+        let listed: Vec<String> = self
+            .get_items_based_on_mode()
+            .iter()
+            .map(|item| item.full_name.clone())
+            .collect();
+
+        if listed.iter().all(|host| self.selected_hosts.contains(host)) {
+            for host in &listed {
+                self.selected_hosts.remove(host);
+            }
+        } else {
+            self.selected_hosts.extend(listed);
+        }
+    }
+
+    /// Hosts a command runs on: the picked ones, or else the highlighted one.
+    pub fn run_targets(&self) -> Vec<&SshGroupItem> {
+        if self.selected_hosts.is_empty() {
+            return self.get_selected_item().into_iter().collect();
+        }
+
+        self.scs
+            .items()
+            .filter(|item| self.selected_hosts.contains(&item.full_name))
+            .collect()
+    }
+
+    /// Runs the command of the prompt on the target hosts, the results are
+    /// shown in place of the configuration.
+    pub fn start_parallel_run(&mut self) {
+        let Some(command) = self.run_prompt.take() else {
+            return;
+        };
+        let command = command.trim();
+        let hosts = self.run_targets();
+        if command.is_empty() || hosts.is_empty() {
+            return;
+        }
+
+        let config = &get_config().parallel_run;
+        let hosts_count = hosts.len();
+        let processes = hosts
+            .into_iter()
+            .map(|host| {
+                let process = parallel_run::ssh_command(
+                    host,
+                    command,
+                    self.ssh_config_path.as_deref(),
+                    config,
+                );
+                (host.full_name.clone(), process)
+            })
+            .collect();
+        let run = ParallelRun::start(command, processes, config.concurrency);
+
+        match run {
+            Ok(run) => {
+                self.status_message =
+                    Some(format!("Running `{}` on {} host(s)", command, hosts_count));
+                self.parallel_run = Some(run);
+                self.show_run_results = true;
+                self.show_history = false;
+                self.config_paragraph_offset = 0;
+            }
+            Err(e) => self.status_message = Some(format!("Failed to run `{}`: {}", command, e)),
+        }
+    }
+
+    /// Writes the results of the last command run to a file next to the
+    /// database.
+    pub fn export_run_results(&mut self) {
+        let Some(run) = &self.parallel_run else {
+            self.status_message = Some("No command has been run yet".to_string());
+            return;
+        };
+
+        let path = self.runs_dir.join(format!("fast-ssh-run-{}.log", run.started_at));
+        let path = std::path::absolute(&path).unwrap_or(path);
+        let exported = fs::create_dir_all(&self.runs_dir)
+            .map_err(|e| format_err!("Could not create {}: {}", self.runs_dir.display(), e))
+            .and_then(|()| run.export(&path));
+
+        self.status_message = Some(match exported {
+            Ok(()) => format!("Results exported to {}", path.display()),
+            Err(e) => format!("Failed to export the results: {}", e),
+        });
+    }
+
+    /// Writes the host of the open editor to its file. The editor stays open
+    /// with an error if the form is invalid or the write failed.
+    pub fn save_host_editor(&mut self) {
+        let Some(editor) = &mut self.host_editor else {
+            return;
+        };
+
+        match editor.save(&self.scs) {
+            Ok(host) => {
+                // Keep the favorite, counts and history of a renamed host
+                let mut message = format!("Saved {}", host);
+                if let Some(original) = editor.renamed_from() {
+                    if let Err(e) = self.db.rename_host(original, &host) {
+                        message =
+                            format!("Saved {}, but failed to move its history: {}", host, e);
+                    }
+                }
+
+                self.host_editor = None;
+                self.reload_after_edit(&host, message);
+            }
+            Err(e) => editor.error = Some(e.to_string()),
+        }
+    }
+
+    /// Deletes the host waiting for confirmation from its file.
+    pub fn confirm_delete(&mut self) {
+        let Some(host) = self.pending_delete.take() else {
+            return;
+        };
+        let Some(item) = self.scs.find_item(&host) else {
+            return;
+        };
+
+        match host_editor::delete_host(item) {
+            Ok(()) => self.reload_after_edit(&host, format!("Deleted {}", host)),
+            Err(e) => self.status_message = Some(format!("Failed to delete {}: {}", host, e)),
+        }
+    }
+
+    fn reload_after_edit(&mut self, host: &str, message: String) {
+        self.status_message = Some(match self.reload() {
+            Ok(()) => {
+                self.select_item_by_name(host);
+                self.with_store_warnings(message)
+            }
+            Err(e) => format!("Failed to reload the SSH config: {}", e),
+        });
+    }
+
+    /// Runs the launch against the host through the launcher, recording it
+    /// in the database.
+    pub fn run_launch(&mut self, host: &SshGroupItem, launch: Launch) -> Result<LaunchOutcome> {
+        let outcome = launcher::launch_and_record(self.launcher.as_mut(), &self.db, host, launch);
+        self.refresh_history();
+        outcome
+    }
+
+    /// History of the selected host, or of every host when none is selected.
+    /// It is only read from the database again when the selection changed or
+    /// after `refresh_history`.
+    pub fn history(&mut self) -> &[HistoryEntry] {
+        let host = self.get_selected_item().map(|item| item.full_name.clone());
+
+        if self.history.as_ref().is_none_or(|(loaded_host, _)| *loaded_host != host) {
+            let filter = HistoryFilter {
+                host: host.clone(),
+                ..Default::default()
+            };
+            let entries = self.db.get_history(&filter).unwrap_or_default();
+            self.history = Some((host, entries));
+        }
+
+        self.history.as_ref().map_or(&[], |(_, entries)| entries)
+    }
+
+    /// Reads the history again on the next draw, e.g. after a launch.
+    #[inline]
+    pub fn refresh_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Clears the launch requests once the command has been run.
+    #[inline]
+    pub fn reset_launch_flags(&mut self) {
+        self.should_quit = false;
+        self.launch = None;
+        self.pending_keys.clear();
+    }
+
+    /// Directory of the database, where the other files of Fast-SSH go.
+    fn data_dir(db_path: Option<&Path>) -> PathBuf {
+        let data_dir = match db_path {
+            Some(db_path) => db_path.parent().map(Path::to_path_buf),
+            None => dirs::config_dir().map(|config_dir| config_dir.join("FastSSH")),
+        };
+
+        data_dir.unwrap_or_else(std::env::temp_dir)
+    }
+
+    /// Opens the tunnels panel on the first tunnel of the selected host.
+    pub fn open_tunnels_panel(&mut self) {
+        let host = self.get_selected_item().map(|item| item.full_name.as_str());
+        let tunnels = &get_config().tunnels;
+        let selected = tunnels
+            .iter()
+            .position(|tunnel| Some(tunnel.host.as_str()) == host)
+            .or((!tunnels.is_empty()).then_some(0));
+
+        let mut state = TableState::default();
+        state.select(selected);
+        self.tunnels_panel = Some(state);
+    }
+
+    #[inline]
+    pub fn change_selected_tunnel(&mut self, forward: bool) {
+        let len = get_config().tunnels.len();
+        let Some(state) = &mut self.tunnels_panel else {
+            return;
+        };
+        if len == 0 {
+            return;
+        }
+
+        let idx = state.selected().unwrap_or(0);
+        state.select(Some(match forward {
+            true => (idx + 1) % len,
+            false => (idx + len - 1) % len,
+        }));
+    }
+
+    /// Starts the tunnel selected in the panel, or stops it if it runs.
+    pub fn toggle_selected_tunnel(&mut self) {
+        let Some(idx) = self.tunnels_panel.as_ref().and_then(TableState::selected) else {
+            return;
+        };
+        let config = get_config();
+        let Some(tunnel) = config.tunnels.get(idx) else {
+            return;
+        };
+
+        let result = match self.tunnels.status(tunnel) {
+            TunnelStatus::Running(_) => self
+                .tunnels
+                .stop(tunnel)
+                .map(|()| format!("Stopped {}", tunnel.name)),
+            _ => self
+                .tunnels
+                .start(tunnel, &self.scs, self.ssh_config_path.as_deref())
+                .map(|pid| format!("Started {} (PID {})", tunnel.name, pid)),
+        };
+
+        self.status_message = Some(match result {
+            Ok(message) => message,
+            Err(e) => format!("{:#}", e),
+        });
+    }
+
+    /// Opens the clean-up panel listing the stale database entries.
+    pub fn open_prune_panel(&mut self) {
+        match find_stale_entries(&self.db, &self.scs) {
+            Ok(entries) => {
+                let mut state = TableState::default();
+                state.select((!entries.is_empty()).then_some(0));
+                self.stale_entries = entries;
+                self.prune_panel = Some(state);
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Failed to read the database: {}", e));
+            }
+        }
+    }
+
+    #[inline]
+    pub fn change_selected_stale_entry(&mut self, forward: bool) {
+        let len = self.stale_entries.len();
+        let Some(state) = &mut self.prune_panel else {
+            return;
+        };
+        if len == 0 {
+            return;
+        }
+
+        let idx = state.selected().unwrap_or(0);
+        state.select(Some(match forward {
+            true => (idx + 1) % len,
+            false => (idx + len - 1) % len,
+        }));
+    }
+
+    /// Merges the selected stale entry into its suggested host, or deletes
+    /// it.
+    pub fn resolve_selected_stale_entry(&mut self, rename: bool) {
+        let Some(idx) = self.prune_panel.as_ref().and_then(TableState::selected) else {
+            return;
+        };
+        let Some(entry) = self.stale_entries.get(idx) else {
+            return;
+        };
+        let key = entry.key.clone();
+
+        let result = match (rename, &entry.rename) {
+            (true, Some(new_key)) => self
+                .db
+                .rename_host(&key, new_key)
+                .map(|()| format!("Merged {} into {}", key, new_key)),
+            (true, None) => {
+                self.status_message = Some(format!("No rename found for {}", key));
+                return;
+            }
+            (false, _) => self
+                .db
+                .remove_host(&key)
+                .map(|()| format!("Deleted {} from the database", key)),
+        };
+
+        let message = match result {
+            Ok(message) => message,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to update {}: {}", key, e));
+                return;
+            }
+        };
+        self.status_message = Some(match self.reload() {
+            Ok(()) => self.with_store_warnings(message),
+            Err(e) => format!("Failed to reload the SSH config: {}", e),
+        });
+
+        // Lists the remaining entries, keeping the selection in place
+        self.stale_entries.remove(idx);
+        if let Some(state) = &mut self.prune_panel {
+            let len = self.stale_entries.len();
+            state.select((len > 0).then(|| idx.min(len - 1)));
+        }
+    }
+
+    pub fn create_or_get_db_file(db_path: Option<&Path>) -> Result<FileDatabase> {
+        if let Some(db_path) = db_path {
+            if let Some(parent) = db_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)
+                    .with_context(|| format_err!("Could not create the database directory"))?;
+            }
+
+            let db_path_str = db_path
+                .to_str()
+                .ok_or_else(|| format_err!("Database path contains invalid UTF-8"))?;
+
+            return FileDatabase::new(db_path_str);
+        }
+
         let config_dir =
             dirs::config_dir().ok_or_else(|| format_err!("Could not get config directory"))?;
 
@@ -99,14 +864,11 @@ impl App {
             .collect::<Vec<&SshGroupItem>>()
     }
 
+    /// Every host once, leaving out the Favorites and Recents groups.
     #[inline]
-    pub fn get_all_items_except_recents(&self) -> Vec<&SshGroupItem> {
-        self.scs
-            .groups
-            .iter()
-            .filter(|group| group.name != "Recents")
-            .flat_map(|group| &group.items)
-            .collect::<Vec<&SshGroupItem>>()
+    pub fn get_all_items_except_virtual(&self) -> Vec<&SshGroupItem> {
+        // Parent groups also list the hosts of their subgroups
+        self.scs.items().collect()
     }
 
     pub fn get_items_based_on_mode(&self) -> Vec<&SshGroupItem> {
@@ -122,8 +884,9 @@ impl App {
                     .iter()
                     .collect::<Vec<&SshGroupItem>>();
 
-                if selected_group.name != "Recents" {
-                    group_items.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+                // Favorites and Recents have their own order
+                if !selected_group.is_virtual() {
+                    group_items.sort_by_key(|item| item.name.to_lowercase());
                 }
 
                 group_items
@@ -136,22 +899,58 @@ impl App {
 
     #[inline]
     pub fn change_selected_group(&mut self, rot_right: bool) {
-        let items_len = self.scs.groups.len();
-        
+        let visible_groups = self.visible_groups();
+        let items_len = visible_groups.len();
+
         // Assert preconditions
         debug_assert!(items_len > 0, "groups should never be empty (validated in new())");
-        debug_assert!(self.selected_group < items_len, "selected_group index should be in bounds");
-        
+
         // Guard against empty groups (should never happen in practice due to validation in new())
         if items_len == 0 {
             return;
         }
 
-        let actual_idx = self.selected_group;
-        self.selected_group = match rot_right {
+        // The selected group is always visible, its parents being expanded
+        let actual_idx = visible_groups
+            .iter()
+            .position(|idx| *idx == self.selected_group)
+            .unwrap_or(0);
+        let next_idx = match rot_right {
             true => (actual_idx + 1) % items_len,
             false => (actual_idx + items_len - 1) % items_len,
         };
+        self.selected_group = visible_groups[next_idx];
+    }
+
+    /// Indexes of the groups whose parents are all expanded.
+    pub fn visible_groups(&self) -> Vec<usize> {
+        self.scs
+            .groups
+            .iter()
+            .enumerate()
+            .filter(|(_, group)| {
+                group.is_virtual()
+                    || group
+                        .name
+                        .match_indices('/')
+                        .all(|(pos, _)| self.expanded_groups.contains(&group.name[..pos]))
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Shows or hides the subgroups of the selected group.
+    pub fn toggle_selected_group(&mut self) {
+        if !self.scs.has_subgroups(self.selected_group) {
+            return;
+        }
+        let Some(name) = self.get_selected_group().map(|group| group.name.clone()) else {
+            return;
+        };
+
+        if !self.expanded_groups.remove(&name) {
+            self.expanded_groups.insert(name);
+        }
     }
 
     #[inline]
@@ -179,16 +978,71 @@ impl App {
         self.host_state.select(Some(i));
     }
 
-    #[inline]
-    pub fn select_recents_group(&mut self) {
-        if let Some(first_group) = self.scs.groups.first() {
-            if first_group.name == "Recents" {
-                self.selected_group = 0;
+    /// Selects the group with the given name (case insensitive), returns
+    /// `false` if there is no such group.
+    pub fn select_group_by_name(&mut self, name: &str) -> bool {
+        match self
+            .scs
+            .groups
+            .iter()
+            .position(|group| group.name.eq_ignore_ascii_case(name))
+        {
+            Some(idx) => {
+                // Expand the parents so the group is visible
+                let group_name = &self.scs.groups[idx].name;
+                for (pos, _) in group_name.match_indices('/') {
+                    self.expanded_groups.insert(group_name[..pos].to_string());
+                }
+
+                self.selected_group = idx;
                 self.host_state.select(Some(0));
+                true
             }
+            None => false,
+        }
+    }
+
+    /// Selects the host with the given full name in its group, returns
+    /// `false` if there is no such host.
+    pub fn select_item_by_name(&mut self, full_name: &str) -> bool {
+        let Some(group_name) = self
+            .scs
+            .find_item(full_name)
+            .map(|item| item.group_name().to_string())
+        else {
+            return false;
+        };
+
+        if matches!(self.state, AppState::Normal) {
+            self.select_group_by_name(&group_name);
+        }
+
+        match self
+            .get_items_based_on_mode()
+            .iter()
+            .position(|item| item.full_name == full_name)
+        {
+            Some(idx) => {
+                self.host_state.select(Some(idx));
+                true
+            }
+            None => false,
         }
     }
 
+    #[inline]
+    pub fn select_recents_group(&mut self) {
+        if let Some(idx) = self.scs.groups.iter().position(|g| g.name == RECENTS_GROUP) {
+            self.selected_group = idx;
+            self.host_state.select(Some(0));
+        }
+    }
+
+    #[inline]
+    pub fn select_favorites_group(&mut self) {
+        self.select_group_by_name(FAVORITES_GROUP);
+    }
+
     #[inline]
     pub fn scroll_config_paragraph(&mut self, offset: i64) {
         // Assert invariants and bounds
@@ -202,7 +1056,8 @@ impl App {
     pub fn toggle_config_display_mode(&mut self) {
         self.config_display_mode = match self.config_display_mode {
             ConfigDisplayMode::Global => ConfigDisplayMode::Selected,
-            ConfigDisplayMode::Selected => ConfigDisplayMode::Global,
+            ConfigDisplayMode::Selected => ConfigDisplayMode::Resolved,
+            ConfigDisplayMode::Resolved => ConfigDisplayMode::Global,
         };
     }
 
@@ -259,169 +1114,312 @@ impl App {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::launcher::RecordingLauncher;
 
-    // Helper function to create a mock App for testing
-    // Note: We can't easily test the full App::new() without mocking async/file operations
-    // So we'll test the individual methods that don't require complex setup
+    /// App over an SSH config and a database in memory.
+    pub(crate) fn test_app(ssh_config: &str) -> App {
+        test_app_with_db(ssh_config, FileDatabase::in_memory().unwrap())
+    }
 
-    #[test]
-    fn test_default_half_page_size() {
-        assert_eq!(DEFAULT_HALF_PAGE_SIZE, 10);
+    pub(crate) fn test_app_with_db(ssh_config: &str, db: FileDatabase) -> App {
+        let loaded = SshConfigLoader::new()
+            .unwrap()
+            .parse(Path::new("config"), ssh_config)
+            .unwrap();
+        let scs = SshConfigStore::from_loaded(loaded, &db).unwrap();
+        let data_dir = std::env::temp_dir().join("fast_ssh_tests").join("app");
+
+        let mut app = App::from_store(scs, db, data_dir);
+        app.host_state.select(Some(0));
+        app
     }
 
-    #[test]
-    fn test_change_selected_group_forward() {
-        // This test demonstrates the modular arithmetic for group navigation
-        let total_groups = 5;
-        
-        // Test forward rotation
-        for i in 0..total_groups {
-            let next = (i + 1) % total_groups;
-            assert_eq!(next, if i == total_groups - 1 { 0 } else { i + 1 });
-        }
+    /// Config with `count` hosts in the `prod` group and one in `dev`.
+    fn hosts_config(count: usize) -> String {
+        let mut config: String = (0..count)
+            .map(|idx| format!("Host prod/web{:02}\n    HostName 10.0.0.{}\n\n", idx, idx))
+            .collect();
+        config.push_str("Host dev/api\n    HostName 10.0.1.1\n");
+        config
+    }
+
+    fn selected_name(app: &App) -> String {
+        app.get_selected_item().unwrap().full_name.clone()
     }
 
     #[test]
-    fn test_change_selected_group_backward() {
-        // This test demonstrates the modular arithmetic for group navigation
-        let total_groups = 5;
-        
-        // Test backward rotation
-        for i in 0..total_groups {
-            let prev = (i + total_groups - 1) % total_groups;
-            assert_eq!(prev, if i == 0 { total_groups - 1 } else { i - 1 });
+    fn test_change_selected_group_wraps() {
+        let mut app = test_app(&hosts_config(2));
+        let names = |app: &App| app.get_selected_group().unwrap().name.clone();
+        let groups_len = app.visible_groups().len();
+        let first = names(&app);
+
+        app.change_selected_group(false);
+        assert_eq!(app.selected_group, *app.visible_groups().last().unwrap());
+
+        app.change_selected_group(true);
+        assert_eq!(names(&app), first);
+
+        for _ in 0..groups_len {
+            app.change_selected_group(true);
         }
+        assert_eq!(names(&app), first);
     }
 
     #[test]
-    fn test_change_selected_item_forward() {
-        let items_len = 10;
-        
-        // Test forward navigation
-        for i in 0..items_len {
-            let next = (i + 1) % items_len;
-            assert_eq!(next, if i == items_len - 1 { 0 } else { i + 1 });
-        }
+    fn test_startup_warnings_in_status() {
+        let mut db = FileDatabase::in_memory().unwrap();
+        db.warnings.push("Upgraded the database to version 2".to_string());
+        let app = test_app_with_db(&hosts_config(1), db);
+
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Upgraded the database to version 2")
+        );
+        assert!(test_app(&hosts_config(1)).status_message.is_none());
     }
 
     #[test]
-    fn test_change_selected_item_backward() {
-        let items_len = 10;
-        
-        // Test backward navigation
-        for i in 0..items_len {
-            let prev = (i + items_len - 1) % items_len;
-            assert_eq!(prev, if i == 0 { items_len - 1 } else { i - 1 });
-        }
+    fn test_change_selected_item_wraps() {
+        let mut app = test_app(&hosts_config(3));
+        app.select_group_by_name("prod");
+        assert_eq!(selected_name(&app), "prod/web00");
+
+        app.change_selected_item(false);
+        assert_eq!(selected_name(&app), "prod/web02");
+
+        app.change_selected_item(true);
+        app.change_selected_item(true);
+        assert_eq!(selected_name(&app), "prod/web01");
     }
 
     #[test]
-    fn test_scroll_config_paragraph_positive() {
-        // Test positive scrolling
-        let mut offset: u16 = 0;
-        let new_offset = (offset as i64 + 5).max(0);
-        offset = new_offset.min(u16::MAX as i64) as u16;
-        assert_eq!(offset, 5);
+    fn test_jump_to_first_and_last_item() {
+        let mut app = test_app(&hosts_config(5));
+        app.select_group_by_name("prod");
+
+        app.jump_to_last_item();
+        assert_eq!(selected_name(&app), "prod/web04");
+
+        app.jump_to_first_item();
+        assert_eq!(selected_name(&app), "prod/web00");
     }
 
     #[test]
-    fn test_scroll_config_paragraph_negative() {
-        // Test negative scrolling doesn't go below 0
-        let mut offset: u16 = 3;
-        let new_offset = (offset as i64 - 5).max(0);
-        offset = new_offset.min(u16::MAX as i64) as u16;
-        assert_eq!(offset, 0);
+    fn test_scroll_half_page() {
+        let mut app = test_app(&hosts_config(25));
+        app.select_group_by_name("prod");
+
+        app.scroll_half_page(true);
+        assert_eq!(app.host_state.selected(), Some(DEFAULT_HALF_PAGE_SIZE));
+        app.scroll_half_page(true);
+        app.scroll_half_page(true);
+        // Stops on the last host
+        assert_eq!(selected_name(&app), "prod/web24");
+
+        app.scroll_half_page(false);
+        assert_eq!(app.host_state.selected(), Some(24 - DEFAULT_HALF_PAGE_SIZE));
+        app.scroll_half_page(false);
+        app.scroll_half_page(false);
+        assert_eq!(app.host_state.selected(), Some(0));
     }
 
     #[test]
-    fn test_scroll_config_paragraph_overflow() {
-        // Test that we don't overflow u16::MAX
-        let mut offset: u16 = u16::MAX - 1;
-        let new_offset = (offset as i64 + 10).max(0);
-        offset = new_offset.min(u16::MAX as i64) as u16;
-        assert_eq!(offset, u16::MAX);
+    fn test_scroll_half_page_small_group() {
+        // A group of 3 hosts scrolls one host at a time
+        let mut app = test_app(&hosts_config(3));
+        app.select_group_by_name("prod");
+
+        app.scroll_half_page(true);
+        assert_eq!(selected_name(&app), "prod/web01");
     }
 
     #[test]
-    fn test_scroll_half_page_bounds() {
-        let items_len = 100;
-        let half_page = DEFAULT_HALF_PAGE_SIZE.min(items_len / 2).max(1);
-        
-        // Test half page calculation
-        assert_eq!(half_page, 10);
-        
-        // Test scroll down doesn't exceed bounds
-        let current = 95;
-        let new_pos = (current + half_page).min(items_len - 1);
-        assert_eq!(new_pos, 99); // Should stop at last item
+    fn test_scroll_config_paragraph() {
+        let mut app = test_app(&hosts_config(1));
+
+        app.scroll_config_paragraph(5);
+        assert_eq!(app.config_paragraph_offset, 5);
+
+        app.scroll_config_paragraph(-8);
+        assert_eq!(app.config_paragraph_offset, 0);
+
+        app.config_paragraph_offset = u16::MAX - 1;
+        app.scroll_config_paragraph(10);
+        assert_eq!(app.config_paragraph_offset, u16::MAX);
     }
 
     #[test]
-    fn test_scroll_half_page_saturating_sub() {
-        let half_page = DEFAULT_HALF_PAGE_SIZE;
-        
-        // Test scroll up with saturating subtraction
-        let current: usize = 5;
-        let new_pos = current.saturating_sub(half_page);
-        assert_eq!(new_pos, 0); // Should stop at 0
-        
-        // Test normal scroll up
-        let current: usize = 20;
-        let new_pos = current.saturating_sub(half_page);
-        assert_eq!(new_pos, 10);
+    fn test_selection_follows_search() {
+        let mut app = test_app(&hosts_config(3));
+        app.state = AppState::Searching;
+        app.searcher.set_search("api");
+
+        assert_eq!(app.get_items_based_on_mode().len(), 1);
+        assert_eq!(selected_name(&app), "dev/api");
     }
 
     #[test]
-    fn test_app_state_enum() {
-        // Test that AppState enum variants exist
-        let _normal = AppState::Normal;
-        let _searching = AppState::Searching;
+    fn test_toggle_config_display_mode() {
+        let mut app = test_app(&hosts_config(1));
+        assert!(matches!(app.config_display_mode, ConfigDisplayMode::Selected));
+
+        app.toggle_config_display_mode();
+        assert!(matches!(app.config_display_mode, ConfigDisplayMode::Resolved));
+        app.toggle_config_display_mode();
+        assert!(matches!(app.config_display_mode, ConfigDisplayMode::Global));
+        app.toggle_config_display_mode();
+        assert!(matches!(app.config_display_mode, ConfigDisplayMode::Selected));
     }
 
     #[test]
-    fn test_config_display_mode_enum() {
-        // Test that ConfigDisplayMode enum variants exist
-        let _global = ConfigDisplayMode::Global;
-        let _selected = ConfigDisplayMode::Selected;
+    fn test_history_is_cached() {
+        let mut app = test_app("Host web\n    HostName 10.0.0.1\n");
+        assert!(app.history().is_empty());
+
+        let entry = HistoryEntry {
+            host_key: "web".to_string(),
+            action: "ssh".to_string(),
+            started_at: 100,
+            ended_at: 160,
+            exit_code: Some(0),
+            command_line: "ssh web".to_string(),
+        };
+        app.db.add_history_entry(entry.clone()).unwrap();
+
+        // Not read from the database on every draw
+        assert!(app.history().is_empty());
+
+        app.refresh_history();
+        assert_eq!(app.history(), [entry]);
     }
 
     #[test]
-    fn test_jump_to_first_item_logic() {
-        // Test the logic for jumping to first item
-        let items_len = 50;
-        if items_len > 0 {
-            let selected = Some(0);
-            assert_eq!(selected, Some(0));
+    fn test_move_favorite_twice() {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("app_move_favorite_{}", std::process::id()));
+        fs::create_dir_all(&test_dir).unwrap();
+        let config_path = test_dir.join("config");
+        let ssh_config = "Host prod/web\n    HostName 10.0.0.1\n\nHost prod/db\n    HostName 10.0.0.2\n\nHost dev/api\n    HostName 10.0.0.3\n";
+        fs::write(&config_path, ssh_config).unwrap();
+
+        let db = FileDatabase::in_memory().unwrap();
+        for host in ["prod/web", "prod/db", "dev/api"] {
+            db.toggle_favorite(host, None).unwrap();
         }
+        let mut app = test_app_with_db(ssh_config, db);
+        app.ssh_config_path = Some(config_path);
+        app.select_favorites_group();
+        app.jump_to_last_item();
+
+        let favorites = |app: &App| -> Vec<String> {
+            let items = app.get_items_based_on_mode();
+            items.iter().map(|item| item.full_name.clone()).collect()
+        };
+        assert_eq!(favorites(&app), ["prod/web", "prod/db", "dev/api"]);
+
+        app.move_favorite(true);
+        app.move_favorite(true);
+
+        assert_eq!(favorites(&app), ["dev/api", "prod/web", "prod/db"]);
+        assert_eq!(app.get_selected_group().unwrap().name, FAVORITES_GROUP);
+        assert_eq!(app.get_selected_item().unwrap().full_name, "dev/api");
+        assert_eq!(app.host_state.selected(), Some(0));
+
+        let _ = fs::remove_dir_all(&test_dir);
     }
 
-    #[test]
-    fn test_jump_to_last_item_logic() {
-        // Test the logic for jumping to last item
-        let items_len = 50;
-        if items_len > 0 {
-            let selected = Some(items_len - 1);
-            assert_eq!(selected, Some(49));
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_export_run_results_next_to_database() {
+        let mut app = test_app("Host web\n    HostName 10.0.0.1\n");
+        app.runs_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("app_runs_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&app.runs_dir);
+
+        let mut process = tokio::process::Command::new("sh");
+        process.arg("-c").arg("echo up");
+        let run = ParallelRun::start("uptime", vec![("web".to_string(), process)], 1).unwrap();
+        let path = app.runs_dir.join(format!("fast-ssh-run-{}.log", run.started_at));
+        app.parallel_run = Some(run);
+
+        app.export_run_results();
+
+        assert!(path.is_absolute());
+        assert!(fs::read_to_string(&path).unwrap().starts_with("$ uptime"));
+        assert_eq!(
+            app.status_message,
+            Some(format!("Results exported to {}", path.display()))
+        );
+
+        let _ = fs::remove_dir_all(&app.runs_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_new_included_file_is_loaded() {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("app_include_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(test_dir.join("config.d")).unwrap();
+        let config = test_dir.join("config");
+        let contents = format!(
+            "Include {}/*\n\nHost web\n    HostName 10.0.0.1\n",
+            test_dir.join("config.d").display()
+        );
+        fs::write(&config, contents).unwrap();
+
+        let loaded = SshConfigLoader::new().unwrap().load(&config).unwrap();
+        let db = FileDatabase::in_memory().unwrap();
+        let scs = SshConfigStore::from_loaded(loaded, &db).unwrap();
+        let mut app = App::from_store(scs, db, test_dir.clone());
+        app.watcher = FileWatcher::new(app.watched_files());
+
+        // Older file systems only keep the modification time in seconds
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        fs::write(test_dir.join("config.d/db"), "Host db\n    HostName 10.0.0.2\n").unwrap();
+
+        for _ in 0..50 {
+            app.check_file_changes();
+            if app.scs.find_item("db").is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         }
+        assert!(app.scs.find_item("db").is_some());
+        assert_eq!(app.status_message.as_deref(), Some("SSH config reloaded"));
+
+        let _ = fs::remove_dir_all(&test_dir);
     }
 
     #[test]
-    fn test_half_page_calculation_edge_cases() {
-        // Test with very small list
-        let items_len = 3;
-        let half_page = DEFAULT_HALF_PAGE_SIZE.min(items_len / 2).max(1);
-        assert_eq!(half_page, 1); // Should be at least 1
-        
-        // Test with empty list
-        let items_len = 0;
-        let half_page = DEFAULT_HALF_PAGE_SIZE.min(items_len / 2).max(1);
-        assert_eq!(half_page, 1); // Should be at least 1
-        
-        // Test with large list
-        let items_len = 1000;
-        let half_page = DEFAULT_HALF_PAGE_SIZE.min(items_len / 2).max(1);
-        assert_eq!(half_page, 10); // Should use DEFAULT_HALF_PAGE_SIZE
+    fn test_run_launch_through_launcher() {
+        let mut app = test_app("Host web \"web server\"\n    HostName 10.0.0.1\n");
+        app.launcher = Box::new(RecordingLauncher {
+            exit_code: Some(0),
+            ..Default::default()
+        });
+        let item = app.get_selected_item().unwrap().clone();
+
+        let outcome = app.run_launch(&item, Launch::CopyFiles).unwrap();
+        assert_eq!(outcome.exit_code, Some(0));
+        assert_eq!(outcome.command_line, "sftp web");
+
+        // Counted and shown in the history panel without reading the files
+        assert_eq!(app.db.get_host_values(&item.full_name).unwrap().connection_count, 1);
+        let history = app.history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].action, "sftp");
+
+        app.launcher = Box::new(RecordingLauncher {
+            fail: true,
+            ..Default::default()
+        });
+        assert!(app.run_launch(&item, Launch::Ssh).is_err());
+        assert_eq!(app.history().len(), 1);
     }
 }
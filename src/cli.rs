@@ -0,0 +1,210 @@
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::{
+    app::AppOptions,
+    host_listing::ListFormat,
+    pick::{PickField, Shell},
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// SSH config file to read [default: ~/.ssh/config]
+    #[arg(long, value_name = "SSH_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Database file storing connection counts [default: <config dir>/FastSSH/db.ron]
+    #[arg(long, value_name = "PATH", global = true)]
+    pub db: Option<PathBuf>,
+
+    /// FastSSH configuration file [default: <config dir>/FastSSH/config.yaml]
+    #[arg(long, value_name = "YAML", global = true)]
+    pub app_config: Option<PathBuf>,
+
+    /// Open the TUI on the given group
+    #[arg(long, value_name = "NAME")]
+    pub group: Option<String>,
+
+    /// Open the TUI in search mode with the given query
+    #[arg(long, value_name = "QUERY")]
+    pub search: Option<String>,
+
+    /// Come back to the TUI once the launched command exits
+    #[arg(long)]
+    pub stay_open: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print every host of the SSH config
+    List {
+        #[arg(long, value_enum, default_value_t)]
+        format: ListFormat,
+        /// Only list the hosts matching this search, as typed in the TUI
+        /// (e.g. `group:prod user:deploy web`)
+        query: Vec<String>,
+    },
+    /// Connect to a host without opening the TUI
+    Connect {
+        /// Full host name, as written in the SSH config (e.g. `Group/Server`)
+        host: String,
+    },
+    /// Print connection statistics
+    Stats,
+    /// Print the connection history, most recent first
+    History {
+        /// Only show launches of this host
+        #[arg(long)]
+        host: Option<String>,
+        /// Only show launches of hosts in this group
+        #[arg(long)]
+        group: Option<String>,
+        /// Only show launches from this day on (YYYY-MM-DD, in UTC)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        since: Option<NaiveDate>,
+        /// Only show launches up to this day, included (YYYY-MM-DD, in UTC)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        until: Option<NaiveDate>,
+    },
+    /// Pick a host in the TUI, drawn on the terminal, and print it to stdout
+    Pick {
+        /// What to print for the picked host
+        #[arg(long, value_enum, default_value_t)]
+        print: PickField,
+    },
+    /// Print the key binding inserting a picked host, to load in the shell
+    Init {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// List the database entries of hosts no longer in the SSH config
+    Prune {
+        /// Merge each entry into the host it was likely renamed to
+        #[arg(long)]
+        rename: bool,
+        /// Delete the entries that are not merged into a renamed host
+        #[arg(long)]
+        delete: bool,
+    },
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date `{}`, expected YYYY-MM-DD", value))
+}
+
+impl Cli {
+    pub fn app_options(&self) -> AppOptions {
+        AppOptions {
+            ssh_config_path: self.config.clone(),
+            db_path: self.db.clone(),
+            app_config_path: self.app_config.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_tui_flags() {
+        let cli = Cli::parse_from([
+            "fast-ssh",
+            "--config",
+            "/tmp/ssh_config",
+            "--group",
+            "Production",
+            "--search",
+            "web",
+        ]);
+
+        assert_eq!(cli.config, Some(PathBuf::from("/tmp/ssh_config")));
+        assert_eq!(cli.group.as_deref(), Some("Production"));
+        assert_eq!(cli.search.as_deref(), Some("web"));
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn test_parse_connect_with_global_flags() {
+        let cli = Cli::parse_from(["fast-ssh", "connect", "Group/Server", "--db", "/tmp/db.ron"]);
+
+        assert_eq!(cli.db, Some(PathBuf::from("/tmp/db.ron")));
+        assert!(matches!(cli.command, Some(Command::Connect { host }) if host == "Group/Server"));
+    }
+
+    #[test]
+    fn test_parse_history_filters() {
+        let cli = Cli::parse_from([
+            "fast-ssh",
+            "history",
+            "--group",
+            "prod",
+            "--since",
+            "2024-05-01",
+        ]);
+
+        let Some(Command::History {
+            group,
+            since,
+            until,
+            ..
+        }) = cli.command
+        else {
+            panic!("expected the history subcommand");
+        };
+        assert_eq!(group.as_deref(), Some("prod"));
+        assert_eq!(since, NaiveDate::from_ymd_opt(2024, 5, 1));
+        assert_eq!(until, None);
+
+        assert!(Cli::try_parse_from(["fast-ssh", "history", "--since", "yesterday"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let cli = Cli::parse_from([
+            "fast-ssh",
+            "list",
+            "--format",
+            "ndjson",
+            "group:prod",
+            "web",
+        ]);
+
+        let Some(Command::List { format, query }) = cli.command else {
+            panic!("expected the list subcommand");
+        };
+        assert_eq!(format, ListFormat::Ndjson);
+        assert_eq!(query, vec!["group:prod", "web"]);
+    }
+
+    #[test]
+    fn test_parse_pick() {
+        let cli = Cli::parse_from(["fast-ssh", "--search", "web", "pick", "--print", "target"]);
+
+        assert_eq!(cli.search.as_deref(), Some("web"));
+        assert!(matches!(
+            cli.command,
+            Some(Command::Pick {
+                print: PickField::Target
+            })
+        ));
+        assert!(matches!(
+            Cli::parse_from(["fast-ssh", "pick"]).command,
+            Some(Command::Pick {
+                print: PickField::Name
+            })
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::actions::CustomAction;
+use crate::keybindings::{Action, KeyBindings, KeyContext};
+
+/// An action that can be run from the palette.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaletteEntry {
+    Action(Action),
+    /// Index of the action in the `actions` section of the config
+    Custom(usize),
+}
+
+impl PaletteEntry {
+    pub fn label<'a>(&self, actions: &'a [CustomAction]) -> &'a str {
+        match self {
+            PaletteEntry::Action(action) => action.description(),
+            PaletteEntry::Custom(idx) => actions.get(*idx).map_or("", |action| &action.name),
+        }
+    }
+
+    pub fn keys(&self, bindings: &KeyBindings) -> String {
+        match self {
+            PaletteEntry::Action(action) => bindings.describe(*action),
+            PaletteEntry::Custom(idx) => bindings
+                .custom_key(*idx)
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Filterable list of every action of the hosts list.
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    pub filter: String,
+    pub selected: usize,
+}
+
+impl CommandPalette {
+    /// Entries whose label contains the filter, custom actions last.
+    pub fn entries(&self, actions: &[CustomAction]) -> Vec<PaletteEntry> {
+        let filter = self.filter.to_lowercase();
+
+        Action::ALL
+            .iter()
+            .filter(|action| {
+                action.contexts().contains(&KeyContext::Normal)
+                    && **action != Action::CommandPalette
+            })
+            .map(|action| PaletteEntry::Action(*action))
+            .chain((0..actions.len()).map(PaletteEntry::Custom))
+            .filter(|entry| entry.label(actions).to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    pub fn selected_entry(&self, actions: &[CustomAction]) -> Option<PaletteEntry> {
+        self.entries(actions).get(self.selected).copied()
+    }
+
+    #[inline]
+    pub fn add_char(&mut self, c: char) {
+        self.filter.push(c);
+        self.selected = 0;
+    }
+
+    #[inline]
+    pub fn del_char(&mut self) {
+        self.filter.pop();
+        self.selected = 0;
+    }
+
+    #[inline]
+    pub fn change_selected(&mut self, actions: &[CustomAction], forward: bool) {
+        let len = self.entries(actions).len();
+        if len == 0 {
+            return;
+        }
+
+        self.selected = match forward {
+            true => (self.selected + 1) % len,
+            false => (self.selected + len - 1) % len,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions() -> Vec<CustomAction> {
+        vec![CustomAction {
+            name: "mosh".to_string(),
+            key: Some("m".to_string()),
+            command: "mosh {alias}".to_string(),
+            interactive: true,
+            return_to_tui: false,
+        }]
+    }
+
+    #[test]
+    fn test_entries_filter() {
+        let actions = actions();
+        let mut palette = CommandPalette::default();
+
+        let entries = palette.entries(&actions);
+        assert!(entries.contains(&PaletteEntry::Action(Action::Connect)));
+        assert!(!entries.contains(&PaletteEntry::Action(Action::CommandPalette)));
+        assert!(!entries.contains(&PaletteEntry::Action(Action::NextMatch)));
+        assert_eq!(entries.last(), Some(&PaletteEntry::Custom(0)));
+
+        for c in "HOST".chars() {
+            palette.add_char(c);
+        }
+        assert_eq!(
+            palette.entries(&actions),
+            vec![
+                PaletteEntry::Action(Action::NextHost),
+                PaletteEntry::Action(Action::PreviousHost),
+                PaletteEntry::Action(Action::FirstHost),
+                PaletteEntry::Action(Action::LastHost),
+                PaletteEntry::Action(Action::AddHost),
+                PaletteEntry::Action(Action::EditHost),
+                PaletteEntry::Action(Action::DuplicateHost),
+                PaletteEntry::Action(Action::DeleteHost),
+            ]
+        );
+
+        palette.filter = "mos".to_string();
+        assert_eq!(
+            palette.selected_entry(&actions),
+            Some(PaletteEntry::Custom(0))
+        );
+    }
+
+    #[test]
+    fn test_change_selected_wraps() {
+        let actions = actions();
+        let mut palette = CommandPalette {
+            filter: "host".to_string(),
+            selected: 0,
+        };
+
+        palette.change_selected(&actions, false);
+        assert_eq!(palette.selected, 7);
+        palette.change_selected(&actions, true);
+        assert_eq!(palette.selected, 0);
+
+        palette.filter = "nothing matches".to_string();
+        palette.change_selected(&actions, true);
+        assert_eq!(palette.selected_entry(&actions), None);
+    }
+}
@@ -0,0 +1,251 @@
+use chrono::NaiveDate;
+use std::io::{self, Write};
+use fast_ssh::{
+    database::HistoryFilter, rank_items, stale_entries::find_stale_entries, Query, SshGroupItem,
+};
+
+use crate::{
+    app::{App, Launch},
+    cli::Command,
+    host_listing::{format_hosts, ListFormat},
+    widgets::{history_widget::HistoryWidget, hosts_widget::HostsWidget},
+};
+
+// Number of hosts shown in the "Most used" section of `stats`
+const STATS_TOP_HOSTS: usize = 10;
+
+/// Runs a subcommand without starting the TUI, returns the exit code of the
+/// program.
+pub fn run(app: &mut App, command: &Command) -> Result<i32, Box<dyn std::error::Error>> {
+    for warning in app.db.warnings.iter().chain(&app.scs.warnings) {
+        eprintln!("Warning: {}", warning);
+    }
+
+    match command {
+        Command::List { format, query } => {
+            list(app, *format, &query.join(" "), &mut io::stdout())
+        }
+        Command::Connect { host } => connect(app, host),
+        Command::Stats => stats(app, &mut io::stdout()).map(|()| 0),
+        Command::History {
+            host,
+            group,
+            since,
+            until,
+        } => history(
+            app,
+            &HistoryFilter {
+                host: host.clone(),
+                group: group.clone(),
+                since: since.map(start_of_day),
+                until: until.and_then(|day| day.succ_opt()).map(|day| start_of_day(day) - 1),
+            },
+        )
+        .map(|()| 0),
+        Command::Prune { rename, delete } => prune(app, *rename, *delete).map(|()| 0),
+        // Handled before, they draw the TUI or need no SSH config
+        Command::Pick { .. } | Command::Init { .. } => Ok(0),
+    }
+}
+
+fn list(
+    app: &App,
+    format: ListFormat,
+    query: &str,
+    out: &mut dyn Write,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let query = Query::parse(query);
+    if !query.errors.is_empty() {
+        eprintln!("Error: {}", query.errors.join(", "));
+        return Ok(1);
+    }
+
+    // Same hosts as the TUI search, in the order of the config without text
+    let now = chrono::offset::Local::now().timestamp();
+    let items: Vec<&SshGroupItem> = rank_items(&query, app.get_all_items_except_virtual(), now)
+        .into_iter()
+        .map(|result| result.item)
+        .collect();
+
+    write!(out, "{}", format_hosts(&items, format)?)?;
+    Ok(0)
+}
+
+/// Returns the exit code of ssh, 1 if it was terminated by a signal.
+fn connect(app: &mut App, host: &str) -> Result<i32, Box<dyn std::error::Error>> {
+    let Some(item) = app.scs.find_item(host).cloned() else {
+        eprintln!("Error: Unknown host '{}'", host);
+        return Ok(1);
+    };
+
+    let outcome = app.run_launch(&item, Launch::Ssh)?;
+    Ok(outcome.exit_code.unwrap_or(1))
+}
+
+fn stats(app: &App, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+    let mut items = app.get_all_items_except_virtual();
+    let groups = app.scs.groups.iter().filter(|g| !g.is_virtual()).count();
+    let connections: i64 = items.iter().map(|item| item.connection_count).sum();
+
+    writeln!(out, "Hosts:       {}", items.len())?;
+    writeln!(out, "Groups:      {}", groups)?;
+    writeln!(out, "Connections: {}", connections)?;
+
+    items.retain(|item| item.connection_count > 0);
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    items.sort_by_key(|item| std::cmp::Reverse(item.connection_count));
+    items.truncate(STATS_TOP_HOSTS);
+
+    let width = items.iter().map(|item| item.full_name.len()).max().unwrap_or(0);
+
+    writeln!(out)?;
+    writeln!(out, "Most used:")?;
+    for item in items {
+        writeln!(
+            out,
+            "  {:>5}  {:<width$}  {}",
+            item.connection_count,
+            item.full_name,
+            HostsWidget::format_last_used_date(item),
+            width = width
+        )?;
+    }
+
+    Ok(())
+}
+
+fn history(app: &App, filter: &HistoryFilter) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = app.db.get_history(filter)?;
+    let width = entries.iter().map(|e| e.host_key.len()).max().unwrap_or(0);
+
+    for entry in entries {
+        println!(
+            "{}  {:<width$}  {:<11}  {:>7}  {:>6}  {}",
+            HostsWidget::format_timestamp(entry.started_at),
+            entry.host_key,
+            entry.action,
+            HistoryWidget::format_duration(entry.duration()),
+            HistoryWidget::format_exit_code(entry.exit_code),
+            entry.command_line,
+            width = width
+        );
+    }
+
+    Ok(())
+}
+
+fn prune(app: &App, rename: bool, delete: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = find_stale_entries(&app.db, &app.scs)?;
+    if entries.is_empty() {
+        println!("Every entry of the database matches a host");
+        return Ok(());
+    }
+
+    let width = entries.iter().map(|entry| entry.key.len()).max().unwrap_or(0);
+
+    for entry in &entries {
+        let renamed = match (&entry.rename, rename) {
+            (Some(new_key), true) => {
+                app.db.rename_host(&entry.key, new_key)?;
+                format!("merged into {}", new_key)
+            }
+            (Some(new_key), false) if !delete && entry.merges_existing => {
+                format!("renamed to {} ? (merged with its own entry)", new_key)
+            }
+            (Some(new_key), false) if !delete => format!("renamed to {} ?", new_key),
+            _ if delete => {
+                app.db.remove_host(&entry.key)?;
+                "deleted".to_string()
+            }
+            _ => String::new(),
+        };
+
+        println!(
+            "{:<width$}  {:>5} connections  {:>5} launches  {}  {}",
+            entry.key,
+            entry.connection_count,
+            entry.launches,
+            HostsWidget::format_timestamp(entry.last_used_date),
+            renamed,
+            width = width
+        );
+    }
+
+    if !rename && !delete {
+        println!();
+        println!("Merge them into their renamed host with --rename, delete them with --delete");
+    }
+
+    Ok(())
+}
+
+/// Days are in UTC, like the printed times.
+fn start_of_day(day: NaiveDate) -> i64 {
+    day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::{test_app, test_app_with_db};
+    use fast_ssh::FileDatabase;
+
+    // A host nested two levels deep is also listed by its parent group
+    const SSH_CONFIG: &str = "\
+Host client/prod/web1
+    HostName 10.0.0.1
+
+Host client/api
+    HostName 10.0.0.2
+
+Host solo
+    HostName 10.0.0.3
+";
+
+    fn run_to_string(run: impl FnOnce(&mut Vec<u8>)) -> String {
+        let mut out = Vec::new();
+        run(&mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_list_nested_hosts_once() {
+        let app = test_app(SSH_CONFIG);
+
+        let output = run_to_string(|out| {
+            assert_eq!(list(&app, ListFormat::Ndjson, "", out).unwrap(), 0);
+        });
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+
+        let web1 = lines
+            .iter()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .find(|host| host["full_name"] == "client/prod/web1")
+            .unwrap();
+        assert_eq!(web1["group"], "client/prod");
+        assert_eq!(web1["alias"], "web1");
+    }
+
+    #[test]
+    fn test_stats_nested_hosts_once() {
+        let db = FileDatabase::in_memory().unwrap();
+        db.save_host_values("client/prod/web1", 3, 1_700_000_000).unwrap();
+        db.save_host_values("solo", 1, 1_700_000_000).unwrap();
+        let app = test_app_with_db(SSH_CONFIG, db);
+
+        let output = run_to_string(|out| stats(&app, out).unwrap());
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Hosts:       3");
+        assert_eq!(lines[2], "Connections: 4");
+        assert_eq!(
+            output.matches("client/prod/web1").count(),
+            1,
+            "most used hosts: {}",
+            output
+        );
+    }
+}
@@ -1,27 +1,72 @@
+use crate::actions::{validate_actions, CustomAction};
+use crate::keybindings::KeyBindings;
+use crate::parallel_run::ParallelRunConfig;
+use crate::reachability::ReachabilityConfig;
+use crate::tunnels::{validate_tunnels, TunnelConfig};
 use crate::Theme;
 use anyhow::Result;
 use serde::Deserialize;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct Config {
     pub theme: Theme,
+    /// Come back to the TUI once the launched command exits
+    #[serde(default)]
+    pub stay_open: bool,
+    /// Keys of each action, replacing the defaults of the listed actions
+    #[serde(default)]
+    pub keybindings: KeyBindings,
+    /// Commands that can be run against the selected host
+    #[serde(default)]
+    pub actions: Vec<CustomAction>,
+    /// Background checks of the hosts SSH port
+    #[serde(default)]
+    pub reachability: ReachabilityConfig,
+    /// Commands run on several hosts at once
+    #[serde(default)]
+    pub parallel_run: ParallelRunConfig,
+    /// Port forwardings that can be started from the tunnels panel
+    #[serde(default)]
+    pub tunnels: Vec<TunnelConfig>,
 }
 
-pub fn resolve_config() -> Config {
-    match parse_user_config() {
+impl Config {
+    /// Checks the custom actions and tunnels, and binds the keys of the
+    /// actions.
+    fn validate(&mut self) -> Result<()> {
+        let mut errors = validate_actions(&self.actions);
+        errors.extend(self.keybindings.add_custom_actions(&self.actions));
+
+        if !errors.is_empty() {
+            return Err(anyhow::anyhow!("Invalid actions: {}", errors.join(", ")));
+        }
+
+        let errors = validate_tunnels(&self.tunnels);
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(anyhow::anyhow!("Invalid tunnels: {}", errors.join(", "))),
+        }
+    }
+}
+
+pub fn resolve_config(config_file: Option<&Path>) -> Config {
+    match parse_user_config(config_file) {
         Ok(config) => config,
         Err(e) => {
             eprintln!("Warning: Failed to load user config: {}", e);
             eprintln!("Using default theme configuration");
-            Config {
-                theme: Theme::default(),
-            }
+            Config::default()
         }
     }
 }
 
-fn parse_user_config() -> Result<Config> {
+fn parse_user_config(config_file: Option<&Path>) -> Result<Config> {
+    if let Some(config_file) = config_file {
+        return parse_config_file(config_file);
+    }
+
     if let Some(config_dir) = dirs::config_dir() {
         let conf_path = config_dir.join("FastSSH");
         let config_file = conf_path.join("config.yaml");
@@ -32,25 +77,35 @@ fn parse_user_config() -> Result<Config> {
             fs::write(&config_file, DEFAULT_CONFIG)?;
         }
 
-        let config_file_text = fs::read_to_string(&config_file)?;
+        return parse_config_file(&config_file);
+    }
 
-        let conf = match serde_yaml::from_str::<Option<Config>>(&config_file_text) {
-            Ok(Some(conf)) => conf,
-            Ok(None) => {
-                return Err(anyhow::anyhow!("Config file is empty or invalid"));
-            }
-            Err(e) => {
-                return Err(anyhow::anyhow!(
-                    "Error parsing config file, make sure format is valid: {}",
-                    e
-                ));
-            }
-        };
+    Err(anyhow::anyhow!("Could not get config directory"))
+}
 
-        return Ok(conf);
+/// Path of the configuration file: the given one, or the default location.
+pub fn config_file_path(config_file: Option<&Path>) -> Option<PathBuf> {
+    match config_file {
+        Some(config_file) => Some(config_file.to_path_buf()),
+        None => dirs::config_dir().map(|config_dir| config_dir.join("FastSSH").join("config.yaml")),
     }
+}
 
-    Err(anyhow::anyhow!("Could not get config directory"))
+pub fn parse_config_file(config_file: &Path) -> Result<Config> {
+    let config_file_text = fs::read_to_string(config_file)
+        .map_err(|e| anyhow::anyhow!("Could not read {}: {}", config_file.display(), e))?;
+
+    match serde_yaml::from_str::<Option<Config>>(&config_file_text) {
+        Ok(Some(mut conf)) => {
+            conf.validate()?;
+            Ok(conf)
+        }
+        Ok(None) => Err(anyhow::anyhow!("Config file is empty or invalid")),
+        Err(e) => Err(anyhow::anyhow!(
+            "Error parsing config file, make sure format is valid: {}",
+            e
+        )),
+    }
 }
 
 const DEFAULT_CONFIG: &str = "
@@ -60,16 +115,61 @@ theme:
     text_primary: \"#b967ff\"
     text_secondary: \"#ffffff\"
     border_color: \"#b967ff\"
+
+# Come back to FastSSH once the SSH session ends instead of exiting.
+stay_open: false
+
+# Keys of each action, see the README for the list of actions.
+# keybindings:
+#     copy_ssh_key: ctrl+k
+#     search: [\"s\", \"/\"]
+
+# Commands that can be run against the selected host, from their key or
+# from the command palette (':'). Placeholders: {alias}, {hostname}, {user},
+# {port} and {group}.
+# actions:
+#     - name: mosh
+#       key: m
+#       command: mosh {alias}
+#     - name: deploy
+#       command: rsync -av . {alias}:/srv
+#       interactive: false
+
+# Check in the background whether the SSH port of the listed hosts is open.
+# Hosts reached through a ProxyJump or a ProxyCommand are not checked.
+reachability:
+    enabled: true
+    timeout_ms: 1000
+    cache_ttl_secs: 60
+    concurrency: 8
+
+# Commands run on the selected hosts ('!'), over ssh without a terminal.
+parallel_run:
+    concurrency: 8
+    connect_timeout_secs: 10
+
+# Port forwardings started in the background from the tunnels panel ('T').
+# kind is local (-L, the default), remote (-R) or dynamic (-D).
+# tunnels:
+#     - name: prod-db
+#       host: prod/db
+#       port: 5433
+#       target: localhost:5432
+#     - name: socks
+#       host: bastion
+#       kind: dynamic
+#       port: 1080
 ";
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::keybindings::Action;
 
     #[test]
     fn test_resolve_config_returns_default_on_error() {
         // Even if config file doesn't exist or is invalid, should return default
-        let config = resolve_config();
+        let config = resolve_config(None);
         // Just verify it doesn't panic and returns a Config
         let _ = config.theme.text_primary();
     }
@@ -113,6 +213,54 @@ mod tests {
         assert_eq!(config.theme.border_color(), tui::style::Color::Magenta);
     }
 
+    #[test]
+    fn test_stay_open_defaults_to_false() {
+        let yaml = "theme:\n    text_primary: \"#ff0000\"";
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse config");
+        assert!(!config.stay_open);
+
+        let yaml = "theme: {}\nstay_open: true";
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse config");
+        assert!(config.stay_open);
+    }
+
+    #[test]
+    fn test_config_with_keybindings() {
+        let yaml = "theme: {}\nkeybindings:\n    quit: ctrl+q";
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse config");
+        assert_eq!(config.keybindings.describe(Action::Quit), "Ctrl+q");
+
+        let yaml = "theme: {}\nkeybindings:\n    quit: s";
+        let error = serde_yaml::from_str::<Config>(yaml).unwrap_err().to_string();
+        assert!(error.contains("`s` is bound to both `quit` and `search`"));
+    }
+
+    #[test]
+    fn test_config_with_actions() {
+        let test_dir = std::env::temp_dir().join("fast_ssh_tests");
+        fs::create_dir_all(&test_dir).unwrap();
+        let path = test_dir.join(format!("config_actions_{}.yaml", std::process::id()));
+
+        fs::write(
+            &path,
+            "theme: {}\nactions:\n  - name: mosh\n    key: m\n    command: mosh {alias}",
+        )
+        .unwrap();
+        let config = parse_user_config(Some(&path)).unwrap();
+        assert_eq!(config.actions[0].name, "mosh");
+        assert_eq!(config.keybindings.custom_key(0).unwrap().to_string(), "m");
+
+        fs::write(
+            &path,
+            "theme: {}\nactions:\n  - name: mosh\n    key: K\n    command: mosh {alias}",
+        )
+        .unwrap();
+        let error = parse_user_config(Some(&path)).unwrap_err().to_string();
+        assert!(error.contains("`K` is bound to both `copy_ssh_key` and `mosh`"));
+
+        let _ = fs::remove_file(&path);
+    }
+
     #[test]
     fn test_empty_config() {
         let yaml = "null";
@@ -122,6 +270,25 @@ mod tests {
         assert!(result.unwrap().is_none());
     }
 
+    #[test]
+    fn test_resolve_config_from_explicit_file() {
+        let test_dir = std::env::temp_dir().join("fast_ssh_tests");
+        fs::create_dir_all(&test_dir).unwrap();
+        let path = test_dir.join(format!("config_{}.yaml", std::process::id()));
+        fs::write(&path, "theme:\n    text_primary: \"#ff0000\"").unwrap();
+
+        let config = resolve_config(Some(&path));
+        assert_eq!(config.theme.text_primary(), tui::style::Color::Rgb(255, 0, 0));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_resolve_config_missing_explicit_file() {
+        let path = std::env::temp_dir().join("fast_ssh_tests/does_not_exist.yaml");
+        assert!(parse_user_config(Some(&path)).is_err());
+    }
+
     #[test]
     fn test_invalid_config() {
         let yaml = "this is not valid yaml: [[[";
@@ -1,10 +1,108 @@
-use anyhow::{Context, Result};
-use rustbreak::{deser::Ron, FileDatabase as _FileDatabase, RustbreakError};
+use anyhow::{format_err, Context, Result};
+use rustbreak::backend::{Backend, PathBackend};
+use rustbreak::{deser::Ron, BackendResult, Database, PathDatabase, RustbreakError};
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
 
+use crate::ssh_config_store::{is_in_group, OTHERS_GROUP};
+
+// Oldest history entries are dropped past this limit
+const HISTORY_LIMIT: usize = 10_000;
+
+// Layout of db.ron, to bump whenever it changes and read the older one in
+// `read_database`
+const DB_VERSION: u32 = 1;
+
+// Layout of the files next to db.ron, to bump whenever one of them changes
+// and read the older one in `read_side_file`
+const SIDE_FILE_VERSION: u32 = 1;
+
+/// The files of the database, shared by every running instance of Fast-SSH.
+/// Each change is made under a lock, on top of the current contents of the
+/// file, and written through a temporary file renamed over it.
 pub struct FileDatabase {
-    db: _FileDatabase<HashMap<String, HostDatabaseEntry>, Ron>,
+    db: Table<DatabaseFile>,
+    history: Table<SideFile<HistoryEntry>>,
+    favorites: Table<SideFile<Favorite>>,
+    /// `None` when the database is kept in memory
+    lock_path: Option<PathBuf>,
+    /// Migrations and recoveries done while loading, to show to the user
+    pub warnings: Vec<String>,
+}
+
+type Table<T> = Database<T, Storage, Ron>;
+
+/// Where each part of the database is saved.
+enum Storage {
+    File(PathBackend),
+    /// Serialized contents, for a database that is never written to disk
+    Memory(Vec<u8>),
+}
+
+impl Backend for Storage {
+    fn get_data(&mut self) -> BackendResult<Vec<u8>> {
+        match self {
+            Storage::File(backend) => backend.get_data(),
+            Storage::Memory(contents) => Ok(contents.clone()),
+        }
+    }
+
+    fn put_data(&mut self, data: &[u8]) -> BackendResult<()> {
+        match self {
+            Storage::File(backend) => backend.put_data(data),
+            Storage::Memory(contents) => {
+                *contents = data.to_vec();
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Versioned envelope of the hosts written to db.ron.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct DatabaseFile {
+    version: u32,
+    hosts: HashMap<String, HostDatabaseEntry>,
+    /// HostName of each host when it was last connected to, used to find it
+    /// once renamed
+    #[serde(default)]
+    hostnames: HashMap<String, String>,
+}
+
+impl Default for DatabaseFile {
+    fn default() -> Self {
+        DatabaseFile {
+            version: DB_VERSION,
+            hosts: HashMap::new(),
+            hostnames: HashMap::new(),
+        }
+    }
+}
+
+/// Versioned envelope of a file next to db.ron, e.g. the history.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct SideFile<T> {
+    version: u32,
+    entries: Vec<T>,
+}
+
+impl<T> Default for SideFile<T> {
+    fn default() -> Self {
+        SideFile {
+            version: SIDE_FILE_VERSION,
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// Only the version of the envelope, readable whatever the layout of the
+/// rest of the file.
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
@@ -13,21 +111,155 @@ pub struct HostDatabaseEntry {
     pub last_used_date: i64,
 }
 
+/// One launch of ssh, sftp or ssh-copy-id against a host.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub host_key: String,
+    pub action: String,
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub exit_code: Option<i32>,
+    pub command_line: String,
+}
+
+impl HistoryEntry {
+    /// Group the host is declared in, e.g. `client/prod` for
+    /// `client/prod/web1`.
+    #[inline]
+    pub fn group(&self) -> Option<&str> {
+        self.host_key.rsplit_once('/').map(|(group, _)| group)
+    }
+
+    /// Whether the host is in the group or one of its subgroups, as in the
+    /// groups bar.
+    #[inline]
+    pub fn in_group(&self, group: &str) -> bool {
+        is_in_group(self.group().unwrap_or(OTHERS_GROUP), group)
+    }
+
+    #[inline]
+    pub fn duration(&self) -> i64 {
+        (self.ended_at - self.started_at).max(0)
+    }
+}
+
+/// A starred host, favorites are kept in the order of the Favorites group.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Favorite {
+    pub host_key: String,
+    /// HostName of the host when it was starred, used to find it once renamed
+    #[serde(default)]
+    pub hostname: Option<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct HistoryFilter {
+    pub host: Option<String>,
+    pub group: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        if let Some(host) = &self.host {
+            if !entry.host_key.eq_ignore_ascii_case(host) {
+                return false;
+            }
+        }
+
+        if let Some(group) = &self.group {
+            if !entry.in_group(group) {
+                return false;
+            }
+        }
+
+        self.since.is_none_or(|since| entry.started_at >= since)
+            && self.until.is_none_or(|until| entry.started_at <= until)
+    }
+}
+
 impl FileDatabase {
     pub fn new(filename: &str) -> Result<FileDatabase> {
-        let db =
-            _FileDatabase::<HashMap<String, HostDatabaseEntry>, Ron>::load_from_path_or_default(
-                filename,
-            )
+        let path = Path::new(filename);
+        let mut warnings = Vec::new();
+
+        // Another instance could be writing the files while they are checked
+        let lock_path = PathBuf::from(format!("{}.lock", filename));
+        let _lock = lock_file(&lock_path)
+            .with_context(|| format!("Could not lock the database {}", filename))?;
+
+        let (data, migrated) = match fs::read_to_string(path) {
+            Ok(contents) if contents.trim().is_empty() => (DatabaseFile::default(), true),
+            Ok(contents) => match read_database(&contents)
+                .with_context(|| format!("Error while loading database from {}", filename))?
+            {
+                Some(mut data) => {
+                    let version = data.version;
+                    if version < DB_VERSION {
+                        let backup = back_up(path, version)?;
+                        warnings.push(format!(
+                            "Upgraded the database to version {}, backup saved to {}",
+                            DB_VERSION,
+                            backup.display()
+                        ));
+                    }
+
+                    data.version = DB_VERSION;
+                    (data, version < DB_VERSION)
+                }
+                None => {
+                    let moved = move_aside(path)?;
+                    warnings.push(format!(
+                        "Unreadable database moved to {}, starting with an empty one",
+                        moved.display()
+                    ));
+                    (DatabaseFile::default(), true)
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (DatabaseFile::default(), false),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Could not read the database {}", filename))
+            }
+        };
+
+        let db = PathDatabase::<DatabaseFile, Ron>::create_at_path(path.to_path_buf(), data)
+            .and_then(on_file)
             .with_context(|| format!("Error while loading database from {}", filename))?;
+        if migrated {
+            db.save()?;
+        }
+
+        // History lives next to the database so older db.ron files stay readable
+        let history_path = path.with_extension("history.ron");
+        let history = load_or_recover(&history_path, "history", &mut warnings)?;
 
-        db.load()?;
-        Ok(FileDatabase { db })
+        let favorites_path = path.with_extension("favorites.ron");
+        let favorites = load_or_recover(&favorites_path, "favorites", &mut warnings)?;
+
+        Ok(FileDatabase {
+            db,
+            history,
+            favorites,
+            lock_path: Some(lock_path),
+            warnings,
+        })
+    }
+
+    /// An empty database that is never saved, e.g. for tests.
+    pub fn in_memory() -> Result<FileDatabase> {
+        Ok(FileDatabase {
+            db: in_memory(DatabaseFile::default())?,
+            history: in_memory(SideFile::default())?,
+            favorites: in_memory(SideFile::default())?,
+            lock_path: None,
+            warnings: Vec::new(),
+        })
     }
 
     pub fn get_host_values(&self, host_key: &str) -> Result<HostDatabaseEntry, RustbreakError> {
         self.db.read(|db| {
-            let key_value = db.get_key_value(host_key);
+            let key_value = db.hosts.get_key_value(host_key);
 
             if let Some(value) = key_value {
                 *value.1
@@ -46,19 +278,390 @@ impl FileDatabase {
         connection_count: i64,
         last_used_date: i64,
     ) -> Result<(), RustbreakError> {
-        self.db.write(|db| {
-            db.insert(
+        let _lock = self.lock()?;
+        update(&self.db, |db| {
+            db.hosts.insert(
                 host_key.to_owned(),
                 HostDatabaseEntry {
                     connection_count,
                     last_used_date,
                 },
             );
+        })
+    }
+
+    /// Counts one more connection to the host, on top of the connections
+    /// recorded by the other instances. Returns the updated entry.
+    pub fn record_connection(
+        &self,
+        host_key: &str,
+        hostname: Option<&str>,
+        last_used_date: i64,
+    ) -> Result<HostDatabaseEntry, RustbreakError> {
+        let _lock = self.lock()?;
+        update(&self.db, |db| {
+            if let Some(hostname) = hostname {
+                db.hostnames
+                    .insert(host_key.to_owned(), hostname.to_owned());
+            }
+
+            let entry = db.hosts.entry(host_key.to_owned()).or_insert(HostDatabaseEntry {
+                connection_count: 0,
+                last_used_date: 0,
+            });
+            entry.connection_count += 1;
+            entry.last_used_date = last_used_date;
+            *entry
+        })
+    }
+
+    pub fn add_history_entry(&self, entry: HistoryEntry) -> Result<(), RustbreakError> {
+        let _lock = self.lock()?;
+        update(&self.history, |history| {
+            history.entries.push(entry);
+
+            if history.entries.len() > HISTORY_LIMIT {
+                let overflow = history.entries.len() - HISTORY_LIMIT;
+                history.entries.drain(..overflow);
+            }
+        })
+    }
+
+    /// Every host something is recorded for: counts, history or favorite.
+    pub fn stored_keys(&self) -> Result<Vec<String>, RustbreakError> {
+        let mut keys: Vec<String> = self.db.read(|db| db.hosts.keys().cloned().collect())?;
+        self.history.read(|history| {
+            keys.extend(history.entries.iter().map(|entry| entry.host_key.clone()))
+        })?;
+        self.favorites.read(|favorites| {
+            keys.extend(favorites.entries.iter().map(|f| f.host_key.clone()))
+        })?;
+
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    /// Last known HostName of the host, recorded on connection or when it
+    /// was starred.
+    pub fn get_hostname(&self, host_key: &str) -> Result<Option<String>, RustbreakError> {
+        let hostname = self.db.read(|db| db.hostnames.get(host_key).cloned())?;
+        match hostname {
+            Some(hostname) => Ok(Some(hostname)),
+            None => self.favorites.read(|favorites| {
+                favorites
+                    .entries
+                    .iter()
+                    .find(|f| f.host_key == host_key)
+                    .and_then(|f| f.hostname.clone())
+            }),
+        }
+    }
+
+    pub fn get_favorites(&self) -> Result<Vec<Favorite>, RustbreakError> {
+        self.favorites.read(|favorites| favorites.entries.clone())
+    }
+
+    /// Stars the host, or removes it from the favorites if it already is
+    /// one. Returns whether the host is now a favorite.
+    pub fn toggle_favorite(
+        &self,
+        host_key: &str,
+        hostname: Option<&str>,
+    ) -> Result<bool, RustbreakError> {
+        let _lock = self.lock()?;
+        update(&self.favorites, |favorites| {
+            match favorites.entries.iter().position(|f| f.host_key == host_key) {
+                Some(idx) => {
+                    favorites.entries.remove(idx);
+                    false
+                }
+                None => {
+                    favorites.entries.push(Favorite {
+                        host_key: host_key.to_string(),
+                        hostname: hostname.map(str::to_string),
+                    });
+                    true
+                }
+            }
+        })
+    }
+
+    /// Moves the favorite one place up or down. Returns `false` if the host
+    /// is not a favorite or is already at the top (or bottom).
+    pub fn move_favorite(&self, host_key: &str, up: bool) -> Result<bool, RustbreakError> {
+        let _lock = self.lock()?;
+        update(&self.favorites, |favorites| {
+            let favorites = &mut favorites.entries;
+            let Some(idx) = favorites.iter().position(|f| f.host_key == host_key) else {
+                return false;
+            };
+
+            let target = match up {
+                true => idx.checked_sub(1),
+                false => Some(idx + 1).filter(|target| *target < favorites.len()),
+            };
+
+            match target {
+                Some(target) => {
+                    favorites.swap(idx, target);
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Moves everything recorded for a host (counts, history and favorite)
+    /// to its new name.
+    pub fn rename_host(&self, old_key: &str, new_key: &str) -> Result<(), RustbreakError> {
+        let _lock = self.lock()?;
+        update(&self.db, |db| {
+            if let Some(old) = db.hosts.remove(old_key) {
+                let entry = db.hosts.entry(new_key.to_string()).or_insert(HostDatabaseEntry {
+                    connection_count: 0,
+                    last_used_date: 0,
+                });
+                entry.connection_count += old.connection_count;
+                entry.last_used_date = entry.last_used_date.max(old.last_used_date);
+            }
+
+            if let Some(hostname) = db.hostnames.remove(old_key) {
+                db.hostnames.entry(new_key.to_string()).or_insert(hostname);
+            }
+        })?;
+
+        update(&self.history, |history| {
+            history
+                .entries
+                .iter_mut()
+                .filter(|entry| entry.host_key == old_key)
+                .for_each(|entry| entry.host_key = new_key.to_string());
+        })?;
+
+        update(&self.favorites, |favorites| {
+            let favorites = &mut favorites.entries;
+            let already_favorite = favorites.iter().any(|f| f.host_key == new_key);
+            match already_favorite {
+                true => favorites.retain(|f| f.host_key != old_key),
+                false => favorites
+                    .iter_mut()
+                    .filter(|f| f.host_key == old_key)
+                    .for_each(|f| f.host_key = new_key.to_string()),
+            }
+        })
+    }
+
+    /// Forgets everything recorded for the host.
+    pub fn remove_host(&self, host_key: &str) -> Result<(), RustbreakError> {
+        let _lock = self.lock()?;
+        update(&self.db, |db| {
+            db.hosts.remove(host_key);
+            db.hostnames.remove(host_key);
+        })?;
+        update(&self.history, |history| {
+            history.entries.retain(|entry| entry.host_key != host_key)
         })?;
+        update(&self.favorites, |favorites| {
+            favorites.entries.retain(|f| f.host_key != host_key)
+        })
+    }
+
+    /// Waits for the other instances to be done with the database, until
+    /// the returned file is dropped.
+    fn lock(&self) -> Result<Option<File>, RustbreakError> {
+        self.lock_path
+            .as_deref()
+            .map(lock_file)
+            .transpose()
+            .map_err(|e| RustbreakError::Backend(e.into()))
+    }
+
+    /// Returns the matching entries, most recent first.
+    pub fn get_history(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, RustbreakError> {
+        self.history.read(|history| {
+            history
+                .entries
+                .iter()
+                .rev()
+                .filter(|entry| filter.matches(entry))
+                .cloned()
+                .collect()
+        })
+    }
+}
 
-        self.db.save()?;
-        Ok(())
+/// Exclusive advisory lock on the file, released when it is closed.
+fn lock_file(path: &Path) -> std::io::Result<File> {
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Keeps the file backend of a database loaded from disk.
+fn on_file<T>(file: PathDatabase<T, Ron>) -> Result<Table<T>, RustbreakError>
+where
+    T: Serialize + DeserializeOwned + Clone + Send,
+{
+    let (data, backend, deser) = file.into_inner()?;
+    Ok(Database::from_parts(data, Storage::File(backend), deser))
+}
+
+/// A table saved in memory only, starting with the given data.
+fn in_memory<T>(data: T) -> Result<Table<T>, RustbreakError>
+where
+    T: Serialize + DeserializeOwned + Clone + Send,
+{
+    let table = Database::from_parts(data, Storage::Memory(Vec::new()), Ron);
+    // Changes load the saved contents first
+    table.save()?;
+    Ok(table)
+}
+
+/// Applies the change to the current contents of the file, so that the
+/// changes made by other instances since it was loaded are kept. The
+/// database lock must be held.
+fn update<T, R>(
+    file: &Table<T>,
+    change: impl FnOnce(&mut T) -> R,
+) -> Result<R, RustbreakError>
+where
+    T: Serialize + DeserializeOwned + Clone + Send,
+{
+    file.load()?;
+    let result = file.write(change)?;
+    file.save()?;
+    Ok(result)
+}
+
+/// Reads db.ron whichever version wrote it, keeping that version in the
+/// returned envelope. `None` when the file cannot be parsed.
+fn read_database(contents: &str) -> Result<Option<DatabaseFile>> {
+    if let Ok(probe) = ron::from_str::<VersionProbe>(contents) {
+        if probe.version > DB_VERSION {
+            return Err(format_err!(
+                "The database was written by a newer version of Fast-SSH (version {}, \
+                 this one reads up to {})",
+                probe.version,
+                DB_VERSION
+            ));
+        }
+
+        return Ok(ron::from_str::<DatabaseFile>(contents).ok());
+    }
+
+    // Version 0: the bare map of the hosts, without an envelope
+    Ok(ron::from_str::<HashMap<String, HostDatabaseEntry>>(contents)
+        .ok()
+        .map(|hosts| DatabaseFile {
+            version: 0,
+            hosts,
+            hostnames: HashMap::new(),
+        }))
+}
+
+/// Reads a file next to the database whichever version wrote it, as
+/// `read_database` does. `None` when the file cannot be parsed.
+fn read_side_file<T: DeserializeOwned>(contents: &str) -> Result<Option<SideFile<T>>> {
+    if let Ok(probe) = ron::from_str::<VersionProbe>(contents) {
+        if probe.version > SIDE_FILE_VERSION {
+            return Err(format_err!(
+                "The file was written by a newer version of Fast-SSH (version {}, \
+                 this one reads up to {})",
+                probe.version,
+                SIDE_FILE_VERSION
+            ));
+        }
+
+        return Ok(ron::from_str::<SideFile<T>>(contents).ok());
     }
+
+    // Version 0: the bare list of the entries, without an envelope
+    Ok(ron::from_str::<Vec<T>>(contents)
+        .ok()
+        .map(|entries| SideFile {
+            version: 0,
+            entries,
+        }))
+}
+
+/// Loads a file next to the database, upgrading an older one after backing
+/// it up. When it cannot be parsed, it is moved aside and an empty one is
+/// started.
+fn load_or_recover<T>(
+    path: &Path,
+    name: &str,
+    warnings: &mut Vec<String>,
+) -> Result<Table<SideFile<T>>>
+where
+    T: Serialize + DeserializeOwned + Clone + Send,
+{
+    let (data, migrated) = match fs::read_to_string(path) {
+        Ok(contents) if contents.trim().is_empty() => (SideFile::default(), true),
+        Ok(contents) => match read_side_file(&contents)
+            .with_context(|| format!("Error while loading {} from {}", name, path.display()))?
+        {
+            Some(mut data) => {
+                let version = data.version;
+                if version < SIDE_FILE_VERSION {
+                    let backup = back_up(path, version)?;
+                    warnings.push(format!(
+                        "Upgraded the {} to version {}, backup saved to {}",
+                        name,
+                        SIDE_FILE_VERSION,
+                        backup.display()
+                    ));
+                }
+
+                data.version = SIDE_FILE_VERSION;
+                (data, version < SIDE_FILE_VERSION)
+            }
+            None => {
+                let moved = move_aside(path)?;
+                warnings.push(format!(
+                    "Unreadable {} moved to {}, starting with an empty one",
+                    name,
+                    moved.display()
+                ));
+                (SideFile::default(), true)
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (SideFile::default(), false),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Could not read {} from {}", name, path.display()))
+        }
+    };
+
+    let table = PathDatabase::<SideFile<T>, Ron>::create_at_path(path.to_path_buf(), data)
+        .and_then(on_file)
+        .with_context(|| format!("Error while loading {} from {}", name, path.display()))?;
+    if migrated {
+        table.save()?;
+    }
+    Ok(table)
+}
+
+/// Copies a file about to be upgraded to `<file>.v<version>.bak`.
+fn back_up(path: &Path, version: u32) -> Result<PathBuf> {
+    let backup = PathBuf::from(format!("{}.v{}.bak", path.display(), version));
+    fs::copy(path, &backup)
+        .with_context(|| format!("Could not back up {} to {}", path.display(), backup.display()))?;
+    Ok(backup)
+}
+
+/// Renames an unreadable file to `<file>.corrupt-<date>`, so it can still be
+/// repaired by hand.
+fn move_aside(path: &Path) -> Result<PathBuf> {
+    let date = chrono::offset::Local::now().format("%Y%m%d-%H%M%S");
+    let target = PathBuf::from(format!("{}.corrupt-{}", path.display(), date));
+
+    fs::rename(path, &target)
+        .with_context(|| format!("Could not move {} aside", path.display()))?;
+    Ok(target)
 }
 
 #[cfg(test)]
@@ -75,6 +678,20 @@ mod tests {
 
     fn cleanup_test_db(path: &str) {
         let _ = fs::remove_file(path);
+        let _ = fs::remove_file(Path::new(path).with_extension("history.ron"));
+        let _ = fs::remove_file(Path::new(path).with_extension("favorites.ron"));
+        let _ = fs::remove_file(format!("{}.lock", path));
+    }
+
+    fn history_entry(host_key: &str, started_at: i64) -> HistoryEntry {
+        HistoryEntry {
+            host_key: host_key.to_string(),
+            action: "ssh".to_string(),
+            started_at,
+            ended_at: started_at + 60,
+            exit_code: Some(0),
+            command_line: format!("ssh {}", host_key),
+        }
     }
 
     #[test]
@@ -210,4 +827,335 @@ mod tests {
         
         cleanup_test_db(&path);
     }
+
+    #[test]
+    fn test_history_entry_group_and_duration() {
+        let entry = history_entry("prod/db", 100);
+        assert_eq!(entry.group(), Some("prod"));
+        assert_eq!(entry.duration(), 60);
+
+        let entry = history_entry("client/prod/web1", 100);
+        assert_eq!(entry.group(), Some("client/prod"));
+
+        let entry = history_entry("standalone", 100);
+        assert_eq!(entry.group(), None);
+        assert!(entry.in_group("others"));
+    }
+
+    #[test]
+    fn test_history_filter() {
+        let entry = history_entry("prod/db", 1000);
+
+        assert!(HistoryFilter::default().matches(&entry));
+        assert!(HistoryFilter {
+            host: Some("PROD/db".to_string()),
+            ..Default::default()
+        }
+        .matches(&entry));
+        assert!(!HistoryFilter {
+            host: Some("prod/web".to_string()),
+            ..Default::default()
+        }
+        .matches(&entry));
+        assert!(HistoryFilter {
+            group: Some("prod".to_string()),
+            since: Some(1000),
+            until: Some(2000),
+            ..Default::default()
+        }
+        .matches(&entry));
+        assert!(!HistoryFilter {
+            since: Some(1001),
+            ..Default::default()
+        }
+        .matches(&entry));
+
+        // Subgroups are part of their parent groups
+        let nested = history_entry("client/prod/web1", 1000);
+        for group in ["client", "client/prod", "Client/Prod"] {
+            assert!(HistoryFilter {
+                group: Some(group.to_string()),
+                ..Default::default()
+            }
+            .matches(&nested));
+        }
+        for group in ["client/pro", "prod", "client/prod/web1"] {
+            assert!(!HistoryFilter {
+                group: Some(group.to_string()),
+                ..Default::default()
+            }
+            .matches(&nested));
+        }
+    }
+
+    #[test]
+    fn test_add_and_get_history() {
+        let path = get_test_db_path().replace(".ron", "_history.ron");
+        let db = FileDatabase::new(&path).unwrap();
+
+        db.add_history_entry(history_entry("prod/db", 100)).unwrap();
+        db.add_history_entry(history_entry("staging/db", 200)).unwrap();
+        db.add_history_entry(history_entry("prod/web", 300)).unwrap();
+
+        let all = db.get_history(&HistoryFilter::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].host_key, "prod/web"); // Most recent first
+
+        let prod = db
+            .get_history(&HistoryFilter {
+                group: Some("prod".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(prod.len(), 2);
+
+        // History is persisted next to the database
+        let reloaded = FileDatabase::new(&path).unwrap();
+        assert_eq!(reloaded.get_history(&HistoryFilter::default()).unwrap(), all);
+
+        cleanup_test_db(&path);
+    }
+
+    #[test]
+    fn test_favorites_toggle_and_move() {
+        let path = get_test_db_path().replace(".ron", "_favorites.ron");
+        let db = FileDatabase::new(&path).unwrap();
+
+        assert!(db.toggle_favorite("prod/db", Some("10.0.0.2")).unwrap());
+        assert!(db.toggle_favorite("prod/web", None).unwrap());
+        assert!(db.toggle_favorite("staging/web", None).unwrap());
+
+        assert!(db.move_favorite("staging/web", true).unwrap());
+        assert!(!db.move_favorite("prod/web", false).unwrap());
+        assert!(!db.move_favorite("prod/db", true).unwrap());
+        assert!(!db.move_favorite("unknown", true).unwrap());
+
+        assert!(!db.toggle_favorite("prod/web", None).unwrap());
+
+        // Favorites are persisted next to the database, in their order
+        let reloaded = FileDatabase::new(&path).unwrap();
+        let favorites = reloaded.get_favorites().unwrap();
+        assert_eq!(
+            favorites
+                .iter()
+                .map(|f| f.host_key.as_str())
+                .collect::<Vec<_>>(),
+            vec!["prod/db", "staging/web"]
+        );
+        assert_eq!(favorites[0].hostname.as_deref(), Some("10.0.0.2"));
+
+        cleanup_test_db(&path);
+    }
+
+    #[test]
+    fn test_in_memory_database() {
+        let db = FileDatabase::in_memory().unwrap();
+
+        db.record_connection("prod/db", Some("10.0.0.2"), 100).unwrap();
+        db.record_connection("prod/db", None, 200).unwrap();
+        db.add_history_entry(history_entry("prod/db", 200)).unwrap();
+        db.toggle_favorite("prod/web", None).unwrap();
+
+        let values = db.get_host_values("prod/db").unwrap();
+        assert_eq!(values.connection_count, 2);
+        assert_eq!(values.last_used_date, 200);
+        assert_eq!(db.get_hostname("prod/db").unwrap().as_deref(), Some("10.0.0.2"));
+        assert_eq!(db.get_history(&HistoryFilter::default()).unwrap().len(), 1);
+        assert_eq!(db.stored_keys().unwrap(), vec!["prod/db", "prod/web"]);
+    }
+
+    fn clean_test_dir(name: &str) -> PathBuf {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+        test_dir
+    }
+
+    #[test]
+    fn test_migrate_legacy_database() {
+        let test_dir = clean_test_dir("db_migrate");
+        let path = test_dir.join("db.ron");
+        let legacy = "{\"prod/db\": (connection_count: 3, last_used_date: 100)}";
+        fs::write(&path, legacy).unwrap();
+
+        let db = FileDatabase::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.get_host_values("prod/db").unwrap().connection_count, 3);
+        assert_eq!(db.warnings.len(), 1);
+
+        // The previous file is kept and the new one has the envelope
+        let backup = test_dir.join("db.ron.v0.bak");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), legacy);
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(read_database(&contents).unwrap().unwrap().version, DB_VERSION);
+
+        // Already migrated, nothing to report
+        let db = FileDatabase::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.get_host_values("prod/db").unwrap().last_used_date, 100);
+        assert!(db.warnings.is_empty());
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_migrate_legacy_side_files() {
+        let test_dir = clean_test_dir("db_side_migrate");
+        let path = test_dir.join("db.ron");
+        let favorites_path = test_dir.join("db.favorites.ron");
+        let legacy = "[(host_key: \"prod/db\", hostname: Some(\"10.0.0.2\"))]";
+        fs::write(&favorites_path, legacy).unwrap();
+
+        let db = FileDatabase::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.get_favorites().unwrap()[0].host_key, "prod/db");
+        assert_eq!(db.warnings.len(), 1);
+
+        let backup = test_dir.join("db.favorites.ron.v0.bak");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), legacy);
+        let contents = fs::read_to_string(&favorites_path).unwrap();
+        let favorites = read_side_file::<Favorite>(&contents).unwrap().unwrap();
+        assert_eq!(favorites.version, SIDE_FILE_VERSION);
+
+        let db = FileDatabase::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.get_favorites().unwrap().len(), 1);
+        assert!(db.warnings.is_empty());
+        drop(db);
+
+        // A history written by a newer release is left untouched
+        let history_path = test_dir.join("db.history.ron");
+        let newer = "(version: 99, entries: [], hosts: {})";
+        fs::write(&history_path, newer).unwrap();
+        assert!(FileDatabase::new(path.to_str().unwrap()).is_err());
+        assert_eq!(fs::read_to_string(&history_path).unwrap(), newer);
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_recover_corrupt_files() {
+        let test_dir = clean_test_dir("db_corrupt");
+        let path = test_dir.join("db.ron");
+        fs::write(&path, "{\"prod/db\": (connection_count: ").unwrap();
+        fs::write(test_dir.join("db.history.ron"), "not ron").unwrap();
+
+        let db = FileDatabase::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.get_host_values("prod/db").unwrap().connection_count, 0);
+        assert!(db.get_history(&HistoryFilter::default()).unwrap().is_empty());
+        assert_eq!(db.warnings.len(), 2);
+
+        // Both files are moved aside rather than overwritten
+        let moved: Vec<String> = fs::read_dir(&test_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.contains(".corrupt-"))
+            .collect();
+        assert_eq!(moved.len(), 2);
+
+        db.save_host_values("prod/db", 1, 200).unwrap();
+        assert!(FileDatabase::new(path.to_str().unwrap()).unwrap().warnings.is_empty());
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_newer_database_is_rejected() {
+        let test_dir = clean_test_dir("db_newer");
+        let path = test_dir.join("db.ron");
+        let newer = "(version: 99, hosts: {}, groups: [])";
+        fs::write(&path, newer).unwrap();
+
+        assert!(FileDatabase::new(path.to_str().unwrap()).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_two_writers_keep_each_other_changes() {
+        let test_dir = clean_test_dir("db_writers");
+        let path = test_dir.join("db.ron");
+        let path = path.to_str().unwrap();
+
+        // Both instances are started before either writes
+        let first = FileDatabase::new(path).unwrap();
+        let second = FileDatabase::new(path).unwrap();
+
+        first.record_connection("prod/db", None, 100).unwrap();
+        second.record_connection("prod/web", None, 200).unwrap();
+        second.record_connection("prod/db", None, 300).unwrap();
+        first.add_history_entry(history_entry("prod/db", 100)).unwrap();
+        second.add_history_entry(history_entry("prod/web", 200)).unwrap();
+        first.toggle_favorite("prod/db", None).unwrap();
+        second.toggle_favorite("prod/web", None).unwrap();
+
+        let reloaded = FileDatabase::new(path).unwrap();
+        let entry = reloaded.get_host_values("prod/db").unwrap();
+        assert_eq!(entry.connection_count, 2);
+        assert_eq!(entry.last_used_date, 300);
+        assert_eq!(reloaded.get_host_values("prod/web").unwrap().connection_count, 1);
+        assert_eq!(reloaded.get_history(&HistoryFilter::default()).unwrap().len(), 2);
+        assert_eq!(reloaded.get_favorites().unwrap().len(), 2);
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_concurrent_writers() {
+        let test_dir = clean_test_dir("db_concurrent");
+        let path = test_dir.join("db.ron").to_str().unwrap().to_string();
+
+        let writers: Vec<_> = (0..4)
+            .map(|_| {
+                let db = FileDatabase::new(&path).unwrap();
+                std::thread::spawn(move || {
+                    for date in 0..25 {
+                        db.record_connection("prod/db", None, date).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let db = FileDatabase::new(&path).unwrap();
+        assert_eq!(db.get_host_values("prod/db").unwrap().connection_count, 100);
+
+        // Writes go through a temporary file, none is left behind
+        let files = fs::read_dir(&test_dir).unwrap().count();
+        assert_eq!(files, 4);
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_rename_host() {
+        let path = get_test_db_path().replace(".ron", "_rename.ron");
+        let db = FileDatabase::new(&path).unwrap();
+
+        db.save_host_values("old/web", 3, 100).unwrap();
+        db.save_host_values("new/web", 1, 200).unwrap();
+        db.add_history_entry(history_entry("old/web", 100)).unwrap();
+        db.toggle_favorite("old/web", None).unwrap();
+        db.record_connection("old/web", Some("10.0.0.1"), 150).unwrap();
+
+        db.rename_host("old/web", "new/web").unwrap();
+
+        let entry = db.get_host_values("new/web").unwrap();
+        assert_eq!(entry.connection_count, 5);
+        assert_eq!(entry.last_used_date, 200);
+        assert_eq!(db.get_host_values("old/web").unwrap().connection_count, 0);
+        assert_eq!(
+            db.get_history(&HistoryFilter::default()).unwrap()[0].host_key,
+            "new/web"
+        );
+        assert_eq!(db.get_favorites().unwrap()[0].host_key, "new/web");
+        assert_eq!(db.get_hostname("new/web").unwrap().as_deref(), Some("10.0.0.1"));
+        assert_eq!(db.stored_keys().unwrap(), vec!["new/web"]);
+
+        db.remove_host("new/web").unwrap();
+        assert!(db.stored_keys().unwrap().is_empty());
+
+        cleanup_test_db(&path);
+    }
 }
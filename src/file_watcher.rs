@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::runtime::Handle;
+
+// How often the modification times are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches files in the background by polling their modification time. A
+/// directory can be watched too, its time changes when an entry is created
+/// or removed in it.
+pub struct FileWatcher {
+    files: Arc<Mutex<Vec<PathBuf>>>,
+    changes: Receiver<PathBuf>,
+}
+
+impl FileWatcher {
+    /// Starts watching the files, `None` when there is no runtime to poll
+    /// them on.
+    pub fn new(files: Vec<PathBuf>) -> Option<FileWatcher> {
+        let runtime = Handle::try_current().ok()?;
+        let files = Arc::new(Mutex::new(files));
+        let (sender, changes) = mpsc::channel();
+
+        let watched = Arc::clone(&files);
+        runtime.spawn(async move {
+            let mut known: HashMap<PathBuf, Option<SystemTime>> = HashMap::new();
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+            loop {
+                interval.tick().await;
+                let files = match watched.lock() {
+                    Ok(files) => files.clone(),
+                    Err(_) => return,
+                };
+
+                for file in files {
+                    let modified = fs::metadata(&file).and_then(|m| m.modified()).ok();
+                    let previous = known.insert(file.clone(), modified);
+
+                    // Files seen for the first time are not reported
+                    if previous.is_some_and(|previous| previous != modified)
+                        && sender.send(file).is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Some(FileWatcher { files, changes })
+    }
+
+    /// Replaces the watched files, e.g. once the included files changed.
+    pub fn watch(&self, files: Vec<PathBuf>) {
+        if let Ok(mut watched) = self.files.lock() {
+            *watched = files;
+        }
+    }
+
+    /// Files modified, created or deleted since the last call.
+    pub fn changed_files(&self) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self.changes.try_iter().collect();
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reports_modified_files() {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("watcher_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+
+        let config = test_dir.join("config");
+        let other = test_dir.join("other");
+        fs::write(&config, "Host a\n").unwrap();
+        fs::write(&other, "").unwrap();
+
+        let watcher = FileWatcher::new(vec![config.clone(), other.clone()]).unwrap();
+        tokio::time::sleep(POLL_INTERVAL * 2).await;
+        assert!(watcher.changed_files().is_empty());
+
+        // Older file systems only keep the modification time in seconds
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        fs::write(&config, "Host b\n").unwrap();
+        tokio::time::sleep(POLL_INTERVAL * 2).await;
+        assert_eq!(watcher.changed_files(), vec![config.clone()]);
+
+        watcher.watch(vec![other]);
+        fs::remove_file(&config).unwrap();
+        tokio::time::sleep(POLL_INTERVAL * 2).await;
+        assert!(watcher.changed_files().is_empty());
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
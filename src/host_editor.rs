@@ -0,0 +1,488 @@
+use anyhow::{format_err, Result};
+use ssh_cfg::SshOptionKey;
+use std::fs;
+use std::path::PathBuf;
+
+use fast_ssh::host_metadata::HostMetadata;
+use fast_ssh::ssh_config_writer::{self, HostEntry};
+use fast_ssh::{SshConfigStore, SshGroupItem};
+
+// Line breaks of the notes are shown as this sequence in the single line field
+const NOTES_LINE_BREAK: &str = "\\n";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorMode {
+    Add,
+    Edit,
+    Duplicate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorField {
+    Alias,
+    Group,
+    HostName,
+    User,
+    Port,
+    IdentityFile,
+    ProxyJump,
+    Tags,
+    Notes,
+}
+
+impl EditorField {
+    pub const ALL: [EditorField; 9] = [
+        EditorField::Alias,
+        EditorField::Group,
+        EditorField::HostName,
+        EditorField::User,
+        EditorField::Port,
+        EditorField::IdentityFile,
+        EditorField::ProxyJump,
+        EditorField::Tags,
+        EditorField::Notes,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditorField::Alias => "Alias",
+            EditorField::Group => "Group",
+            EditorField::HostName => "HostName",
+            EditorField::User => "User",
+            EditorField::Port => "Port",
+            EditorField::IdentityFile => "IdentityFile",
+            EditorField::ProxyJump => "ProxyJump",
+            EditorField::Tags => "Tags",
+            EditorField::Notes => "Notes",
+        }
+    }
+
+    fn option_key(&self) -> Option<SshOptionKey> {
+        match self {
+            EditorField::HostName => Some(SshOptionKey::Hostname),
+            EditorField::User => Some(SshOptionKey::User),
+            EditorField::Port => Some(SshOptionKey::Port),
+            EditorField::IdentityFile => Some(SshOptionKey::IdentityFile),
+            EditorField::ProxyJump => Some(SshOptionKey::ProxyJump),
+            EditorField::Alias | EditorField::Group | EditorField::Tags | EditorField::Notes => {
+                None
+            }
+        }
+    }
+}
+
+/// Form used to add, edit or duplicate a host of the SSH config.
+#[derive(Debug)]
+pub struct HostEditor {
+    pub mode: EditorMode,
+    pub values: [String; EditorField::ALL.len()],
+    pub focused: usize,
+    pub error: Option<String>,
+    /// File the host is written to
+    source_file: PathBuf,
+    /// Host being edited, or the one to insert the duplicate after
+    original_host: Option<String>,
+    /// Patterns following the first one on the `Host` line, e.g.
+    /// `web.example.com` for `Host prod/web web.example.com`, kept as they are
+    other_patterns: String,
+    /// Metadata of the host, written back with the notes
+    metadata: HostMetadata,
+}
+
+impl HostEditor {
+    pub fn new_host(source_file: PathBuf, group: Option<&str>) -> HostEditor {
+        let mut editor = HostEditor {
+            mode: EditorMode::Add,
+            values: Default::default(),
+            focused: 0,
+            error: None,
+            source_file,
+            original_host: None,
+            other_patterns: String::new(),
+            metadata: HostMetadata::default(),
+        };
+        editor.set_value(EditorField::Group, group.unwrap_or_default().to_string());
+        editor
+    }
+
+    pub fn edit_host(item: &SshGroupItem) -> HostEditor {
+        HostEditor::from_item(EditorMode::Edit, item)
+    }
+
+    pub fn duplicate_host(item: &SshGroupItem) -> HostEditor {
+        let mut editor = HostEditor::from_item(EditorMode::Duplicate, item);
+        let alias = format!("{}-copy", editor.value(EditorField::Alias));
+        editor.set_value(EditorField::Alias, alias);
+        editor
+    }
+
+    fn from_item(mode: EditorMode, item: &SshGroupItem) -> HostEditor {
+        // Only the first pattern is edited, a quoted one is left whole and
+        // refused when saving
+        let ssh_name = item.ssh_name();
+        let (name, other_patterns) = match item.full_name.strip_prefix(ssh_name.as_str()) {
+            Some(rest) => (ssh_name.as_str(), rest.trim_start()),
+            None => (item.full_name.as_str(), ""),
+        };
+        let (group, alias) = match name.rsplit_once('/') {
+            Some((group, alias)) => (group, alias),
+            None => ("", name),
+        };
+        // A duplicate would match the same names as the original
+        let other_patterns = match mode {
+            EditorMode::Duplicate => String::new(),
+            _ => other_patterns.to_string(),
+        };
+
+        let mut editor = HostEditor {
+            mode,
+            values: Default::default(),
+            focused: 0,
+            error: None,
+            source_file: item.source_file.clone(),
+            original_host: Some(item.full_name.clone()),
+            other_patterns,
+            metadata: item.metadata.clone(),
+        };
+
+        editor.set_value(EditorField::Alias, alias.to_string());
+        editor.set_value(EditorField::Group, group.to_string());
+        editor.set_value(EditorField::Tags, item.metadata.tags.join(", "));
+        editor.set_value(
+            EditorField::Notes,
+            item.comment
+                .as_deref()
+                .unwrap_or_default()
+                .replace('\n', NOTES_LINE_BREAK),
+        );
+        for field in EditorField::ALL {
+            if let Some(value) = field
+                .option_key()
+                .and_then(|key| item.host_config.get(&key))
+            {
+                editor.set_value(field, value.clone());
+            }
+        }
+
+        editor
+    }
+
+    pub fn value(&self, field: EditorField) -> &str {
+        &self.values[field as usize]
+    }
+
+    fn set_value(&mut self, field: EditorField, value: String) {
+        self.values[field as usize] = value;
+    }
+
+    #[inline]
+    pub fn focused_field(&self) -> EditorField {
+        EditorField::ALL[self.focused]
+    }
+
+    #[inline]
+    pub fn focus_next(&mut self, forward: bool) {
+        let len = EditorField::ALL.len();
+        self.focused = match forward {
+            true => (self.focused + 1) % len,
+            false => (self.focused + len - 1) % len,
+        };
+    }
+
+    #[inline]
+    pub fn add_char(&mut self, c: char) {
+        self.values[self.focused].push(c);
+        self.error = None;
+    }
+
+    #[inline]
+    pub fn del_char(&mut self) {
+        self.values[self.focused].pop();
+        self.error = None;
+    }
+
+    /// Full host name, as written on the `Host` line.
+    pub fn host_name(&self) -> String {
+        let alias = self.value(EditorField::Alias).trim();
+        let name = match self.value(EditorField::Group).trim() {
+            "" => alias.to_string(),
+            group => format!("{}/{}", group, alias),
+        };
+
+        match self.other_patterns.is_empty() {
+            true => name,
+            false => format!("{} {}", name, self.other_patterns),
+        }
+    }
+
+    /// Previous name of the edited host, if the form renames it.
+    pub fn renamed_from(&self) -> Option<&str> {
+        match self.mode {
+            EditorMode::Edit => self
+                .original_host
+                .as_deref()
+                .filter(|original| *original != self.host_name()),
+            _ => None,
+        }
+    }
+
+    /// Checks the form and builds the entry to write.
+    pub fn to_entry(&self, scs: &SshConfigStore) -> Result<HostEntry, String> {
+        let alias = self.value(EditorField::Alias).trim();
+        let group = self.value(EditorField::Group).trim();
+
+        if alias.is_empty() {
+            return Err("Alias is required".to_string());
+        }
+        if alias.contains(char::is_whitespace) || group.contains(char::is_whitespace) {
+            return Err("Alias and group cannot contain spaces".to_string());
+        }
+        if alias.contains('/') {
+            return Err("Alias cannot contain '/'".to_string());
+        }
+        // Subgroups are separated with '/', e.g. `client/prod`
+        if !group.is_empty() && group.split('/').any(str::is_empty) {
+            return Err("Group cannot have an empty part".to_string());
+        }
+
+        let port = self.value(EditorField::Port).trim();
+        if !port.is_empty() && !port.parse::<u16>().is_ok_and(|port| port > 0) {
+            return Err("Port must be a number between 1 and 65535".to_string());
+        }
+
+        let host = self.host_name();
+        let renamed = self.mode != EditorMode::Edit || self.original_host.as_ref() != Some(&host);
+        if renamed && scs.find_item(&host).is_some() {
+            return Err(format!("Host `{}` already exists", host));
+        }
+
+        let options = EditorField::ALL
+            .iter()
+            .filter_map(|field| {
+                let key = field.option_key()?;
+                Some((key, self.value(*field).trim().to_string()))
+            })
+            .collect();
+
+        // Tags come from their own field, the other metadata is kept as is
+        let mut metadata = HostMetadata::default();
+        metadata.fields = self.metadata.fields.clone();
+        metadata.add_tags(self.value(EditorField::Tags));
+
+        let notes = self.value(EditorField::Notes).trim();
+        let notes = metadata
+            .to_lines()
+            .into_iter()
+            .chain((!notes.is_empty()).then(|| notes.replace(NOTES_LINE_BREAK, "\n")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let notes = (!notes.is_empty()).then_some(notes);
+
+        Ok(HostEntry {
+            host,
+            options,
+            notes,
+        })
+    }
+
+    /// Writes the host to its file, returns its full name.
+    pub fn save(&self, scs: &SshConfigStore) -> Result<String> {
+        let entry = self.to_entry(scs).map_err(|e| format_err!(e))?;
+        let contents = read_source_file(&self.source_file)?;
+
+        let updated = match (self.mode, &self.original_host) {
+            (EditorMode::Edit, Some(original)) => {
+                ssh_config_writer::update_host(&contents, original, &entry)?
+            }
+            (EditorMode::Duplicate, Some(original)) => {
+                ssh_config_writer::insert_host(&contents, Some(original), &entry)?
+            }
+            _ => ssh_config_writer::insert_host(&contents, None, &entry)?,
+        };
+
+        ssh_config_writer::write_with_backup(&self.source_file, &updated)?;
+        Ok(entry.host)
+    }
+}
+
+/// Removes the block of the host from the file it was declared in.
+pub fn delete_host(item: &SshGroupItem) -> Result<()> {
+    let contents = read_source_file(&item.source_file)?;
+    let updated = ssh_config_writer::delete_host(&contents, &item.full_name)?;
+    ssh_config_writer::write_with_backup(&item.source_file, &updated)
+}
+
+fn read_source_file(path: &PathBuf) -> Result<String> {
+    if path.as_os_str().is_empty() {
+        return Err(format_err!("The file declaring this host is unknown"));
+    }
+
+    fs::read_to_string(path).map_err(|e| format_err!("Could not read {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fast_ssh::ssh_config_loader::SshConfigLoader;
+    use fast_ssh::FileDatabase;
+    use std::path::Path;
+
+    fn test_store(name: &str, contents: &str) -> (SshConfigStore, PathBuf, FileDatabase) {
+        let test_dir = std::env::temp_dir().join("fast_ssh_tests");
+        fs::create_dir_all(&test_dir).unwrap();
+
+        let config_path = test_dir.join(format!("editor_{}_{}", name, std::process::id()));
+        fs::write(&config_path, contents).unwrap();
+
+        let db_path = test_dir.join(format!("editor_{}_{}.ron", name, std::process::id()));
+        let db = FileDatabase::new(db_path.to_str().unwrap()).unwrap();
+
+        let loaded = SshConfigLoader::new().unwrap().load(&config_path).unwrap();
+        (
+            SshConfigStore::from_loaded(loaded, &db).unwrap(),
+            config_path,
+            db,
+        )
+    }
+
+    fn cleanup(config_path: &Path) {
+        let mut backup = config_path.as_os_str().to_owned();
+        backup.push(".bak");
+        let _ = fs::remove_file(config_path);
+        let _ = fs::remove_file(backup);
+        let db_path = config_path.with_extension("ron");
+        let _ = fs::remove_file(&db_path);
+        let _ = fs::remove_file(db_path.with_extension("history.ron"));
+    }
+
+    #[test]
+    fn test_edit_prefills_fields() {
+        let (scs, config_path, _db) = test_store(
+            "prefill",
+            "# Web\n# Frontend\nHost prod/web\n    HostName 10.0.0.1\n    Port 2222\n",
+        );
+
+        let editor = HostEditor::edit_host(scs.find_item("prod/web").unwrap());
+        assert_eq!(editor.value(EditorField::Alias), "web");
+        assert_eq!(editor.value(EditorField::Group), "prod");
+        assert_eq!(editor.value(EditorField::HostName), "10.0.0.1");
+        assert_eq!(editor.value(EditorField::Port), "2222");
+        assert_eq!(editor.value(EditorField::User), "");
+        assert_eq!(editor.value(EditorField::Notes), "Web\\nFrontend");
+
+        let editor = HostEditor::duplicate_host(scs.find_item("prod/web").unwrap());
+        assert_eq!(editor.host_name(), "prod/web-copy");
+
+        cleanup(&config_path);
+    }
+
+    #[test]
+    fn test_validation() {
+        let (scs, config_path, _db) =
+            test_store("validation", "Host prod/web\n    HostName 10.0.0.1\n");
+        let mut editor = HostEditor::new_host(config_path.clone(), Some("prod"));
+
+        assert_eq!(editor.to_entry(&scs), Err("Alias is required".to_string()));
+
+        editor.values[EditorField::Alias as usize] = "web".to_string();
+        assert_eq!(
+            editor.to_entry(&scs),
+            Err("Host `prod/web` already exists".to_string())
+        );
+
+        editor.values[EditorField::Alias as usize] = "my web".to_string();
+        assert!(editor.to_entry(&scs).is_err());
+
+        editor.values[EditorField::Alias as usize] = "api".to_string();
+        editor.values[EditorField::Port as usize] = "70000".to_string();
+        assert!(editor.to_entry(&scs).is_err());
+
+        editor.values[EditorField::Port as usize] = "22".to_string();
+        assert_eq!(editor.to_entry(&scs).unwrap().host, "prod/api");
+
+        // Groups can be nested, but not end with '/'
+        editor.values[EditorField::Group as usize] = "prod/eu".to_string();
+        assert_eq!(editor.to_entry(&scs).unwrap().host, "prod/eu/api");
+        editor.values[EditorField::Group as usize] = "prod/".to_string();
+        assert!(editor.to_entry(&scs).is_err());
+
+        // Keeping the same name while editing is not a duplicate
+        let editor = HostEditor::edit_host(scs.find_item("prod/web").unwrap());
+        assert!(editor.to_entry(&scs).is_ok());
+
+        cleanup(&config_path);
+    }
+
+    #[test]
+    fn test_save_writes_to_source_file() {
+        let (scs, config_path, _db) = test_store(
+            "save",
+            "Host prod/web\n    HostName 10.0.0.1\n    User root\n",
+        );
+
+        let mut editor = HostEditor::edit_host(scs.find_item("prod/web").unwrap());
+        editor.values[EditorField::User as usize] = "deploy".to_string();
+        editor.values[EditorField::Notes as usize] = "Line 1\\nLine 2".to_string();
+        assert_eq!(editor.save(&scs).unwrap(), "prod/web");
+
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "# Line 1\n# Line 2\nHost prod/web\n    HostName 10.0.0.1\n    User deploy\n"
+        );
+
+        delete_host(scs.find_item("prod/web").unwrap()).unwrap();
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), "");
+
+        cleanup(&config_path);
+    }
+
+    #[test]
+    fn test_edit_multi_pattern_host() {
+        let (scs, config_path, _db) = test_store(
+            "patterns",
+            "Host prod/web web.example.com\n    HostName 10.0.0.1\n",
+        );
+        let item = scs.find_item("prod/web web.example.com").unwrap();
+
+        let mut editor = HostEditor::edit_host(item);
+        assert_eq!(editor.value(EditorField::Alias), "web");
+        assert_eq!(editor.value(EditorField::Group), "prod");
+        assert_eq!(editor.renamed_from(), None);
+
+        // The other patterns are written back after the edited one
+        editor.values[EditorField::Alias as usize] = "api".to_string();
+        editor.values[EditorField::User as usize] = "deploy".to_string();
+        assert_eq!(editor.save(&scs).unwrap(), "prod/api web.example.com");
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "Host prod/api web.example.com\n    HostName 10.0.0.1\n    User deploy\n"
+        );
+
+        let editor = HostEditor::duplicate_host(item);
+        assert_eq!(editor.host_name(), "prod/web-copy");
+
+        cleanup(&config_path);
+    }
+
+    #[test]
+    fn test_save_keeps_metadata() {
+        let (scs, config_path, _db) = test_store(
+            "metadata",
+            "# Primary\n# @env: staging\n# @tags: prod\nHost db\n    HostName 10.0.0.2\n",
+        );
+
+        let mut editor = HostEditor::edit_host(scs.find_item("db").unwrap());
+        assert_eq!(editor.value(EditorField::Tags), "prod");
+        assert_eq!(editor.value(EditorField::Notes), "Primary");
+
+        editor.values[EditorField::Tags as usize] = "prod, postgres".to_string();
+        editor.save(&scs).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "# @tags: prod, postgres\n# @env: staging\n# Primary\nHost db\n    HostName 10.0.0.2\n"
+        );
+
+        cleanup(&config_path);
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use ssh_cfg::SshOptionKey;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+
+use fast_ssh::SshGroupItem;
+
+/// Output of `fast-ssh list`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum ListFormat {
+    /// Name and HostName of each host, aligned
+    #[default]
+    Table,
+    /// A single array of hosts
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    Csv,
+    Tsv,
+}
+
+/// A host as exported by `fast-ssh list`.
+#[derive(Debug, Serialize)]
+pub struct HostRecord<'a> {
+    pub group: &'a str,
+    pub alias: &'a str,
+    pub full_name: &'a str,
+    /// Options of the `Host` block, by name
+    pub options: BTreeMap<String, &'a str>,
+    pub comment: Option<&'a str>,
+    pub tags: &'a [String],
+    /// `@key value` lines of the comment
+    pub metadata: BTreeMap<&'a str, &'a str>,
+    pub connection_count: i64,
+    /// Timestamp of the last connection, 0 if there was none
+    pub last_used: i64,
+}
+
+impl<'a> From<&'a SshGroupItem> for HostRecord<'a> {
+    fn from(item: &'a SshGroupItem) -> Self {
+        HostRecord {
+            group: item.group_name(),
+            alias: &item.name,
+            full_name: &item.full_name,
+            options: item
+                .host_config
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.as_str()))
+                .collect(),
+            comment: item.comment.as_deref(),
+            tags: &item.metadata.tags,
+            metadata: item
+                .metadata
+                .fields
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect(),
+            connection_count: item.connection_count,
+            last_used: item.last_used,
+        }
+    }
+}
+
+pub fn format_hosts(items: &[&SshGroupItem], format: ListFormat) -> Result<String> {
+    let records: Vec<HostRecord> = items.iter().map(|item| HostRecord::from(*item)).collect();
+
+    Ok(match format {
+        ListFormat::Table => format_table(items),
+        ListFormat::Json => serde_json::to_string_pretty(&records)? + "\n",
+        ListFormat::Ndjson => {
+            let mut output = String::new();
+            for record in &records {
+                output.push_str(&serde_json::to_string(record)?);
+                output.push('\n');
+            }
+            output
+        }
+        ListFormat::Csv => format_separated(&records, ',', csv_field),
+        ListFormat::Tsv => format_separated(&records, '\t', tsv_field),
+    })
+}
+
+fn format_table(items: &[&SshGroupItem]) -> String {
+    let width = items
+        .iter()
+        .map(|item| item.full_name.len())
+        .max()
+        .unwrap_or(0);
+    let mut output = String::new();
+
+    for item in items {
+        let host_name = item
+            .host_config
+            .get(&SshOptionKey::Hostname)
+            .map(String::as_str)
+            .unwrap_or("");
+        let _ = writeln!(
+            output,
+            "{:<width$}  {}",
+            item.full_name,
+            host_name,
+            width = width
+        );
+    }
+
+    output
+}
+
+/// One line per host, with a column for every option and metadata key set
+/// on at least one of them.
+fn format_separated(records: &[HostRecord], separator: char, escape: fn(&str) -> String) -> String {
+    let options: BTreeSet<&str> = records
+        .iter()
+        .flat_map(|record| record.options.keys().map(String::as_str))
+        .collect();
+    let metadata: BTreeSet<&str> = records
+        .iter()
+        .flat_map(|record| record.metadata.keys().copied())
+        .collect();
+
+    let mut header = vec!["group".to_string(), "alias".into(), "full_name".into()];
+    header.extend(options.iter().map(|key| key.to_string()));
+    header.extend(["comment".into(), "tags".into()]);
+    header.extend(metadata.iter().map(|key| format!("@{}", key)));
+    header.extend(["connection_count".into(), "last_used".into()]);
+
+    let mut lines = vec![header];
+    for record in records {
+        let mut line = vec![
+            record.group.to_string(),
+            record.alias.to_string(),
+            record.full_name.to_string(),
+        ];
+        line.extend(
+            options
+                .iter()
+                .map(|key| record.options.get(*key).unwrap_or(&"").to_string()),
+        );
+        line.push(record.comment.unwrap_or("").to_string());
+        line.push(record.tags.join(","));
+        line.extend(
+            metadata
+                .iter()
+                .map(|key| record.metadata.get(key).unwrap_or(&"").to_string()),
+        );
+        line.push(record.connection_count.to_string());
+        line.push(record.last_used.to_string());
+        lines.push(line);
+    }
+
+    let mut output = String::new();
+    for line in lines {
+        let fields: Vec<String> = line.iter().map(|field| escape(field)).collect();
+        output.push_str(&fields.join(&separator.to_string()));
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Quotes the field when it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+/// TSV has no quoting, tabs and line breaks become spaces.
+fn tsv_field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_item(full_name: &str, options: &[(SshOptionKey, &str)]) -> SshGroupItem {
+        let mut item = SshGroupItem::new(full_name, options);
+        item.connection_count = 2;
+        item.last_used = 1000;
+        item
+    }
+
+    #[test]
+    fn test_json_formats() {
+        let mut db = test_item("prod/db", &[(SshOptionKey::Hostname, "10.0.0.2")]);
+        db.comment = Some("Primary database".to_string());
+        db.metadata.add_tags("postgres");
+        let items = vec![&db];
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_hosts(&items, ListFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["group"], "prod");
+        assert_eq!(json[0]["alias"], "db");
+        assert_eq!(json[0]["options"]["Hostname"], "10.0.0.2");
+        assert_eq!(json[0]["comment"], "Primary database");
+        assert_eq!(json[0]["tags"][0], "postgres");
+        assert_eq!(json[0]["connection_count"], 2);
+
+        let web = test_item("prod/web", &[]);
+        let ndjson = format_hosts(&[&db, &web], ListFormat::Ndjson).unwrap();
+        assert_eq!(ndjson.lines().count(), 2);
+        assert!(ndjson.lines().all(|line| line.starts_with('{')));
+    }
+
+    #[test]
+    fn test_separated_formats() {
+        let mut db = test_item(
+            "prod/db",
+            &[
+                (SshOptionKey::Hostname, "10.0.0.2"),
+                (SshOptionKey::User, "postgres"),
+            ],
+        );
+        db.comment = Some("Primary, \"main\" database".to_string());
+        let web = test_item("web", &[(SshOptionKey::Port, "2222")]);
+
+        assert_eq!(
+            format_hosts(&[&db, &web], ListFormat::Csv).unwrap(),
+            "group,alias,full_name,Hostname,Port,User,comment,tags,connection_count,last_used\n\
+             prod,db,prod/db,10.0.0.2,,postgres,\"Primary, \"\"main\"\" database\",,2,1000\n\
+             Others,web,web,,2222,,,,2,1000\n"
+        );
+
+        let tsv = format_hosts(&[&db], ListFormat::Tsv).unwrap();
+        assert_eq!(
+            tsv.lines().nth(1),
+            Some("prod\tdb\tprod/db\t10.0.0.2\tpostgres\tPrimary, \"main\" database\t\t2\t1000")
+        );
+    }
+}
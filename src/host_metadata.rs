@@ -0,0 +1,130 @@
+// Key of the metadata line listing the tags of a host
+const TAGS_KEY: &str = "tags";
+
+/// Metadata written in the comment block of a host as `@key: value` lines,
+/// e.g. `# @tags: prod, postgres` or `# @env: staging`.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct HostMetadata {
+    pub tags: Vec<String>,
+    /// Other metadata, in the order they were written
+    pub fields: Vec<(String, String)>,
+}
+
+impl HostMetadata {
+    /// Splits a comment between its metadata lines and the remaining notes.
+    pub fn parse(comment: &str) -> (HostMetadata, Option<String>) {
+        let mut metadata = HostMetadata::default();
+        let mut notes = Vec::new();
+
+        for line in comment.lines() {
+            match parse_line(line) {
+                Some((key, value)) if key == TAGS_KEY || key == "tag" => {
+                    metadata.add_tags(value);
+                }
+                Some((key, value)) => {
+                    if metadata.get(&key).is_none() {
+                        metadata.fields.push((key, value.to_string()));
+                    }
+                }
+                None => notes.push(line),
+            }
+        }
+
+        let notes = notes.join("\n").trim().to_string();
+        (metadata, (!notes.is_empty()).then_some(notes))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[inline]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Adds the tags of a comma separated list, skipping the known ones.
+    pub fn add_tags(&mut self, list: &str) {
+        for tag in list.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+            if !self.has_tag(tag) {
+                self.tags.push(tag.to_string());
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.fields.is_empty()
+    }
+
+    /// Comment lines, without the `#`, writing the metadata back.
+    pub fn to_lines(&self) -> Vec<String> {
+        let tags =
+            (!self.tags.is_empty()).then(|| format!("@{}: {}", TAGS_KEY, self.tags.join(", ")));
+
+        tags.into_iter()
+            .chain(
+                self.fields
+                    .iter()
+                    .map(|(key, value)| format!("@{}: {}", key, value)),
+            )
+            .collect()
+    }
+}
+
+/// Parses a `@key: value` line, the key being returned in lowercase.
+fn parse_line(line: &str) -> Option<(String, &str)> {
+    let (key, value) = line.trim().strip_prefix('@')?.split_once(':')?;
+    let is_key = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+
+    match !key.is_empty() && key.chars().all(is_key) {
+        true => Some((key.to_ascii_lowercase(), value.trim())),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let (metadata, notes) = HostMetadata::parse(
+            "Primary database\n@tags: prod, postgres\n@Env: staging\n@tag: Postgres, backup\nReplicated nightly",
+        );
+
+        assert_eq!(metadata.tags, vec!["prod", "postgres", "backup"]);
+        assert_eq!(metadata.get("env"), Some("staging"));
+        assert!(metadata.has_tag("PROD"));
+        assert_eq!(
+            notes.as_deref(),
+            Some("Primary database\nReplicated nightly")
+        );
+    }
+
+    #[test]
+    fn test_parse_keeps_other_lines_as_notes() {
+        let (metadata, notes) = HostMetadata::parse("@tags: web\nping @admin: on call\nsee: @docs");
+
+        assert_eq!(metadata.tags, vec!["web"]);
+        assert_eq!(notes.as_deref(), Some("ping @admin: on call\nsee: @docs"));
+
+        let (metadata, notes) = HostMetadata::parse("@tags: web");
+        assert_eq!(notes, None);
+        assert!(!metadata.is_empty());
+    }
+
+    #[test]
+    fn test_to_lines() {
+        let (metadata, _) = HostMetadata::parse("@env: staging\n@tags: prod,postgres");
+        assert_eq!(
+            metadata.to_lines(),
+            vec!["@tags: prod, postgres", "@env: staging"]
+        );
+        assert!(HostMetadata::default().to_lines().is_empty());
+    }
+}
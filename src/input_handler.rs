@@ -1,107 +1,362 @@
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use fast_ssh::ssh_config_store::OTHERS_GROUP;
+use std::time::Duration;
 
-use crate::app::{App, AppState};
+use crate::app::{App, AppState, Launch};
+use crate::command_palette::{CommandPalette, PaletteEntry};
+use crate::get_config;
+use crate::host_editor::HostEditor;
+use crate::keybindings::{Action, KeyChord, KeyContext, Lookup};
+
+// Time to wait for a key before drawing again, so background results show up
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(250);
 
 pub fn handle_inputs(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    if !event::poll(INPUT_POLL_INTERVAL)? {
+        return Ok(());
+    }
+
     if let Event::Key(key) = event::read()? {
-        match app.state {
-            AppState::Normal => {
-                handle_input_normal_mode(app, key.code, key.modifiers);
-            }
-            AppState::Searching => {
-                handle_input_search_mode(app, key.code, key.modifiers);
-            }
-        };
+        handle_key(app, key);
     }
     Ok(())
 }
 
+/// Applies a key press to the panel or mode that has the focus.
+pub fn handle_key(app: &mut App, key: KeyEvent) {
+    if app.host_editor.is_some() {
+        handle_input_host_editor(app, key.code, key.modifiers);
+        return;
+    }
+
+    if app.pending_delete.is_some() {
+        handle_input_delete_confirmation(app, key.code);
+        return;
+    }
+
+    if app.pending_rename.is_some() {
+        app.confirm_rename(matches!(key.code, KeyCode::Char('y') | KeyCode::Enter));
+        return;
+    }
+
+    if app.palette.is_some() {
+        handle_input_palette(app, key.code, key.modifiers);
+        return;
+    }
+
+    if app.tunnels_panel.is_some() {
+        handle_input_tunnels_panel(app, KeyChord::from(key));
+        return;
+    }
+
+    if app.prune_panel.is_some() {
+        handle_input_prune_panel(app, KeyChord::from(key));
+        return;
+    }
+
+    if app.run_prompt.is_some() {
+        handle_input_run_prompt(app, key.code, key.modifiers);
+        return;
+    }
+
+    match app.state {
+        AppState::Normal => {
+            handle_input_normal_mode(app, KeyChord::from(key));
+        }
+        AppState::Searching => {
+            handle_input_search_mode(app, KeyChord::from(key));
+        }
+    };
+}
+
+/// Adds the key to the ones pressed so far and returns the action they are
+/// bound to, if any.
+fn pressed_action(app: &mut App, context: KeyContext, chord: KeyChord) -> Option<PaletteEntry> {
+    let bindings = &get_config().keybindings;
+    app.pending_keys.push(chord);
+
+    match bindings.lookup(context, &app.pending_keys) {
+        Lookup::Action(action) => {
+            app.pending_keys.clear();
+            Some(PaletteEntry::Action(action))
+        }
+        Lookup::Custom(idx) => {
+            app.pending_keys.clear();
+            Some(PaletteEntry::Custom(idx))
+        }
+        Lookup::Pending => None,
+        Lookup::None => {
+            // The key did not continue the sequence, it may start a new one
+            let restart = app.pending_keys.len() > 1;
+            app.pending_keys.clear();
+            match restart {
+                true => pressed_action(app, context, chord),
+                false => None,
+            }
+        }
+    }
+}
+
 /// Handle input in normal mode
-fn handle_input_normal_mode(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
-    // Handle mode-specific commands first
-    match key {
-        KeyCode::Char('c') => app.toggle_config_display_mode(),
-        KeyCode::Char('?') => app.show_help = !app.show_help,
-        KeyCode::Char('s') | KeyCode::Char('/') => app.state = AppState::Searching,
-        KeyCode::Char('q') => app.should_quit = true,
-        KeyCode::Char('K') => {
-            if app.get_selected_item().is_some() {
-                app.should_copy_ssh_key = true;
-                app.should_quit = true;
+fn handle_input_normal_mode(app: &mut App, chord: KeyChord) {
+    match pressed_action(app, KeyContext::Normal, chord) {
+        Some(PaletteEntry::Action(action)) => run_action(app, action),
+        Some(PaletteEntry::Custom(idx)) => run_custom_action(app, idx),
+        None => {}
+    }
+}
+
+/// Runs an action of the hosts list, from its keys or from the palette
+fn run_action(app: &mut App, action: Action) {
+    match action {
+        Action::CommandPalette => app.palette = Some(CommandPalette::default()),
+        Action::ToggleConfigMode => app.toggle_config_display_mode(),
+        Action::ToggleHelp => app.show_help = !app.show_help,
+        Action::ToggleHistory => {
+            app.show_history = !app.show_history;
+            app.show_run_results = false;
+            app.refresh_history();
+        }
+        Action::ToggleRunResults => {
+            app.show_run_results = !app.show_run_results && app.parallel_run.is_some();
+            app.show_history = false;
+        }
+        Action::ExportRunResults => app.export_run_results(),
+        Action::Tunnels => app.open_tunnels_panel(),
+        Action::PruneDatabase => app.open_prune_panel(),
+        Action::ToggleSelection => app.toggle_host_selection(),
+        Action::SelectAll => app.toggle_listed_hosts_selection(),
+        Action::RunCommand => {
+            if !app.run_targets().is_empty() {
+                app.run_prompt = Some(String::new());
             }
         }
-        KeyCode::Char('C') => {
-            if app.get_selected_item().is_some() {
-                app.should_copy_files = true;
-                app.should_quit = true;
+        Action::ToggleFavorite => app.toggle_favorite(),
+        Action::MoveFavoriteUp => app.move_favorite(true),
+        Action::MoveFavoriteDown => app.move_favorite(false),
+        Action::Search => app.state = AppState::Searching,
+        Action::Quit => app.should_quit = true,
+        Action::CopySshKey => launch(app, Launch::CopySshKey),
+        Action::CopyFiles => launch(app, Launch::CopyFiles),
+        Action::Connect => launch(app, Launch::Ssh),
+        Action::AddHost => {
+            // New hosts go to the main config file, in the displayed group
+            let group = app
+                .get_selected_group()
+                .filter(|group| !group.is_virtual() && group.name != OTHERS_GROUP)
+                .map(|group| group.name.as_str());
+            if let Some(root_file) = app.scs.files.first() {
+                app.host_editor = Some(HostEditor::new_host(root_file.clone(), group));
             }
         }
-        _ => {
-            // Handle navigation
-            handle_normal_mode_navigation(app, key, modifiers);
+        Action::EditHost => {
+            app.host_editor = app.get_selected_item().map(HostEditor::edit_host);
+        }
+        Action::DuplicateHost => {
+            app.host_editor = app.get_selected_item().map(HostEditor::duplicate_host);
+        }
+        Action::DeleteHost => {
+            if let Some(host) = app.get_selected_item().map(|item| item.full_name.clone()) {
+                app.status_message = Some(format!("Delete {}? (y/n)", host));
+                app.pending_delete = Some(host);
+            }
         }
-    }
-}
 
-/// Handle navigation in normal mode
-#[inline]
-fn handle_normal_mode_navigation(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
-    match key {
         // Group navigation
-        KeyCode::Tab => app.change_selected_group(true),
-        KeyCode::BackTab => app.change_selected_group(false),
-        KeyCode::Left | KeyCode::Char('h') => app.change_selected_group(false),
-        KeyCode::Right | KeyCode::Char('l') => app.change_selected_group(true),
-        KeyCode::Char(' ') => app.select_recents_group(),
-        
+        Action::NextGroup => app.change_selected_group(true),
+        Action::PreviousGroup => app.change_selected_group(false),
+        Action::SelectRecents => app.select_recents_group(),
+        Action::SelectFavorites => app.select_favorites_group(),
+        Action::ToggleGroup => app.toggle_selected_group(),
+        Action::ToggleGroupTree => app.show_group_tree = !app.show_group_tree,
+
         // Item navigation
-        KeyCode::Down | KeyCode::Char('j') => app.change_selected_item(true),
-        KeyCode::Up | KeyCode::Char('k') => app.change_selected_item(false),
-        
+        Action::NextHost => app.change_selected_item(true),
+        Action::PreviousHost => app.change_selected_item(false),
+
         // Config scrolling
-        KeyCode::PageDown => app.scroll_config_paragraph(1),
-        KeyCode::PageUp => app.scroll_config_paragraph(-1),
-        
-        // Enter to connect
+        Action::ScrollConfigDown => app.scroll_config_paragraph(1),
+        Action::ScrollConfigUp => app.scroll_config_paragraph(-1),
+
+        action => handle_common_navigation(app, action),
+    }
+}
+
+/// Closes the TUI to run the command, if a host is selected
+fn launch(app: &mut App, launch: Launch) {
+    if app.get_selected_item().is_some() {
+        app.launch = Some(launch);
+    }
+}
+
+/// Runs a custom action from the config. Interactive ones close the TUI,
+/// the others run in place and report their exit status.
+fn run_custom_action(app: &mut App, idx: usize) {
+    let config = get_config();
+    let Some(action) = config.actions.get(idx) else {
+        return;
+    };
+    if action.interactive {
+        launch(app, Launch::Custom(idx));
+        return;
+    }
+
+    let Some(item) = app.get_selected_item().cloned() else {
+        return;
+    };
+    let host_name = &item.full_name;
+
+    let message = match app.run_launch(&item, Launch::Custom(idx)) {
+        Ok(outcome) => outcome.describe(&action.name, host_name),
+        Err(e) => format!("{} {}: {}", action.name, host_name, e),
+    };
+
+    // Pick up the updated connection count
+    app.status_message = Some(match app.reload() {
+        Ok(()) => app.with_store_warnings(message),
+        Err(e) => format!("Failed to reload the SSH config: {}", e),
+    });
+}
+
+/// Handle input while the command palette is open
+fn handle_input_palette(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
+    let actions = &get_config().actions;
+    let Some(palette) = &mut app.palette else {
+        return;
+    };
+
+    match key {
+        KeyCode::Esc => app.palette = None,
         KeyCode::Enter => {
-            if app.get_selected_item().is_some() {
-                app.should_spawn_ssh = true;
+            let entry = palette.selected_entry(actions);
+            app.palette = None;
+            match entry {
+                Some(PaletteEntry::Action(action)) => run_action(app, action),
+                Some(PaletteEntry::Custom(idx)) => run_custom_action(app, idx),
+                None => {}
             }
-            app.pending_g = false;
         }
-        
+        KeyCode::Down | KeyCode::Tab => palette.change_selected(actions, true),
+        KeyCode::Up | KeyCode::BackTab => palette.change_selected(actions, false),
+        KeyCode::Backspace => palette.del_char(),
+        KeyCode::Char(c) if !modifiers.intersects(KeyModifiers::ALT | KeyModifiers::CONTROL) => {
+            palette.add_char(c);
+        }
+        _ => {}
+    }
+}
+
+/// Handle input while the command to run on the selected hosts is typed
+fn handle_input_run_prompt(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
+    let Some(prompt) = &mut app.run_prompt else {
+        return;
+    };
+
+    match key {
+        KeyCode::Esc => app.run_prompt = None,
+        KeyCode::Enter => app.start_parallel_run(),
+        KeyCode::Backspace => {
+            prompt.pop();
+        }
+        KeyCode::Char(c) if !modifiers.intersects(KeyModifiers::ALT | KeyModifiers::CONTROL) => {
+            prompt.push(c);
+        }
+        _ => {}
+    }
+}
+
+/// Handle input while the tunnels panel is open
+fn handle_input_tunnels_panel(app: &mut App, chord: KeyChord) {
+    let Some(PaletteEntry::Action(action)) = pressed_action(app, KeyContext::Tunnels, chord) else {
+        return;
+    };
+
+    match action {
+        Action::ClosePanel | Action::Tunnels => app.tunnels_panel = None,
+        Action::ToggleTunnel => app.toggle_selected_tunnel(),
+        Action::NextEntry => app.change_selected_tunnel(true),
+        Action::PreviousEntry => app.change_selected_tunnel(false),
+        _ => {}
+    }
+}
+
+/// Handle input while the database clean-up panel is open
+fn handle_input_prune_panel(app: &mut App, chord: KeyChord) {
+    let Some(PaletteEntry::Action(action)) = pressed_action(app, KeyContext::Prune, chord) else {
+        return;
+    };
+
+    match action {
+        Action::ClosePanel | Action::PruneDatabase => app.prune_panel = None,
+        Action::MergeStaleEntry => app.resolve_selected_stale_entry(true),
+        Action::DeleteStaleEntry => app.resolve_selected_stale_entry(false),
+        Action::NextEntry => app.change_selected_stale_entry(true),
+        Action::PreviousEntry => app.change_selected_stale_entry(false),
+        _ => {}
+    }
+}
+
+/// Handle input while the host editor form is open
+fn handle_input_host_editor(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
+    let Some(editor) = &mut app.host_editor else {
+        return;
+    };
+
+    match key {
+        KeyCode::Esc => app.host_editor = None,
+        KeyCode::Enter => app.save_host_editor(),
+        KeyCode::Tab | KeyCode::Down => editor.focus_next(true),
+        KeyCode::BackTab | KeyCode::Up => editor.focus_next(false),
+        KeyCode::Backspace => editor.del_char(),
+        KeyCode::Char(c) if !modifiers.intersects(KeyModifiers::ALT | KeyModifiers::CONTROL) => {
+            editor.add_char(c);
+        }
+        _ => {}
+    }
+}
+
+/// Handle the answer to the host deletion prompt
+fn handle_input_delete_confirmation(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Char('y') | KeyCode::Enter => app.confirm_delete(),
         _ => {
-            // Handle common vim-like navigation
-            handle_vim_navigation(app, key, modifiers);
+            app.pending_delete = None;
+            app.status_message = None;
         }
     }
 }
 
 /// Handle input in search mode
-fn handle_input_search_mode(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
-    // Exit search mode
-    if matches!(key, KeyCode::Esc | KeyCode::Char('q')) {
+fn handle_input_search_mode(app: &mut App, chord: KeyChord) {
+    // Exit search mode, even while typing
+    let bindings = &get_config().keybindings;
+    if bindings.lookup(KeyContext::Search, &[chord]) == Lookup::Action(Action::ExitSearch) {
         app.searcher.clear_search();
         app.state = AppState::Normal;
+        app.pending_keys.clear();
         return;
     }
 
     // Handle Enter key for search commit or connection
-    if key == KeyCode::Enter {
+    if chord.code == KeyCode::Enter {
         if !app.searcher.is_committed() {
             app.searcher.commit_search();
             app.host_state.select(Some(0));
-        } else if app.get_selected_item().is_some() {
-            app.should_spawn_ssh = true;
+        } else {
+            launch(app, Launch::Ssh);
         }
-        app.pending_g = false;
+        app.pending_keys.clear();
         return;
     }
 
     // Only allow typing if search is not committed
     if !app.searcher.is_committed() {
-        match key {
-            KeyCode::Char(c) if !modifiers.intersects(KeyModifiers::ALT | KeyModifiers::CONTROL) => {
+        match chord.code {
+            KeyCode::Char(c)
+                if !chord
+                    .modifiers
+                    .intersects(KeyModifiers::ALT | KeyModifiers::CONTROL) =>
+            {
                 app.searcher.add_char(c);
             }
             KeyCode::Backspace => {
@@ -111,68 +366,23 @@ fn handle_input_search_mode(app: &mut App, key: KeyCode, modifiers: KeyModifiers
         }
     } else {
         // When search is committed, handle navigation
-        handle_search_mode_navigation(app, key, modifiers);
-    }
-}
-
-/// Handle navigation when search is committed
-#[inline]
-fn handle_search_mode_navigation(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
-    match key {
-        // Arrow keys always work
-        KeyCode::Down => app.change_selected_item(true),
-        KeyCode::Up => app.change_selected_item(false),
-        
-        // Alt+j/k for navigation
-        KeyCode::Char('j') if modifiers.contains(KeyModifiers::ALT) => {
-            app.change_selected_item(true);
-        }
-        KeyCode::Char('k') if modifiers.contains(KeyModifiers::ALT) => {
-            app.change_selected_item(false);
-        }
-        
-        // n/N for next/previous (vim-style)
-        KeyCode::Char('n') => app.change_selected_item(true),
-        KeyCode::Char('N') => app.change_selected_item(false),
-        
-        _ => {
-            // Handle common vim-like navigation
-            handle_vim_navigation(app, key, modifiers);
+        match pressed_action(app, KeyContext::Search, chord) {
+            Some(PaletteEntry::Action(Action::NextMatch)) => app.change_selected_item(true),
+            Some(PaletteEntry::Action(Action::PreviousMatch)) => app.change_selected_item(false),
+            Some(PaletteEntry::Action(action)) => run_action(app, action),
+            _ => {}
         }
     }
 }
 
-/// Handle vim-like navigation common to both modes
+/// Handle the navigation actions common to both modes
 #[inline]
-fn handle_vim_navigation(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
-    match key {
-        // gg to go to top
-        KeyCode::Char('g') => {
-            if app.pending_g {
-                app.jump_to_first_item();
-                app.pending_g = false;
-            } else {
-                app.pending_g = true;
-            }
-        }
-        // G to go to bottom
-        KeyCode::Char('G') => {
-            app.jump_to_last_item();
-            app.pending_g = false;
-        }
-        // Ctrl+d for half-page down
-        KeyCode::Char('d') if modifiers.contains(KeyModifiers::CONTROL) => {
-            app.scroll_half_page(true);
-            app.pending_g = false;
-        }
-        // Ctrl+u for half-page up
-        KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => {
-            app.scroll_half_page(false);
-            app.pending_g = false;
-        }
-        _ => {
-            // Reset pending_g on any other key
-            app.pending_g = false;
-        }
+fn handle_common_navigation(app: &mut App, action: Action) {
+    match action {
+        Action::FirstHost => app.jump_to_first_item(),
+        Action::LastHost => app.jump_to_last_item(),
+        Action::HalfPageDown => app.scroll_half_page(true),
+        Action::HalfPageUp => app.scroll_half_page(false),
+        _ => {}
     }
 }
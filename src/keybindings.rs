@@ -0,0 +1,690 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::actions::CustomAction;
+
+/// Where a binding is active: in the groups view, once a search is committed
+/// or in one of the panels listing tunnels and stale entries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyContext {
+    Normal,
+    Search,
+    Tunnels,
+    Prune,
+}
+
+impl KeyContext {
+    const ALL: [KeyContext; 4] = [
+        KeyContext::Normal,
+        KeyContext::Search,
+        KeyContext::Tunnels,
+        KeyContext::Prune,
+    ];
+}
+
+/// Headings of the shortcuts panel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Section {
+    General,
+    Navigation,
+    Actions,
+    Edition,
+    Search,
+    Panels,
+}
+
+impl Section {
+    pub const ALL: [Section; 6] = [
+        Section::General,
+        Section::Navigation,
+        Section::Actions,
+        Section::Edition,
+        Section::Search,
+        Section::Panels,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Section::General => "General",
+            Section::Navigation => "Navigation",
+            Section::Actions => "Actions",
+            Section::Edition => "Edition",
+            Section::Search => "Search Mode",
+            Section::Panels => "Panels",
+        }
+    }
+}
+
+macro_rules! def_actions {
+    ($($variant:ident => $name:literal, $section:ident, $description:literal, [$($key:literal),*]);+ $(;)?) => {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub enum Action {
+            $( $variant, )+
+        }
+
+        impl Action {
+            pub const ALL: &'static [Action] = &[$( Action::$variant, )+];
+
+            /// Name used in the `keybindings` section of the config.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $( Action::$variant => $name, )+
+                }
+            }
+
+            pub fn description(&self) -> &'static str {
+                match self {
+                    $( Action::$variant => $description, )+
+                }
+            }
+
+            pub fn section(&self) -> Section {
+                match self {
+                    $( Action::$variant => Section::$section, )+
+                }
+            }
+
+            fn default_keys(&self) -> &'static [&'static str] {
+                match self {
+                    $( Action::$variant => &[$( $key ),*], )+
+                }
+            }
+        }
+    };
+}
+
+def_actions!(
+    ToggleHelp => "toggle_help", General, "Toggle Shortcuts Panel", ["?"];
+    CommandPalette => "command_palette", General, "Open Command Palette", [":"];
+    Quit => "quit", General, "Exit Fast-SSH", ["q"];
+    NextGroup => "next_group", Navigation, "Next Group", ["tab", "right", "l"];
+    PreviousGroup => "previous_group", Navigation, "Previous Group", ["shift+tab", "left", "h"];
+    SelectRecents => "select_recents", Navigation, "Select Recents Group", ["space"];
+    SelectFavorites => "select_favorites", Navigation, "Select Favorites Group", ["F"];
+    ToggleGroup => "toggle_group", Navigation, "Expand or Collapse Group", ["o"];
+    ToggleGroupTree => "toggle_group_tree", Navigation, "Toggle Group Tree", ["t"];
+    NextHost => "next_host", Navigation, "Next Host", ["down", "j"];
+    PreviousHost => "previous_host", Navigation, "Previous Host", ["up", "k"];
+    FirstHost => "first_host", Navigation, "Jump to First Host", ["g g"];
+    LastHost => "last_host", Navigation, "Jump to Last Host", ["G"];
+    HalfPageDown => "half_page_down", Navigation, "Scroll Half Page Down", ["ctrl+d"];
+    HalfPageUp => "half_page_up", Navigation, "Scroll Half Page Up", ["ctrl+u"];
+    Connect => "connect", Actions, "Open Selected SSH Connection", ["enter"];
+    CopySshKey => "copy_ssh_key", Actions, "Copy SSH Key and Exit", ["K"];
+    CopyFiles => "copy_files", Actions, "Copy Files and Exit", ["C"];
+    ToggleConfigMode => "toggle_config_mode", Actions, "Switch Config Display Mode", ["c"];
+    ToggleHistory => "toggle_history", Actions, "Toggle Connection History", ["H"];
+    ToggleFavorite => "toggle_favorite", Actions, "Toggle Favorite", ["*"];
+    MoveFavoriteUp => "move_favorite_up", Actions, "Move Favorite Up", ["alt+up", "alt+k"];
+    MoveFavoriteDown => "move_favorite_down", Actions, "Move Favorite Down", ["alt+down", "alt+j"];
+    ScrollConfigDown => "scroll_config_down", Actions, "Scroll Configuration Down", ["pagedown"];
+    ScrollConfigUp => "scroll_config_up", Actions, "Scroll Configuration Up", ["pageup"];
+    ToggleSelection => "toggle_selection", Actions, "Toggle Selection", ["x"];
+    SelectAll => "select_all", Actions, "Select All Listed", ["X"];
+    RunCommand => "run_command", Actions, "Run Command on Selection", ["!"];
+    ToggleRunResults => "toggle_run_results", Actions, "Toggle Command Results", ["R"];
+    ExportRunResults => "export_run_results", Actions, "Export Command Results", ["E"];
+    Tunnels => "tunnels", Actions, "Manage Tunnels", ["T"];
+    PruneDatabase => "prune_database", Actions, "Clean Up Database", ["P"];
+    AddHost => "add_host", Edition, "Add Host", ["a"];
+    EditHost => "edit_host", Edition, "Edit Selected Host", ["e"];
+    DuplicateHost => "duplicate_host", Edition, "Duplicate Selected Host", ["y"];
+    DeleteHost => "delete_host", Edition, "Delete Selected Host", ["D"];
+    Search => "search", Search, "Enable Search Mode", ["s", "/"];
+    ExitSearch => "exit_search", Search, "Exit Search Mode", ["esc", "q"];
+    NextMatch => "next_match", Search, "Next Match (after commit)", ["down", "n", "alt+j"];
+    PreviousMatch => "previous_match", Search, "Previous Match (after commit)", ["up", "N", "alt+k"];
+    ClosePanel => "close_panel", Panels, "Close Panel", ["esc", "q"];
+    NextEntry => "next_entry", Panels, "Next Entry", ["down", "j"];
+    PreviousEntry => "previous_entry", Panels, "Previous Entry", ["up", "k"];
+    ToggleTunnel => "toggle_tunnel", Panels, "Start or Stop Tunnel", ["enter", "space"];
+    MergeStaleEntry => "merge_stale_entry", Panels, "Merge Entry into Suggested Host", ["enter", "r"];
+    DeleteStaleEntry => "delete_stale_entry", Panels, "Delete Stale Entry", ["delete", "d"];
+);
+
+impl Action {
+    pub fn contexts(&self) -> &'static [KeyContext] {
+        match self {
+            Action::FirstHost | Action::LastHost | Action::HalfPageDown | Action::HalfPageUp => {
+                &[KeyContext::Normal, KeyContext::Search]
+            }
+            Action::ToggleSelection | Action::SelectAll | Action::RunCommand => {
+                &[KeyContext::Normal, KeyContext::Search]
+            }
+            Action::ExitSearch | Action::NextMatch | Action::PreviousMatch => &[KeyContext::Search],
+            // The key opening a panel also closes it
+            Action::Tunnels => &[KeyContext::Normal, KeyContext::Tunnels],
+            Action::PruneDatabase => &[KeyContext::Normal, KeyContext::Prune],
+            Action::ClosePanel | Action::NextEntry | Action::PreviousEntry => {
+                &[KeyContext::Tunnels, KeyContext::Prune]
+            }
+            Action::ToggleTunnel => &[KeyContext::Tunnels],
+            Action::MergeStaleEntry | Action::DeleteStaleEntry => &[KeyContext::Prune],
+            _ => &[KeyContext::Normal],
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+}
+
+/// A key along with its modifiers, e.g. `ctrl+d`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        // Shift is already part of the character (`K`) or of the key (BackTab)
+        let code = match code {
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            code => code,
+        };
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+
+        KeyChord { code, modifiers }
+    }
+
+    fn parse(input: &str) -> Result<KeyChord, String> {
+        let (modifier_names, key) = match input.strip_suffix("++") {
+            Some(modifier_names) => (modifier_names, "+"),
+            None if input == "+" => ("", "+"),
+            None => input.rsplit_once('+').unwrap_or(("", input)),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("Unknown modifier `{}` in `{}`", name, input)),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "enter" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("Unknown key `{}` in `{}`", key, input)),
+                },
+            },
+        };
+
+        Ok(KeyChord::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(event: KeyEvent) -> KeyChord {
+        KeyChord::new(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Keys to press one after the other, e.g. `g g`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySequence(pub Vec<KeyChord>);
+
+impl KeySequence {
+    fn parse(input: &str) -> Result<KeySequence, String> {
+        let chords = input
+            .split_whitespace()
+            .map(KeyChord::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if chords.is_empty() {
+            return Err("Empty key binding".to_string());
+        }
+
+        Ok(KeySequence(chords))
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chords: Vec<String> = self.0.iter().map(KeyChord::to_string).collect();
+        write!(f, "{}", chords.join(" "))
+    }
+}
+
+/// Result of looking up the keys pressed so far.
+#[derive(Debug, PartialEq)]
+pub enum Lookup {
+    Action(Action),
+    /// Index of the action in the `actions` section of the config
+    Custom(usize),
+    /// The keys start a longer binding, wait for the next one
+    Pending,
+    None,
+}
+
+/// One key or a list of keys, as written in the config.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Active bindings: the defaults, overridden per action by the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "BTreeMap<String, KeyList>")]
+pub struct KeyBindings {
+    bindings: Vec<(Action, Vec<KeySequence>)>,
+    /// Keys of the custom actions, along with their index and name
+    custom: Vec<(usize, String, KeySequence)>,
+}
+
+/// What a binding triggers.
+#[derive(Debug, Clone, Copy)]
+enum Target<'a> {
+    Action(Action),
+    Custom(usize, &'a str),
+}
+
+impl Target<'_> {
+    fn name(&self) -> &str {
+        match self {
+            Target::Action(action) => action.name(),
+            Target::Custom(_, name) => name,
+        }
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        let bindings = Action::ALL
+            .iter()
+            .map(|action| {
+                let keys = action
+                    .default_keys()
+                    .iter()
+                    .filter_map(|key| KeySequence::parse(key).ok())
+                    .collect();
+                (*action, keys)
+            })
+            .collect();
+
+        KeyBindings {
+            bindings,
+            custom: Vec::new(),
+        }
+    }
+}
+
+impl TryFrom<BTreeMap<String, KeyList>> for KeyBindings {
+    type Error = String;
+
+    fn try_from(overrides: BTreeMap<String, KeyList>) -> Result<KeyBindings, String> {
+        let mut key_bindings = KeyBindings::default();
+        let mut errors = Vec::new();
+
+        for (name, keys) in overrides {
+            let Some(action) = Action::from_name(&name) else {
+                errors.push(format!("Unknown action `{}`", name));
+                continue;
+            };
+
+            let keys = match keys {
+                KeyList::One(key) => vec![key],
+                KeyList::Many(keys) => keys,
+            };
+
+            let mut sequences = Vec::new();
+            for key in keys {
+                match KeySequence::parse(&key) {
+                    Ok(sequence) => sequences.push(sequence),
+                    Err(error) => errors.push(format!("{} for `{}`", error, name)),
+                }
+            }
+
+            if let Some(binding) = key_bindings.bindings.iter_mut().find(|b| b.0 == action) {
+                binding.1 = sequences;
+            }
+        }
+
+        errors.extend(key_bindings.conflicts());
+
+        match errors.is_empty() {
+            true => Ok(key_bindings),
+            false => Err(format!("Invalid keybindings: {}", errors.join(", "))),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn keys(&self, action: Action) -> &[KeySequence] {
+        self.bindings
+            .iter()
+            .find(|binding| binding.0 == action)
+            .map_or(&[], |binding| binding.1.as_slice())
+    }
+
+    /// Binds the keys of the custom actions, returns the invalid or
+    /// conflicting ones.
+    pub fn add_custom_actions(&mut self, actions: &[CustomAction]) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for (idx, action) in actions.iter().enumerate() {
+            let Some(key) = &action.key else {
+                continue;
+            };
+
+            match KeySequence::parse(key) {
+                Ok(sequence) => self.custom.push((idx, action.name.clone(), sequence)),
+                Err(error) => errors.push(format!("{} for `{}`", error, action.name)),
+            }
+        }
+
+        errors.extend(self.conflicts());
+        errors
+    }
+
+    /// Key of the custom action at the given index, if it has one.
+    pub fn custom_key(&self, idx: usize) -> Option<&KeySequence> {
+        self.custom
+            .iter()
+            .find(|(custom_idx, _, _)| *custom_idx == idx)
+            .map(|(_, _, sequence)| sequence)
+    }
+
+    /// Keys of the action for display, e.g. `s or /`.
+    pub fn describe(&self, action: Action) -> String {
+        let keys: Vec<String> = self
+            .keys(action)
+            .iter()
+            .map(KeySequence::to_string)
+            .collect();
+        keys.join(" or ")
+    }
+
+    pub fn lookup(&self, context: KeyContext, pressed: &[KeyChord]) -> Lookup {
+        let mut lookup = Lookup::None;
+
+        for (target, sequence) in self.active_bindings(context) {
+            if sequence.0 == pressed {
+                return match target {
+                    Target::Action(action) => Lookup::Action(action),
+                    Target::Custom(idx, _) => Lookup::Custom(idx),
+                };
+            }
+            if sequence.0.starts_with(pressed) {
+                lookup = Lookup::Pending;
+            }
+        }
+
+        lookup
+    }
+
+    fn active_bindings(&self, context: KeyContext) -> Vec<(Target<'_>, &KeySequence)> {
+        let actions = self
+            .bindings
+            .iter()
+            .filter(|(action, _)| action.contexts().contains(&context))
+            .flat_map(|(action, keys)| keys.iter().map(|key| (Target::Action(*action), key)));
+
+        // Custom actions run on the selected host of the hosts list
+        let custom = self
+            .custom
+            .iter()
+            .filter(|_| context == KeyContext::Normal)
+            .map(|(idx, name, key)| (Target::Custom(*idx, name), key));
+
+        actions.chain(custom).collect()
+    }
+
+    /// Bindings sharing the same keys, or shadowing a longer sequence, in a
+    /// given context.
+    fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+
+        for context in KeyContext::ALL {
+            let bindings = self.active_bindings(context);
+
+            for (idx, (action, sequence)) in bindings.iter().enumerate() {
+                for (other_action, other_sequence) in &bindings[idx + 1..] {
+                    let conflict = if sequence == other_sequence {
+                        format!(
+                            "`{}` is bound to both `{}` and `{}`",
+                            sequence,
+                            action.name(),
+                            other_action.name()
+                        )
+                    } else if other_sequence.0.starts_with(&sequence.0)
+                        || sequence.0.starts_with(&other_sequence.0)
+                    {
+                        format!(
+                            "`{}` of `{}` and `{}` of `{}` start with the same keys",
+                            sequence,
+                            action.name(),
+                            other_sequence,
+                            other_action.name()
+                        )
+                    } else {
+                        continue;
+                    };
+
+                    // Actions active in both contexts would be reported twice
+                    if !conflicts.contains(&conflict) {
+                        conflicts.push(conflict);
+                    }
+                }
+            }
+        }
+
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord::new(code, modifiers)
+    }
+
+    fn parse_bindings(yaml: &str) -> Result<KeyBindings, String> {
+        serde_yaml::from_str::<KeyBindings>(yaml).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_default_bindings_have_no_conflicts() {
+        assert_eq!(KeyBindings::default().conflicts(), Vec::<String>::new());
+        for action in Action::ALL {
+            assert_eq!(
+                KeyBindings::default().keys(*action).len(),
+                action.default_keys().len(),
+                "invalid default key for `{}`",
+                action.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_chords() {
+        assert_eq!(
+            KeyChord::parse("ctrl+d"),
+            Ok(chord(KeyCode::Char('d'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            KeyChord::parse("shift+k"),
+            Ok(chord(KeyCode::Char('K'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            KeyChord::parse("Shift+Tab"),
+            Ok(chord(KeyCode::BackTab, KeyModifiers::NONE))
+        );
+        assert_eq!(
+            KeyChord::parse("alt++"),
+            Ok(chord(KeyCode::Char('+'), KeyModifiers::ALT))
+        );
+        assert_eq!(
+            KeyChord::parse("F5"),
+            Ok(chord(KeyCode::F(5), KeyModifiers::NONE))
+        );
+        assert!(KeyChord::parse("hyper+x").is_err());
+        assert!(KeyChord::parse("enterr").is_err());
+    }
+
+    #[test]
+    fn test_key_events_are_normalized() {
+        // Terminals report uppercase letters and BackTab along with Shift
+        assert_eq!(
+            KeyChord::new(KeyCode::Char('K'), KeyModifiers::SHIFT),
+            KeyChord::parse("K").unwrap()
+        );
+        assert_eq!(
+            KeyChord::new(KeyCode::BackTab, KeyModifiers::SHIFT),
+            KeyChord::parse("backtab").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let bindings = KeyBindings::default();
+        assert_eq!(bindings.describe(Action::FirstHost), "g g");
+        assert_eq!(bindings.describe(Action::Search), "s or /");
+        assert_eq!(bindings.describe(Action::HalfPageDown), "Ctrl+d");
+        assert_eq!(
+            bindings.describe(Action::PreviousGroup),
+            "Shift+Tab or Left or h"
+        );
+        assert_eq!(bindings.describe(Action::SelectRecents), "Space");
+    }
+
+    #[test]
+    fn test_lookup_sequences() {
+        let bindings = KeyBindings::default();
+        let g = chord(KeyCode::Char('g'), KeyModifiers::NONE);
+
+        assert_eq!(bindings.lookup(KeyContext::Normal, &[g]), Lookup::Pending);
+        assert_eq!(
+            bindings.lookup(KeyContext::Normal, &[g, g]),
+            Lookup::Action(Action::FirstHost)
+        );
+        assert_eq!(
+            bindings.lookup(KeyContext::Search, &[g, g]),
+            Lookup::Action(Action::FirstHost)
+        );
+        assert_eq!(
+            bindings.lookup(
+                KeyContext::Normal,
+                &[chord(KeyCode::Char('n'), KeyModifiers::NONE)]
+            ),
+            Lookup::None
+        );
+        assert_eq!(
+            bindings.lookup(
+                KeyContext::Search,
+                &[chord(KeyCode::Char('n'), KeyModifiers::NONE)]
+            ),
+            Lookup::Action(Action::NextMatch)
+        );
+
+        // Panels have their own meaning for the keys
+        let enter = chord(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(
+            bindings.lookup(KeyContext::Tunnels, &[enter]),
+            Lookup::Action(Action::ToggleTunnel)
+        );
+        assert_eq!(
+            bindings.lookup(KeyContext::Prune, &[enter]),
+            Lookup::Action(Action::MergeStaleEntry)
+        );
+        assert_eq!(
+            bindings.lookup(
+                KeyContext::Tunnels,
+                &[chord(KeyCode::Char('T'), KeyModifiers::NONE)]
+            ),
+            Lookup::Action(Action::Tunnels)
+        );
+    }
+
+    #[test]
+    fn test_overrides_replace_defaults() {
+        let bindings =
+            parse_bindings("copy_ssh_key: ctrl+k\nsearch: [\"/\", \"f\"]\nquit: []").unwrap();
+
+        assert_eq!(bindings.describe(Action::CopySshKey), "Ctrl+k");
+        assert_eq!(bindings.describe(Action::Search), "/ or f");
+        assert!(bindings.keys(Action::Quit).is_empty());
+        assert_eq!(bindings.describe(Action::Connect), "Enter");
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let error = parse_bindings("launch_rockets: x").unwrap_err();
+        assert!(error.contains("Unknown action `launch_rockets`"));
+
+        let error = parse_bindings("search: ctrl+nope").unwrap_err();
+        assert!(error.contains("Unknown key `nope` in `ctrl+nope` for `search`"));
+
+        let error = parse_bindings("copy_files: K").unwrap_err();
+        assert!(error.contains("`K` is bound to both `copy_ssh_key` and `copy_files`"));
+
+        let error = parse_bindings("last_host: g").unwrap_err();
+        assert!(
+            error.contains("`g g` of `first_host` and `g` of `last_host` start with the same keys")
+        );
+
+        // Bindings of different contexts do not conflict
+        assert!(parse_bindings("exit_search: q\nquit: q").is_ok());
+    }
+}
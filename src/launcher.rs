@@ -0,0 +1,274 @@
+use anyhow::{format_err, Result};
+use ssh_cfg::SshOptionKey;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::actions;
+use crate::app::Launch;
+use fast_ssh::database::HistoryEntry;
+use fast_ssh::{FileDatabase, SshGroupItem};
+
+// Options given to ssh so a dead connection is noticed
+const SSH_CONNECT_TIMEOUT: &str = "ConnectTimeout=10";
+const SSH_KEEP_ALIVE_INTERVAL: &str = "ServerAliveInterval=5";
+
+/// How a launched command ended.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchOutcome {
+    /// `None` if the command was terminated by a signal
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+    /// Command line recorded in the history
+    pub command_line: String,
+}
+
+impl LaunchOutcome {
+    /// Message for the status line, e.g. `ssh prod/web: exited with code 255`.
+    pub fn describe(&self, name: &str, host_name: &str) -> String {
+        match self.exit_code {
+            Some(0) => format!("{} {}: exited successfully", name, host_name),
+            Some(code) => format!("{} {}: exited with code {}", name, host_name, code),
+            None => format!("{} {}: terminated by a signal", name, host_name),
+        }
+    }
+}
+
+/// Runs ssh, sftp, ssh-copy-id or a custom action against a host.
+pub trait Launcher {
+    fn launch(&mut self, host: &SshGroupItem, launch: Launch) -> Result<LaunchOutcome>;
+}
+
+/// Runs the commands as child processes and waits for them to exit.
+pub struct ProcessLauncher {
+    /// SSH config given on the command line, passed on to the commands
+    ssh_config_path: Option<PathBuf>,
+}
+
+impl ProcessLauncher {
+    pub fn new(ssh_config_path: Option<PathBuf>) -> ProcessLauncher {
+        ProcessLauncher { ssh_config_path }
+    }
+}
+
+impl Launcher for ProcessLauncher {
+    fn launch(&mut self, host: &SshGroupItem, launch: Launch) -> Result<LaunchOutcome> {
+        let mut command = build_command(host, launch, self.ssh_config_path.as_deref())?;
+        let command_line = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let started_at = Instant::now();
+        let status = command
+            .spawn()
+            .map_err(|e| format_err!("Could not run {}: {}", launch.name(), e))?
+            .wait()?;
+
+        Ok(LaunchOutcome {
+            exit_code: status.code(),
+            duration: started_at.elapsed(),
+            command_line,
+        })
+    }
+}
+
+/// Builds the process of the launch. Custom actions that are not
+/// interactive get no terminal.
+pub fn build_command(
+    host: &SshGroupItem,
+    launch: Launch,
+    ssh_config_path: Option<&Path>,
+) -> Result<Command> {
+    if let Launch::Custom(idx) = launch {
+        let action = launch
+            .custom_action()
+            .ok_or_else(|| format_err!("Unknown action #{}", idx))?;
+        let mut command = actions::build_command(&action, host);
+
+        if !action.interactive {
+            command
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null());
+        }
+        return Ok(command);
+    }
+
+    let mut command = Command::new(launch.name());
+    command.args(launch_args(host, launch, ssh_config_path));
+    Ok(command)
+}
+
+/// Arguments of ssh, sftp and ssh-copy-id, ending with the name of the host.
+pub fn launch_args(
+    host: &SshGroupItem,
+    launch: Launch,
+    ssh_config_path: Option<&Path>,
+) -> Vec<OsString> {
+    let options: &[&str] = match launch {
+        Launch::Ssh => &["-o", SSH_CONNECT_TIMEOUT, "-o", SSH_KEEP_ALIVE_INTERVAL],
+        _ => &[],
+    };
+
+    ssh_args(host, options, ssh_config_path)
+}
+
+/// Arguments of any ssh command line: the SSH config given on the command
+/// line, the options, then the name of the host.
+pub fn ssh_args<S: AsRef<OsStr>>(
+    host: &SshGroupItem,
+    options: &[S],
+    ssh_config_path: Option<&Path>,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = Vec::new();
+
+    // Use the same SSH config as the TUI when it was given on the command line
+    if let Some(ssh_config_path) = ssh_config_path {
+        args.push("-F".into());
+        args.push(ssh_config_path.into());
+    }
+
+    args.extend(options.iter().map(|option| option.as_ref().to_owned()));
+    args.push(host.ssh_name().into());
+    args
+}
+
+/// Runs the launch, then counts the connection in the database and adds it
+/// to the history. Nothing is recorded when the command could not be run.
+pub fn launch_and_record(
+    launcher: &mut dyn Launcher,
+    db: &FileDatabase,
+    host: &SshGroupItem,
+    launch: Launch,
+) -> Result<LaunchOutcome> {
+    let started_at = chrono::offset::Local::now().timestamp();
+    let outcome = launcher.launch(host, launch)?;
+
+    db.record_connection(
+        &host.full_name,
+        host.host_config
+            .get(&SshOptionKey::Hostname)
+            .map(String::as_str),
+        started_at,
+    )?;
+
+    db.add_history_entry(HistoryEntry {
+        host_key: host.full_name.clone(),
+        action: launch.name().to_string(),
+        started_at,
+        ended_at: started_at + outcome.duration.as_secs() as i64,
+        exit_code: outcome.exit_code,
+        command_line: outcome.command_line.clone(),
+    })?;
+
+    Ok(outcome)
+}
+
+/// Records the launches instead of running them, for tests.
+#[cfg(test)]
+#[derive(Default)]
+pub struct RecordingLauncher {
+    /// Host and launch of each call
+    pub launches: Vec<(String, Launch)>,
+    pub exit_code: Option<i32>,
+    /// Fails as if the command could not be spawned
+    pub fail: bool,
+}
+
+#[cfg(test)]
+impl Launcher for RecordingLauncher {
+    fn launch(&mut self, host: &SshGroupItem, launch: Launch) -> Result<LaunchOutcome> {
+        self.launches.push((host.full_name.clone(), launch));
+        if self.fail {
+            return Err(format_err!("Could not run {}: not found", launch.name()));
+        }
+
+        Ok(LaunchOutcome {
+            exit_code: self.exit_code,
+            duration: Duration::from_secs(60),
+            command_line: format!("{} {}", launch.name(), host.ssh_name()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fast_ssh::database::HistoryFilter;
+
+    fn test_item(full_name: &str) -> SshGroupItem {
+        SshGroupItem::new(full_name, &[(SshOptionKey::Hostname, "10.0.0.1")])
+    }
+
+    #[test]
+    fn test_launch_args() {
+        let item = test_item("prod/web web.example.com");
+
+        assert_eq!(
+            launch_args(&item, Launch::Ssh, None),
+            vec![
+                "-o",
+                SSH_CONNECT_TIMEOUT,
+                "-o",
+                SSH_KEEP_ALIVE_INTERVAL,
+                "prod/web"
+            ]
+        );
+        assert_eq!(
+            launch_args(&item, Launch::CopyFiles, Some(Path::new("/tmp/my config"))),
+            vec!["-F", "/tmp/my config", "prod/web"]
+        );
+
+        let command = build_command(&item, Launch::CopySshKey, None).unwrap();
+        assert_eq!(command.get_program(), "ssh-copy-id");
+    }
+
+    #[test]
+    fn test_launch_and_record() {
+        let db = FileDatabase::in_memory().unwrap();
+        let item = test_item("prod/web");
+        let mut launcher = RecordingLauncher {
+            exit_code: Some(255),
+            ..Default::default()
+        };
+
+        let outcome = launch_and_record(&mut launcher, &db, &item, Launch::Ssh).unwrap();
+        assert_eq!(outcome.exit_code, Some(255));
+        assert_eq!(
+            outcome.describe("ssh", "prod/web"),
+            "ssh prod/web: exited with code 255"
+        );
+        assert_eq!(
+            launcher.launches,
+            vec![("prod/web".to_string(), Launch::Ssh)]
+        );
+
+        assert_eq!(db.get_host_values("prod/web").unwrap().connection_count, 1);
+        let history = db.get_history(&HistoryFilter::default()).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].exit_code, Some(255));
+        assert_eq!(history[0].duration(), 60);
+        assert_eq!(history[0].command_line, "ssh prod/web");
+    }
+
+    #[test]
+    fn test_failed_launch_is_not_recorded() {
+        let db = FileDatabase::in_memory().unwrap();
+        let item = test_item("prod/web");
+        let mut launcher = RecordingLauncher {
+            fail: true,
+            ..Default::default()
+        };
+
+        assert!(launch_and_record(&mut launcher, &db, &item, Launch::Ssh).is_err());
+        assert_eq!(launcher.launches.len(), 1);
+        assert_eq!(db.get_host_values("prod/web").unwrap().connection_count, 0);
+        assert!(db
+            .get_history(&HistoryFilter::default())
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,7 +1,5 @@
-use std::io::Stdout;
-
 use tui::{
-    backend::CrosstermBackend,
+    backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
     Frame,
 };
@@ -10,14 +8,16 @@ use crate::app::App;
 
 pub struct AppLayout {
     pub groups_area: Rect,
+    pub group_tree_area: Option<Rect>,
     pub help_area: Rect,
     pub hosts_area: Rect,
     pub config_area: Rect,
     pub shortcuts_area: Option<Rect>,
+    pub status_area: Rect,
     pub version_area: Rect,
 }
 
-pub fn create_layout(app: &App, frame: &mut Frame<CrosstermBackend<Stdout>>) -> AppLayout {
+pub fn create_layout<B: Backend>(app: &App, frame: &mut Frame<B>) -> AppLayout {
     let base_chunk = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
@@ -61,6 +61,11 @@ pub fn create_layout(app: &App, frame: &mut Frame<CrosstermBackend<Stdout>>) ->
         }
     };
 
+    let chunks_status = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(10)].as_ref())
+        .split(base_chunk[2]);
+
     let chunks_bot = Layout::default()
         .direction(Direction::Horizontal)
         .margin(1)
@@ -68,16 +73,30 @@ pub fn create_layout(app: &App, frame: &mut Frame<CrosstermBackend<Stdout>>) ->
         .constraints(constraints.as_slice())
         .split(base_chunk[1]);
 
+    // The group tree takes the left part of the hosts area
+    let (group_tree_area, hosts_area) = match app.show_group_tree {
+        true => {
+            let chunks_hosts = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(35), Constraint::Min(0)].as_ref())
+                .split(chunks_bot[0]);
+            (Some(chunks_hosts[0]), chunks_hosts[1])
+        }
+        false => (None, chunks_bot[0]),
+    };
+
     AppLayout {
         groups_area: chunks_top[0],
+        group_tree_area,
         help_area: chunks_top[2],
-        hosts_area: chunks_bot[0],
+        hosts_area,
         config_area: chunks_bot[2],
         shortcuts_area: if app.show_help {
             Some(chunks_bot[4])
         } else {
             None
         },
-        version_area: base_chunk[2],
+        status_area: chunks_status[0],
+        version_area: chunks_status[1],
     }
 }
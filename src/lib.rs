@@ -0,0 +1,29 @@
+//! Hosts of the SSH config grouped by name, with the connection history and
+//! search used by the `fast-ssh` TUI.
+//!
+//! ```no_run
+//! use fast_ssh::{rank_items, FileDatabase, Query, SshConfigStore};
+//!
+//! let db = FileDatabase::new("/tmp/fast-ssh.ron")?;
+//! let store = SshConfigStore::new(&db, None)?;
+//!
+//! let query = Query::parse("group:prod web");
+//! let now = chrono::offset::Local::now().timestamp();
+//! for result in rank_items(&query, store.items().collect(), now) {
+//!     println!("{}", result.item.full_name);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod database;
+pub mod host_metadata;
+pub mod search;
+pub mod ssh_config_loader;
+pub mod ssh_config_resolver;
+pub mod ssh_config_store;
+pub mod ssh_config_writer;
+pub mod stale_entries;
+
+pub use database::FileDatabase;
+pub use search::{rank_items, Query, SearchResult};
+pub use ssh_config_store::{SshConfigStore, SshGroup, SshGroupItem};
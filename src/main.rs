@@ -1,51 +1,78 @@
-use layout::create_layout;
-use std::process::Command;
-use std::sync::OnceLock;
+use clap::Parser;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+use tui::Terminal;
 
+mod actions;
 mod app;
+mod cli;
+mod command_palette;
+mod commands;
 mod config;
-mod database;
+mod file_watcher;
+mod host_editor;
+mod host_listing;
 mod input_handler;
+mod keybindings;
+mod launcher;
 mod layout;
+mod parallel_run;
+mod pick;
+mod reachability;
 mod searcher;
-mod ssh_config_store;
 mod term;
 mod theme;
+mod tunnels;
+mod ui;
 mod widgets;
 
-use app::{App, AppState};
+use app::{App, AppState, Launch};
+use cli::Cli;
 use config::{resolve_config, Config};
 use input_handler::handle_inputs;
-use term::{init_terminal, restore_terminal};
+use pick::{format_pick, PickField, CANCEL_EXIT_CODE};
+use term::{init_terminal, restore_terminal, TerminalBackend, TerminalOutput};
 use theme::Theme;
-use widgets::{
-    config_widget::ConfigWidget, groups_widget::GroupsWidget, help_widget::HelpWidget,
-    hosts_widget::HostsWidget, shortcuts_widget::ShortcutsWidget, version_widget::VersionWidget,
-};
 
-// SSH connection constants
-const SSH_CONNECT_TIMEOUT: &str = "ConnectTimeout=10";
-const SSH_KEEP_ALIVE_INTERVAL: &str = "ServerAliveInterval=5";
+// Replaced when the configuration file changes, see `set_config`
+static CONFIG: OnceLock<RwLock<Arc<Config>>> = OnceLock::new();
 
-static CONFIG: OnceLock<Config> = OnceLock::new();
+fn init_config(config_file: Option<&Path>) -> &'static RwLock<Arc<Config>> {
+    CONFIG.get_or_init(|| RwLock::new(Arc::new(resolve_config(config_file))))
+}
 
-pub fn get_config() -> &'static Config {
-    CONFIG.get_or_init(resolve_config)
+/// Current configuration, kept alive by the returned handle even if the file
+/// changes meanwhile.
+pub fn get_config() -> Arc<Config> {
+    let config = init_config(None);
+    Arc::clone(&config.read().unwrap_or_else(PoisonError::into_inner))
 }
 
-// Re-export THEME for backwards compatibility
-pub static THEME: OnceLock<Theme> = OnceLock::new();
+/// Replaces the configuration, the previous one is dropped once no longer
+/// used.
+pub fn set_config(new_config: Config) {
+    let config = init_config(None);
+    *config.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(new_config);
+}
 
-pub fn get_theme() -> &'static Theme {
-    THEME.get_or_init(|| get_config().theme)
+pub fn get_theme() -> Theme {
+    get_config().theme
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    // Printed at every start of the shell, so the SSH config is not loaded
+    if let Some(cli::Command::Init { shell }) = &cli.command {
+        print!("{}", shell.snippet());
+        return Ok(());
+    }
+
     // Initialize configuration and theme
-    get_theme();
+    init_config(cli.app_config.as_deref());
 
-    let mut app = match App::new().await {
+    let mut app = match App::new(&cli.app_options()) {
         Ok(app) => app,
         Err(e) => {
             eprintln!("{}", e);
@@ -53,89 +80,122 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let mut terminal = init_terminal()?;
+    let pick = match &cli.command {
+        Some(cli::Command::Pick { print }) => Some(*print),
+        Some(command) => {
+            let exit_code = commands::run(&mut app, command)?;
+            // Exiting skips the destructors, the database must be released first
+            drop(app);
+            std::process::exit(exit_code);
+        }
+        None => None,
+    };
 
     app.host_state.select(Some(0));
 
-    loop {
-        terminal.draw(|frame| {
-            let layout = create_layout(&app, frame);
+    if let Some(group) = &cli.group {
+        if !app.select_group_by_name(group) {
+            eprintln!("Error: Unknown group '{}'", group);
+            std::process::exit(1);
+        }
+    }
 
-            match app.state {
-                AppState::Normal => GroupsWidget::render(&app, layout.groups_area, frame),
-                AppState::Searching => app.searcher.render(&app, layout.groups_area, frame),
-            };
+    if let Some(search) = &cli.search {
+        app.state = AppState::Searching;
+        app.searcher.set_search(search);
+    }
 
-            HelpWidget::render(&app, layout.help_area, frame);
-            HostsWidget::render(&mut app, layout.hosts_area, frame);
-            ConfigWidget::render(&app, layout.config_area, frame);
-            VersionWidget::render(&app, layout.version_area, frame);
+    if let Some(field) = pick {
+        let exit_code = pick_host(&mut app, field)?;
+        // Exiting skips the destructors, the database must be released first
+        drop(app);
+        std::process::exit(exit_code);
+    }
 
-            if let Some(shortcuts_area) = layout.shortcuts_area {
-                ShortcutsWidget::render(&app, shortcuts_area, frame);
-            }
-        })?;
+    let stay_open = cli.stay_open || get_config().stay_open;
 
-        handle_inputs(&mut app)?;
+    loop {
+        let mut terminal = init_terminal(TerminalOutput::stdout())?;
+        run_tui(&mut terminal, &mut app)?;
+        restore_terminal(&mut terminal)?;
 
-        if app.should_quit || app.should_spawn_ssh {
+        let Some(launch) = app.launch else {
             break;
-        }
-    }
-
-    restore_terminal(&mut terminal)?;
-
-    // Execute the command based on the app state
-    let command = if app.should_spawn_ssh {
-        Some("ssh")
-    } else if app.should_copy_ssh_key {
-        Some("ssh-copy-id")
-    } else if app.should_copy_files {
-        Some("sftp")
-    } else {
-        None
-    };
+        };
 
-    if let Some(cmd) = command {
         // Safely get selected config, exit gracefully if none selected
-        let Some(selected_config) = app.get_selected_item() else {
+        let Some(selected_config) = app.get_selected_item().cloned() else {
             eprintln!("Error: No host selected");
             return Ok(());
         };
-        
-        // Assert preconditions
-        debug_assert!(!selected_config.full_name.is_empty(), "host name should not be empty");
-        debug_assert!(selected_config.connection_count >= 0, "connection count should be non-negative");
-        
-        let host_name = &selected_config.full_name;
-
-        // Update database with connection info
-        app.db.save_host_values(
-            host_name,
-            selected_config.connection_count + 1,
-            chrono::offset::Local::now().timestamp(),
-        )?;
-
-        // Extract the first part of the hostname (before any space)
-        let host_arg = host_name.split_whitespace().next().unwrap_or(host_name);
-        
-        // Assert extracted host is valid
-        debug_assert!(!host_arg.is_empty(), "extracted host should not be empty");
-
-        // Build and execute the command
-        let mut command = Command::new(cmd);
-        
-        // Add SSH-specific options
-        if cmd == "ssh" {
-            command
-                .arg("-o")
-                .arg(SSH_CONNECT_TIMEOUT)
-                .arg("-o")
-                .arg(SSH_KEEP_ALIVE_INTERVAL);
+
+        let host_name = selected_config.full_name.clone();
+        let outcome = app.run_launch(&selected_config, launch);
+
+        let return_to_tui = launch.custom_action().is_some_and(|action| action.return_to_tui);
+        if !stay_open && !return_to_tui {
+            outcome?;
+            break;
         }
-        
-        command.arg(host_arg).spawn()?.wait()?;
+
+        app.reset_launch_flags();
+        // A command that could not be started leaves the TUI open with the error
+        let message = match outcome {
+            Ok(outcome) => outcome.describe(&launch.name(), &host_name),
+            Err(e) => format!("{} {}: {}", launch.name(), host_name, e),
+        };
+
+        app.status_message = Some(match app.reload() {
+            Ok(()) => app.with_store_warnings(message),
+            Err(e) => format!("Failed to reload the SSH config: {}", e),
+        });
     }
 
     Ok(())
 }
+
+/// Draws the TUI on the terminal itself and prints the host picked with
+/// Enter, returns the exit code of the program: `CANCEL_EXIT_CODE` if no
+/// host was picked.
+fn pick_host(app: &mut App, field: PickField) -> Result<i32, Box<dyn std::error::Error>> {
+    loop {
+        let mut terminal = init_terminal(TerminalOutput::tty()?)?;
+        run_tui(&mut terminal, app)?;
+        restore_terminal(&mut terminal)?;
+
+        match app.launch {
+            Some(Launch::Ssh) => break,
+            // Only Enter picks, the other launches make no sense here
+            Some(_) => {
+                app.reset_launch_flags();
+                app.status_message = Some("Press Enter to pick the selected host".to_string());
+            }
+            None => return Ok(CANCEL_EXIT_CODE),
+        }
+    }
+
+    let Some(item) = app.get_selected_item() else {
+        return Ok(CANCEL_EXIT_CODE);
+    };
+
+    println!("{}", format_pick(item, field));
+    Ok(0)
+}
+
+/// Draws the TUI and handles inputs until the user quits or launches a command.
+fn run_tui(
+    terminal: &mut Terminal<TerminalBackend>,
+    app: &mut App,
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        app.check_file_changes();
+
+        terminal.draw(|frame| ui::draw(app, frame))?;
+
+        handle_inputs(app)?;
+
+        if app.should_quit || app.launch.is_some() {
+            return Ok(());
+        }
+    }
+}
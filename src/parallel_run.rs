@@ -0,0 +1,328 @@
+use anyhow::{format_err, Result};
+use serde::Deserialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+
+use crate::launcher;
+use fast_ssh::SshGroupItem;
+
+/// `parallel_run` section of the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ParallelRunConfig {
+    /// Maximum number of hosts the command runs on at the same time
+    pub concurrency: usize,
+    /// Time given to ssh to connect to each host, in seconds
+    pub connect_timeout_secs: u64,
+}
+
+impl Default for ParallelRunConfig {
+    fn default() -> Self {
+        ParallelRunConfig {
+            concurrency: 8,
+            connect_timeout_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunStatus {
+    /// Waiting for a free slot
+    Queued,
+    Running,
+    /// Exit code of ssh, `None` if it was terminated by a signal
+    Finished(Option<i32>),
+    /// ssh could not be started
+    Failed(String),
+}
+
+impl RunStatus {
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        matches!(self, RunStatus::Finished(_) | RunStatus::Failed(_))
+    }
+
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        *self == RunStatus::Finished(Some(0))
+    }
+}
+
+/// Status and output of the command on one host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostRun {
+    pub host: String,
+    pub status: RunStatus,
+    pub output: Vec<(OutputStream, String)>,
+}
+
+/// A command running over ssh on several hosts at once. The output of each
+/// host is collected as it comes.
+pub struct ParallelRun {
+    pub command: String,
+    pub started_at: i64,
+    runs: Arc<Mutex<Vec<HostRun>>>,
+}
+
+impl ParallelRun {
+    /// Starts the command on every host, given with the process to spawn for
+    /// it.
+    pub fn start(
+        command: &str,
+        processes: Vec<(String, Command)>,
+        concurrency: usize,
+    ) -> Result<ParallelRun> {
+        let runtime =
+            Handle::try_current().map_err(|_| format_err!("No runtime to run the command on"))?;
+
+        let runs = Arc::new(Mutex::new(
+            processes
+                .iter()
+                .map(|(host, _)| HostRun {
+                    host: host.clone(),
+                    status: RunStatus::Queued,
+                    output: Vec::new(),
+                })
+                .collect::<Vec<_>>(),
+        ));
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+
+        for (idx, (_, process)) in processes.into_iter().enumerate() {
+            let runs = Arc::clone(&runs);
+            let semaphore = Arc::clone(&semaphore);
+
+            runtime.spawn(async move {
+                let Ok(_permit) = semaphore.acquire().await else {
+                    return;
+                };
+                let status = run_on_host(process, idx, &runs).await;
+                update(&runs, idx, |run| run.status = status);
+            });
+        }
+
+        Ok(ParallelRun {
+            command: command.to_string(),
+            started_at: chrono::offset::Local::now().timestamp(),
+            runs,
+        })
+    }
+
+    /// Copy of the status and output of every host, in the order they were
+    /// given.
+    pub fn runs(&self) -> Vec<HostRun> {
+        self.runs
+            .lock()
+            .map(|runs| runs.clone())
+            .unwrap_or_default()
+    }
+
+    /// Writes the command, then the status and output of every host.
+    pub fn export(&self, path: &Path) -> Result<()> {
+        fs::write(path, format_report(&self.command, &self.runs()))
+            .map_err(|e| format_err!("Could not write {}: {}", path.display(), e))
+    }
+}
+
+/// Builds the ssh process running the command on the host, without a
+/// terminal nor password prompts.
+pub fn ssh_command(
+    host: &SshGroupItem,
+    command: &str,
+    ssh_config_path: Option<&Path>,
+    config: &ParallelRunConfig,
+) -> Command {
+    let connect_timeout = format!("ConnectTimeout={}", config.connect_timeout_secs);
+    let options = ["-o", "BatchMode=yes", "-o", &connect_timeout, "-T"];
+
+    let mut process = Command::new("ssh");
+    process
+        .args(launcher::ssh_args(host, &options, ssh_config_path))
+        .arg(command);
+    process
+}
+
+/// Plain text report of the runs, as exported.
+pub fn format_report(command: &str, runs: &[HostRun]) -> String {
+    let mut report = format!("$ {}\n", command);
+
+    for run in runs {
+        let status = match &run.status {
+            RunStatus::Queued => "not started".to_string(),
+            RunStatus::Running => "still running".to_string(),
+            RunStatus::Finished(Some(code)) => format!("exit {}", code),
+            RunStatus::Finished(None) => "terminated by a signal".to_string(),
+            RunStatus::Failed(error) => format!("failed: {}", error),
+        };
+
+        let _ = writeln!(report, "\n== {} ({})", run.host, status);
+        for (stream, line) in &run.output {
+            match stream {
+                OutputStream::Stdout => report.push_str(line),
+                OutputStream::Stderr => {
+                    let _ = write!(report, "[stderr] {}", line);
+                }
+            }
+            report.push('\n');
+        }
+    }
+
+    report
+}
+
+fn update(runs: &Mutex<Vec<HostRun>>, idx: usize, f: impl FnOnce(&mut HostRun)) {
+    if let Ok(mut runs) = runs.lock() {
+        if let Some(run) = runs.get_mut(idx) {
+            f(run);
+        }
+    }
+}
+
+async fn run_on_host(mut process: Command, idx: usize, runs: &Mutex<Vec<HostRun>>) -> RunStatus {
+    process
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = match process.spawn() {
+        Ok(child) => child,
+        Err(e) => return RunStatus::Failed(e.to_string()),
+    };
+    update(runs, idx, |run| run.status = RunStatus::Running);
+
+    tokio::join!(
+        read_lines(child.stdout.take(), OutputStream::Stdout, idx, runs),
+        read_lines(child.stderr.take(), OutputStream::Stderr, idx, runs),
+    );
+
+    match child.wait().await {
+        Ok(status) => RunStatus::Finished(status.code()),
+        Err(e) => RunStatus::Failed(e.to_string()),
+    }
+}
+
+async fn read_lines<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    stream: OutputStream,
+    idx: usize,
+    runs: &Mutex<Vec<HostRun>>,
+) {
+    let Some(reader) = reader else {
+        return;
+    };
+
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        update(runs, idx, |run| run.output.push((stream, line)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn is_finished(run: &ParallelRun) -> bool {
+        run.runs().iter().all(|run| run.status.is_done())
+    }
+
+    #[test]
+    fn test_ssh_command() {
+        let config = ParallelRunConfig::default();
+        let host = SshGroupItem::new("\"prod/web server\" alias", &[]);
+        let process = ssh_command(&host, "uptime", Some(Path::new("/tmp/config")), &config);
+
+        let args: Vec<String> = process
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            args,
+            vec![
+                "-F",
+                "/tmp/config",
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                "ConnectTimeout=10",
+                "-T",
+                "prod/web server",
+                "uptime"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_report() {
+        let runs = vec![
+            HostRun {
+                host: "web".to_string(),
+                status: RunStatus::Finished(Some(0)),
+                output: vec![
+                    (OutputStream::Stdout, "up 3 days".to_string()),
+                    (OutputStream::Stderr, "warning".to_string()),
+                ],
+            },
+            HostRun {
+                host: "db".to_string(),
+                status: RunStatus::Failed("not found".to_string()),
+                output: Vec::new(),
+            },
+        ];
+
+        assert_eq!(
+            format_report("uptime", &runs),
+            "$ uptime\n\n== web (exit 0)\nup 3 days\n[stderr] warning\n\n== db (failed: not found)\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_run_collects_output_of_each_host() {
+        let processes = ["web", "db", "cache"]
+            .iter()
+            .map(|host| {
+                let mut process = Command::new("sh");
+                process.arg("-c").arg(format!(
+                    "echo out-{0}; echo err-{0} >&2; [ {0} != db ]",
+                    host
+                ));
+                (host.to_string(), process)
+            })
+            .collect();
+        let run = ParallelRun::start("check", processes, 2).unwrap();
+
+        for _ in 0..100 {
+            if is_finished(&run) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        let runs = run.runs();
+        assert!(is_finished(&run));
+        assert_eq!(runs[0].host, "web");
+        assert!(runs[0].status.is_success());
+        assert_eq!(runs[1].status, RunStatus::Finished(Some(1)));
+        assert!(runs[2]
+            .output
+            .contains(&(OutputStream::Stdout, "out-cache".to_string())));
+        assert!(runs[2]
+            .output
+            .contains(&(OutputStream::Stderr, "err-cache".to_string())));
+    }
+}
@@ -0,0 +1,88 @@
+use clap::ValueEnum;
+use ssh_cfg::SshOptionKey;
+
+use fast_ssh::SshGroupItem;
+
+/// Exit code of `fast-ssh pick` when no host was picked, as for a shell
+/// command interrupted by Ctrl+C.
+pub const CANCEL_EXIT_CODE: i32 = 130;
+
+/// What `fast-ssh pick` prints for the picked host.
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum PickField {
+    /// Name given to ssh, e.g. `prod/web`
+    #[default]
+    Name,
+    /// HostName option, or the name if there is none
+    Hostname,
+    /// `user@hostname:port`, without the parts that are not set
+    Target,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// Key binding inserting the picked host in the command line.
+    pub fn snippet(&self) -> &'static str {
+        match self {
+            Shell::Bash => include_str!("../shell/fast-ssh.bash"),
+            Shell::Zsh => include_str!("../shell/fast-ssh.zsh"),
+            Shell::Fish => include_str!("../shell/fast-ssh.fish"),
+        }
+    }
+}
+
+pub fn format_pick(item: &SshGroupItem, field: PickField) -> String {
+    let name = item.ssh_name();
+    let hostname = item
+        .host_config
+        .get(&SshOptionKey::Hostname)
+        .map_or(name.as_str(), String::as_str);
+
+    match field {
+        PickField::Name => name.clone(),
+        PickField::Hostname => hostname.to_string(),
+        PickField::Target => {
+            let mut target = hostname.to_string();
+            if let Some(user) = item.host_config.get(&SshOptionKey::User) {
+                target = format!("{}@{}", user, target);
+            }
+            if let Some(port) = item.host_config.get(&SshOptionKey::Port) {
+                target = format!("{}:{}", target, port);
+            }
+            target
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_pick() {
+        let item = SshGroupItem::new(
+            "prod/web web",
+            &[
+                (SshOptionKey::Hostname, "10.0.0.1"),
+                (SshOptionKey::User, "deploy"),
+                (SshOptionKey::Port, "2222"),
+            ],
+        );
+        assert_eq!(format_pick(&item, PickField::Name), "prod/web");
+        assert_eq!(format_pick(&item, PickField::Hostname), "10.0.0.1");
+        assert_eq!(
+            format_pick(&item, PickField::Target),
+            "deploy@10.0.0.1:2222"
+        );
+
+        let bare = SshGroupItem::new("laptop", &[]);
+        assert_eq!(format_pick(&bare, PickField::Hostname), "laptop");
+        assert_eq!(format_pick(&bare, PickField::Target), "laptop");
+    }
+}
@@ -0,0 +1,250 @@
+use serde::Deserialize;
+use ssh_cfg::SshOptionKey;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+
+use fast_ssh::ssh_config_loader::SshConfigBlock;
+use fast_ssh::ssh_config_resolver::{self, ResolvedConfig};
+use fast_ssh::SshGroupItem;
+
+// Port used by ssh when the host does not set one
+const DEFAULT_SSH_PORT: u16 = 22;
+
+/// `reachability` section of the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ReachabilityConfig {
+    pub enabled: bool,
+    /// Time to wait for the TCP connection, in milliseconds
+    pub timeout_ms: u64,
+    /// How long a result is kept before the host is checked again, in seconds
+    pub cache_ttl_secs: u64,
+    /// Maximum number of hosts checked at the same time
+    pub concurrency: usize,
+}
+
+impl Default for ReachabilityConfig {
+    fn default() -> Self {
+        ReachabilityConfig {
+            enabled: true,
+            timeout_ms: 1000,
+            cache_ttl_secs: 60,
+            concurrency: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProbeStatus {
+    Checking,
+    /// The port accepted the connection, after the given time
+    Up(Duration),
+    Down,
+    /// Reached through a proxy, or without a valid port
+    Unknown,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    status: ProbeStatus,
+    checked_at: Instant,
+}
+
+/// Checks in the background that the SSH port of the hosts accepts TCP
+/// connections, and keeps the results for a while.
+pub struct ReachabilityChecker {
+    // Probes are disabled when there is no runtime to run them on
+    runtime: Option<Handle>,
+    cache: Arc<Mutex<HashMap<String, CacheEntry>>>,
+    semaphore: Arc<Semaphore>,
+    timeout: Duration,
+    ttl: Duration,
+}
+
+impl ReachabilityChecker {
+    pub fn new(config: &ReachabilityConfig) -> ReachabilityChecker {
+        ReachabilityChecker {
+            runtime: config
+                .enabled
+                .then(Handle::try_current)
+                .and_then(Result::ok),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            semaphore: Arc::new(Semaphore::new(config.concurrency.max(1))),
+            timeout: Duration::from_millis(config.timeout_ms),
+            ttl: Duration::from_secs(config.cache_ttl_secs),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.runtime.is_some()
+    }
+
+    /// Last known status of the host, `None` if it was never checked.
+    pub fn status(&self, full_name: &str) -> Option<ProbeStatus> {
+        let cache = self.cache.lock().ok()?;
+        cache.get(full_name).map(|entry| entry.status)
+    }
+
+    /// Starts a check of the host, unless one is running or its last result
+    /// is still fresh.
+    pub fn probe(&self, item: &SshGroupItem, blocks: &[SshConfigBlock]) {
+        let Some(runtime) = &self.runtime else {
+            return;
+        };
+        let Ok(mut cache) = self.cache.lock() else {
+            return;
+        };
+
+        let is_fresh = cache.get(&item.full_name).is_some_and(|entry| {
+            entry.status == ProbeStatus::Checking || entry.checked_at.elapsed() < self.ttl
+        });
+        if is_fresh {
+            return;
+        }
+
+        let alias = item.ssh_name();
+        let resolved = ssh_config_resolver::resolve(blocks, &alias);
+        let Some((host, port)) = probe_target(&resolved, &alias) else {
+            cache.insert(
+                item.full_name.clone(),
+                CacheEntry {
+                    status: ProbeStatus::Unknown,
+                    checked_at: Instant::now(),
+                },
+            );
+            return;
+        };
+
+        cache.insert(
+            item.full_name.clone(),
+            CacheEntry {
+                status: ProbeStatus::Checking,
+                checked_at: Instant::now(),
+            },
+        );
+
+        let key = item.full_name.clone();
+        let cache = Arc::clone(&self.cache);
+        let semaphore = Arc::clone(&self.semaphore);
+        let timeout = self.timeout;
+
+        runtime.spawn(async move {
+            let Ok(_permit) = semaphore.acquire().await else {
+                return;
+            };
+            let status = check(&host, port, timeout).await;
+
+            if let Ok(mut cache) = cache.lock() {
+                cache.insert(
+                    key,
+                    CacheEntry {
+                        status,
+                        checked_at: Instant::now(),
+                    },
+                );
+            }
+        });
+    }
+}
+
+/// Address ssh connects to, `None` when the host is reached through a proxy
+/// since only the proxy could tell whether it is up.
+fn probe_target(resolved: &ResolvedConfig, alias: &str) -> Option<(String, u16)> {
+    // `none` cancels a proxy set by a more generic block
+    let has_proxy = |key| {
+        resolved
+            .get(key)
+            .is_some_and(|v| !v.eq_ignore_ascii_case("none"))
+    };
+    if has_proxy(SshOptionKey::ProxyJump) || has_proxy(SshOptionKey::ProxyCommand) {
+        return None;
+    }
+
+    let host = resolved.get(SshOptionKey::Hostname).unwrap_or(alias);
+    let port = match resolved.get(SshOptionKey::Port) {
+        Some(port) => port.parse().ok()?,
+        None => DEFAULT_SSH_PORT,
+    };
+
+    Some((host.to_string(), port))
+}
+
+async fn check(host: &str, port: u16, timeout: Duration) -> ProbeStatus {
+    let started_at = Instant::now();
+
+    match tokio::time::timeout(timeout, TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => ProbeStatus::Up(started_at.elapsed()),
+        _ => ProbeStatus::Down,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fast_ssh::ssh_config_resolver::ResolvedOption;
+    use std::path::PathBuf;
+
+    fn resolved(options: &[(SshOptionKey, &str)]) -> ResolvedConfig {
+        ResolvedConfig {
+            options: options
+                .iter()
+                .map(|(key, value)| ResolvedOption {
+                    key: *key,
+                    value: value.to_string(),
+                    origin: "Host *".to_string(),
+                    source_file: PathBuf::new(),
+                })
+                .collect(),
+            skipped: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_probe_target() {
+        assert_eq!(
+            probe_target(&resolved(&[]), "web"),
+            Some(("web".to_string(), 22))
+        );
+        assert_eq!(
+            probe_target(
+                &resolved(&[
+                    (SshOptionKey::Hostname, "10.0.0.1"),
+                    (SshOptionKey::Port, "2222")
+                ]),
+                "web"
+            ),
+            Some(("10.0.0.1".to_string(), 2222))
+        );
+        assert_eq!(
+            probe_target(&resolved(&[(SshOptionKey::ProxyJump, "bastion")]), "web"),
+            None
+        );
+        assert_eq!(
+            probe_target(&resolved(&[(SshOptionKey::ProxyJump, "none")]), "web"),
+            Some(("web".to_string(), 22))
+        );
+        assert_eq!(
+            probe_target(&resolved(&[(SshOptionKey::Port, "ssh")]), "web"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let status = check("127.0.0.1", port, Duration::from_secs(1)).await;
+        assert!(matches!(status, ProbeStatus::Up(_)));
+
+        // Nothing listens on the port anymore
+        drop(listener);
+        let status = check("127.0.0.1", port, Duration::from_secs(1)).await;
+        assert_eq!(status, ProbeStatus::Down);
+    }
+}
@@ -0,0 +1,219 @@
+use ssh_cfg::SshOptionKey;
+use sublime_fuzzy::best_match;
+
+use crate::ssh_config_store::SshGroupItem;
+
+mod query;
+
+pub use query::{Field, Filter, Query};
+
+// Weights applied to the fuzzy score depending on the field that matched
+const ALIAS_WEIGHT: f64 = 3.0;
+const HOSTNAME_WEIGHT: f64 = 2.0;
+const COMMENT_WEIGHT: f64 = 1.0;
+
+// Scale of the boost given to frequently and recently used hosts
+const FRECENCY_WEIGHT: f64 = 10.0;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+pub struct SearchResult<'a> {
+    pub item: &'a SshGroupItem,
+    pub score: f64,
+    /// Char indices of `item.name` matched by the query
+    pub name_indices: Vec<usize>,
+}
+
+/// Keeps the items passing the query filters, then scores them against its
+/// free text and sorts them by descending score. Items that match none of the
+/// alias, HostName or comment are dropped.
+pub fn rank_items<'a>(
+    query: &Query,
+    items: Vec<&'a SshGroupItem>,
+    now: i64,
+) -> Vec<SearchResult<'a>> {
+    let items = items
+        .into_iter()
+        .filter(|item| query.matches_filters(item));
+
+    if query.text.is_empty() {
+        return items
+            .map(|item| SearchResult {
+                item,
+                score: 0.0,
+                name_indices: Vec::new(),
+            })
+            .collect();
+    }
+
+    let mut results: Vec<SearchResult> = items
+        .filter_map(|item| score_item(&query.text, item, now))
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.item.full_name.cmp(&b.item.full_name))
+    });
+
+    results
+}
+
+fn score_item<'a>(query: &str, item: &'a SshGroupItem, now: i64) -> Option<SearchResult<'a>> {
+    let alias_match = best_match(query, &item.full_name);
+
+    let hostname_score = item
+        .host_config
+        .get(&SshOptionKey::Hostname)
+        .and_then(|hostname| best_match(query, hostname))
+        .map(|m| m.score() as f64 * HOSTNAME_WEIGHT);
+
+    let comment_score = item
+        .comment
+        .as_ref()
+        .and_then(|comment| best_match(query, comment))
+        .map(|m| m.score() as f64 * COMMENT_WEIGHT);
+
+    let alias_score = alias_match.as_ref().map(|m| m.score() as f64 * ALIAS_WEIGHT);
+
+    let best_score = [alias_score, hostname_score, comment_score]
+        .into_iter()
+        .flatten()
+        .reduce(f64::max)?;
+
+    // The displayed name is the end of the full name (group prefix removed)
+    let name_offset = item.full_name.chars().count() - item.name.chars().count();
+    let name_indices = alias_match
+        .map(|m| {
+            m.matched_indices()
+                .filter(|&&idx| idx >= name_offset)
+                .map(|idx| idx - name_offset)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(SearchResult {
+        item,
+        score: best_score + frecency(item, now),
+        name_indices,
+    })
+}
+
+/// Boost for hosts used often and recently.
+fn frecency(item: &SshGroupItem, now: i64) -> f64 {
+    if item.connection_count <= 0 || item.last_used <= 0 {
+        return 0.0;
+    }
+
+    let age_days = (now - item.last_used).max(0) as f64 / SECONDS_PER_DAY;
+    let recency = match age_days {
+        d if d < 1.0 => 4.0,
+        d if d < 7.0 => 2.0,
+        d if d < 30.0 => 1.0,
+        _ => 0.5,
+    };
+
+    (1.0 + item.connection_count as f64).ln() * recency * FRECENCY_WEIGHT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_item(full_name: &str, hostname: &str, comment: Option<&str>) -> SshGroupItem {
+        let mut item = SshGroupItem::new(full_name, &[(SshOptionKey::Hostname, hostname)]);
+        item.comment = comment.map(str::to_string);
+        item
+    }
+
+    #[test]
+    fn test_rank_empty_query_keeps_order() {
+        let a = test_item("b-host", "10.0.0.1", None);
+        let b = test_item("a-host", "10.0.0.2", None);
+
+        let results = rank_items(&Query::parse(""), vec![&a, &b], 0);
+        let names: Vec<&str> = results.iter().map(|r| r.item.full_name.as_str()).collect();
+        assert_eq!(names, vec!["b-host", "a-host"]);
+    }
+
+    #[test]
+    fn test_rank_sorts_by_score() {
+        let weak = test_item("prod/d-a-t-a-b-a-s-e", "10.0.0.1", None);
+        let strong = test_item("prod/database", "10.0.0.2", None);
+        let none = test_item("prod/web", "10.0.0.3", None);
+
+        let results = rank_items(&Query::parse("database"), vec![&weak, &strong, &none], 0);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].item.full_name, "prod/database");
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn test_rank_alias_beats_hostname_and_comment() {
+        let by_comment = test_item("one", "10.0.0.1", Some("mail"));
+        let by_hostname = test_item("two", "mail", None);
+        let by_alias = test_item("mail", "10.0.0.3", None);
+
+        let items = vec![&by_comment, &by_hostname, &by_alias];
+        let results = rank_items(&Query::parse("mail"), items, 0);
+        let names: Vec<&str> = results.iter().map(|r| r.item.full_name.as_str()).collect();
+
+        assert_eq!(names, vec!["mail", "two", "one"]);
+    }
+
+    #[test]
+    fn test_rank_frecency_breaks_ties() {
+        let now = 1_000_000;
+        let unused = test_item("web1", "10.0.0.1", None);
+        let mut used = test_item("web2", "10.0.0.2", None);
+        used.connection_count = 10;
+        used.last_used = now - 60;
+
+        let results = rank_items(&Query::parse("web"), vec![&unused, &used], now);
+        assert_eq!(results[0].item.full_name, "web2");
+    }
+
+    #[test]
+    fn test_rank_name_indices_skip_group_prefix() {
+        let item = test_item("prod/db", "10.0.0.1", None);
+
+        let results = rank_items(&Query::parse("db"), vec![&item], 0);
+        assert_eq!(results[0].name_indices, vec![0, 1]);
+
+        // Matches only in the hostname don't highlight the name
+        let results = rank_items(&Query::parse("10.0"), vec![&item], 0);
+        assert!(results[0].name_indices.is_empty());
+    }
+
+    #[test]
+    fn test_rank_applies_filters() {
+        let mut prod = test_item("prod/db", "10.0.0.1", None);
+        prod.host_config.insert(SshOptionKey::User, "deploy".to_string());
+        let staging = test_item("staging/db", "10.0.1.1", None);
+
+        let results = rank_items(&Query::parse("-group:staging"), vec![&prod, &staging], 0);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].item.full_name, "prod/db");
+
+        let results = rank_items(&Query::parse("user:deploy db"), vec![&prod, &staging], 0);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name_indices, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_frecency() {
+        let now = 100 * SECONDS_PER_DAY as i64;
+        let mut item = test_item("host", "10.0.0.1", None);
+        assert_eq!(frecency(&item, now), 0.0);
+
+        item.connection_count = 5;
+        item.last_used = now - 3600;
+        let recent = frecency(&item, now);
+
+        item.last_used = now - 60 * SECONDS_PER_DAY as i64;
+        let old = frecency(&item, now);
+
+        assert!(recent > old);
+        assert!(old > 0.0);
+    }
+}
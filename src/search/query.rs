@@ -0,0 +1,299 @@
+use ssh_cfg::SshOptionKey;
+
+use crate::ssh_config_store::SshGroupItem;
+
+/// A parsed search, e.g. `group:prod -user:root "web server"`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Query {
+    /// Free text, fuzzy matched against the alias, HostName and notes
+    pub text: String,
+    pub filters: Vec<Filter>,
+    /// Qualifiers that could not be parsed, they are ignored when matching
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Filter {
+    pub field: Field,
+    pub value: String,
+    pub negated: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Field {
+    Alias,
+    Group,
+    Comment,
+    Tag,
+    Option(SshOptionKey),
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Query {
+        let mut query = Query::default();
+        let mut words = Vec::new();
+
+        let (tokens, unterminated_quote) = tokenize(input);
+        if unterminated_quote {
+            query.errors.push("Unterminated quote".to_string());
+        }
+
+        for token in tokens {
+            match token {
+                Token::Text(word) => words.push(word),
+                Token::Qualifier {
+                    name,
+                    value,
+                    negated,
+                } => match Filter::new(&name, value, negated) {
+                    Ok(filter) => query.filters.push(filter),
+                    Err(error) => query.errors.push(error),
+                },
+            }
+        }
+
+        // Words are fuzzy matched as a whole, so `prod db` finds `prod/db`
+        query.text = words.concat();
+        query
+    }
+
+    pub fn matches_filters(&self, item: &SshGroupItem) -> bool {
+        self.filters.iter().all(|filter| filter.matches(item))
+    }
+}
+
+impl Filter {
+    fn new(name: &str, value: String, negated: bool) -> Result<Filter, String> {
+        let field = match name.to_ascii_lowercase().as_str() {
+            "host" | "alias" => Field::Alias,
+            "group" => Field::Group,
+            "comment" | "note" | "notes" => Field::Comment,
+            "tag" | "tags" => Field::Tag,
+            _ => match name.parse::<SshOptionKey>() {
+                Ok(key) => Field::Option(key),
+                Err(_) => return Err(format!("Unknown qualifier `{}`", name)),
+            },
+        };
+
+        if value.is_empty() {
+            return Err(format!("Missing value for `{}:`", name));
+        }
+
+        Ok(Filter {
+            field,
+            value,
+            negated,
+        })
+    }
+
+    pub fn matches(&self, item: &SshGroupItem) -> bool {
+        let matched = match &self.field {
+            Field::Alias => matches_pattern(&self.value, &item.full_name),
+            Field::Group => item.in_group(&self.value),
+            Field::Comment => item
+                .comment
+                .as_ref()
+                .is_some_and(|comment| comment.to_lowercase().contains(&self.value.to_lowercase())),
+            Field::Tag => item.metadata.has_tag(&self.value),
+            Field::Option(key) => item
+                .host_config
+                .get(key)
+                .is_some_and(|value| matches_pattern(&self.value, value)),
+        };
+
+        matched != self.negated
+    }
+}
+
+/// Case insensitive comparison where `*` matches any sequence of characters.
+fn matches_pattern(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let value = value.to_lowercase();
+
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == value;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !value.starts_with(first) || !value.ends_with(last) || value.len() < first.len() + last.len()
+    {
+        return false;
+    }
+
+    let mut rest = &value[first.len()..value.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+
+    true
+}
+
+enum Token {
+    Text(String),
+    Qualifier {
+        name: String,
+        value: String,
+        negated: bool,
+    },
+}
+
+/// Splits the input on whitespace, keeping quoted values together. Returns
+/// whether a quote was left open.
+fn tokenize(input: &str) -> (Vec<Token>, bool) {
+    // Each raw token remembers if it started with a quote, in which case
+    // it is free text even if it contains a `:`
+    let mut raw_tokens: Vec<(String, bool)> = Vec::new();
+    let mut current: Option<(String, bool)> = None;
+    let mut in_quotes = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.get_or_insert_with(|| (String::new(), true));
+            }
+            c if c.is_whitespace() && !in_quotes => raw_tokens.extend(current.take()),
+            c => current
+                .get_or_insert_with(|| (String::new(), false))
+                .0
+                .push(c),
+        }
+    }
+    raw_tokens.extend(current);
+
+    let tokens = raw_tokens
+        .into_iter()
+        .map(|(token, starts_quoted)| {
+            let (negated, rest) = match token.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, token.as_str()),
+            };
+
+            match rest.split_once(':') {
+                Some((name, value)) if !starts_quoted && !name.is_empty() => Token::Qualifier {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                    negated,
+                },
+                _ => Token::Text(token),
+            }
+        })
+        .collect();
+
+    (tokens, in_quotes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_free_text_only() {
+        let query = Query::parse("prod db");
+        assert_eq!(query.text, "proddb");
+        assert!(query.filters.is_empty());
+        assert!(query.errors.is_empty());
+    }
+
+    #[test]
+    fn test_parse_qualifiers() {
+        let query = Query::parse("group:prod User:deploy port:2222 db");
+
+        assert_eq!(query.text, "db");
+        assert_eq!(
+            query.filters,
+            vec![
+                Filter {
+                    field: Field::Group,
+                    value: "prod".to_string(),
+                    negated: false
+                },
+                Filter {
+                    field: Field::Option(SshOptionKey::User),
+                    value: "deploy".to_string(),
+                    negated: false
+                },
+                Filter {
+                    field: Field::Option(SshOptionKey::Port),
+                    value: "2222".to_string(),
+                    negated: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_negation_and_quotes() {
+        let query = Query::parse("-group:staging comment:\"primary db\" \"web server\"");
+
+        assert_eq!(query.text, "web server");
+        assert!(query.filters[0].negated);
+        assert_eq!(query.filters[1].value, "primary db");
+        assert!(!query.filters[1].negated);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let query = Query::parse("foo:bar user: web");
+        assert_eq!(query.text, "web");
+        assert!(query.filters.is_empty());
+        assert_eq!(
+            query.errors,
+            vec!["Unknown qualifier `foo`", "Missing value for `user:`"]
+        );
+
+        let query = Query::parse("comment:\"open");
+        assert_eq!(query.errors, vec!["Unterminated quote"]);
+
+        // Quoted text is never a qualifier
+        let query = Query::parse("\"10.0.0.1:22\"");
+        assert_eq!(query.text, "10.0.0.1:22");
+        assert!(query.errors.is_empty());
+    }
+
+    #[test]
+    fn test_filters_match_items() {
+        let mut item = SshGroupItem::new(
+            "prod/db",
+            &[
+                (SshOptionKey::User, "deploy"),
+                (SshOptionKey::Port, "2222"),
+                (SshOptionKey::IdentityFile, "~/.ssh/id_work"),
+            ],
+        );
+        item.comment = Some("Primary database".to_string());
+
+        assert!(Query::parse("group:PROD user:deploy port:2222").matches_filters(&item));
+        assert!(!Query::parse("port:22").matches_filters(&item));
+        assert!(Query::parse("identityfile:*id_work").matches_filters(&item));
+        assert!(Query::parse("comment:primary").matches_filters(&item));
+        assert!(!Query::parse("-group:prod").matches_filters(&item));
+        assert!(Query::parse("-group:staging").matches_filters(&item));
+        assert!(!Query::parse("proxyjump:bastion").matches_filters(&item));
+        assert!(Query::parse("-proxyjump:bastion").matches_filters(&item));
+    }
+
+    #[test]
+    fn test_tag_filter() {
+        let mut item = SshGroupItem::new("prod/db", &[]);
+        item.metadata.add_tags("prod, Postgres");
+
+        assert!(Query::parse("tag:postgres").matches_filters(&item));
+        assert!(Query::parse("tags:PROD -tag:mysql").matches_filters(&item));
+        assert!(!Query::parse("tag:post").matches_filters(&item));
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("10.0.*", "10.0.0.1"));
+        assert!(matches_pattern("*.corp", "db.CORP"));
+        assert!(matches_pattern("a*b*c", "aXXbYYc"));
+        assert!(!matches_pattern("a*b*c", "aXXcYYb"));
+        assert!(!matches_pattern("ab*ba", "aba"));
+        assert!(matches_pattern("*", "anything"));
+    }
+}
@@ -1,8 +1,10 @@
-use crate::{app::App, get_theme, ssh_config_store::SshGroupItem, widgets::block};
-use std::io::Stdout;
-use sublime_fuzzy::best_match;
+use crate::{app::App, get_theme, widgets::block};
+use fast_ssh::search::{rank_items, Query, SearchResult};
+use fast_ssh::SshGroupItem;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use tui::{
-    backend::CrosstermBackend,
+    backend::Backend,
     layout::Rect,
     style::{Modifier, Style},
     text::{Span, Spans},
@@ -13,6 +15,16 @@ use tui::{
 pub struct Searcher {
     search_string: String,
     is_committed: bool,
+    /// Results of the current search, ranked again only once the search or
+    /// the config store changed
+    ranked: RefCell<Option<Vec<RankedItem>>>,
+}
+
+/// A search result, as the index of the item in `SshConfigStore::items`.
+struct RankedItem {
+    index: usize,
+    score: f64,
+    name_indices: Vec<usize>,
 }
 
 impl Default for Searcher {
@@ -26,6 +38,7 @@ impl Searcher {
         Searcher {
             search_string: String::new(),
             is_committed: false,
+            ranked: RefCell::new(None),
         }
     }
 
@@ -40,42 +53,67 @@ impl Searcher {
     }
 
     pub fn get_filtered_items<'a>(&self, app: &'a App) -> Vec<&'a SshGroupItem> {
-        if self.search_string.is_empty() {
-            return app.get_all_items_except_recents();
-        }
+        self.search(app)
+            .into_iter()
+            .map(|result| result.item)
+            .collect()
+    }
+
+    /// Returns the matching items, best match first.
+    pub fn search<'a>(&self, app: &'a App) -> Vec<SearchResult<'a>> {
+        let items = app.get_all_items_except_virtual();
+        let mut ranked = self.ranked.borrow_mut();
+        let ranked = ranked.get_or_insert_with(|| self.rank(&items));
 
-        app.get_all_items_except_recents()
+        ranked
+            .iter()
+            .map(|result| SearchResult {
+                item: items[result.index],
+                score: result.score,
+                name_indices: result.name_indices.clone(),
+            })
+            .collect()
+    }
+
+    fn rank(&self, items: &[&SshGroupItem]) -> Vec<RankedItem> {
+        let now = chrono::offset::Local::now().timestamp();
+        let query = Query::parse(&self.search_string);
+        let indexes: HashMap<&str, usize> = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (item.full_name.as_str(), index))
+            .collect();
+
+        rank_items(&query, items.to_vec(), now)
             .into_iter()
-            .filter(|item| {
-                // Check host name match
-                if best_match(&self.search_string, &item.full_name).is_some() {
-                    return true;
-                }
-
-                // Check hostname parameter match - use case-insensitive comparison without allocation
-                let has_hostname_match = item.host_config.iter().any(|(key, value)| {
-                    key.to_string().eq_ignore_ascii_case("hostname")
-                        && best_match(&self.search_string, value).is_some()
-                });
-                
-                if has_hostname_match {
-                    return true;
-                }
-
-                // Check notes/comments match
-                item.comment
-                    .as_ref()
-                    .is_some_and(|comment| best_match(&self.search_string, comment).is_some())
+            .map(|result| RankedItem {
+                index: indexes[result.item.full_name.as_str()],
+                score: result.score,
+                name_indices: result.name_indices,
             })
             .collect()
     }
 
+    /// Ranks the items again on the next search, e.g. after the config store
+    /// was replaced.
+    #[inline]
+    pub fn invalidate(&mut self) {
+        *self.ranked.get_mut() = None;
+    }
+
+    pub fn set_search(&mut self, search: &str) {
+        self.search_string = search.to_string();
+        self.is_committed = false;
+        self.invalidate();
+    }
+
     pub fn add_char(&mut self, c: char) {
         // Assert preconditions
         debug_assert!(c.is_ascii() || c.len_utf8() <= 4, "char should be valid unicode");
         debug_assert!(self.search_string.len() < 1000, "search string should be reasonable length");
         
         self.search_string.push(c);
+        self.invalidate();
     }
 
     pub fn del_char(&mut self) {
@@ -84,17 +122,19 @@ impl Searcher {
         debug_assert!(self.search_string.len() < 1000, "search string should be reasonable length");
         
         self.search_string.pop();
+        self.invalidate();
     }
 
     pub fn clear_search(&mut self) {
         self.search_string.clear();
         self.is_committed = false;
+        self.invalidate();
     }
 
-    pub fn render(&self, _app: &App, area: Rect, frame: &mut Frame<CrosstermBackend<Stdout>>) {
+    pub fn render<B: Backend>(&self, _app: &App, area: Rect, frame: &mut Frame<B>) {
         let block = block::new(" Search ");
 
-        let spans = if self.is_committed {
+        let mut spans = if self.is_committed {
             // Show navigation hint when committed
             Spans::from(vec![
                 Span::styled(" > ", Style::default().fg(get_theme().text_primary())),
@@ -122,6 +162,14 @@ impl Searcher {
             ])
         };
 
+        let errors = Query::parse(&self.search_string).errors;
+        if !errors.is_empty() {
+            spans.0.push(Span::styled(
+                format!("  {}", errors.join(", ")),
+                Style::default().fg(get_theme().error_color()),
+            ));
+        }
+
         let paragraph = Paragraph::new(spans).block(block);
 
         frame.render_widget(paragraph, area);
@@ -131,6 +179,7 @@ impl Searcher {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::tests::test_app;
 
     #[test]
     fn test_searcher_new() {
@@ -201,6 +250,38 @@ mod tests {
         assert!(!searcher.is_committed());
     }
 
+    #[test]
+    fn test_set_search() {
+        let mut searcher = Searcher::new();
+        searcher.commit_search();
+        searcher.set_search("web");
+
+        assert_eq!(searcher.search_string, "web");
+        assert!(!searcher.is_committed());
+    }
+
+    #[test]
+    fn test_results_are_cached() {
+        let mut app =
+            test_app("Host web\n    HostName 10.0.0.1\n\nHost db\n    HostName 10.0.0.2\n");
+        app.searcher.set_search("we");
+
+        assert_eq!(app.searcher.get_filtered_items(&app)[0].full_name, "web");
+        assert!(app.searcher.ranked.borrow().is_some());
+        // Read from the cache
+        assert_eq!(app.searcher.get_filtered_items(&app)[0].full_name, "web");
+
+        app.searcher.add_char('x');
+        assert!(app.searcher.ranked.borrow().is_none());
+        assert!(app.searcher.get_filtered_items(&app).is_empty());
+
+        app.searcher.del_char();
+        app.searcher.del_char();
+        app.searcher.del_char();
+        app.searcher.add_char('d');
+        assert_eq!(app.searcher.get_filtered_items(&app)[0].full_name, "db");
+    }
+
     #[test]
     fn test_unicode_support() {
         let mut searcher = Searcher::new();
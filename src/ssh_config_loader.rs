@@ -0,0 +1,510 @@
+use anyhow::{format_err, Result};
+use ssh_cfg::{ConfigError, SshConfig, SshHostConfig, SshOptionKey};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+// Same nesting limit as OpenSSH (READCONF_MAX_DEPTH)
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockKind {
+    Host,
+    Match,
+}
+
+/// A single `Host` or `Match` block, along with the file it was declared in.
+#[derive(Debug, Clone)]
+pub struct SshConfigBlock {
+    pub kind: BlockKind,
+    /// Patterns of a `Host` block, or criteria of a `Match` block
+    pub host: String,
+    pub config: SshHostConfig,
+    pub comment: Option<String>,
+    pub source_file: PathBuf,
+}
+
+/// The result of loading a SSH config file and every file it includes.
+#[derive(Debug, Default)]
+pub struct LoadedSshConfig {
+    /// Host blocks in the order OpenSSH would read them.
+    pub blocks: Vec<SshConfigBlock>,
+    /// Every file that was read, starting with the root config.
+    pub files: Vec<PathBuf>,
+    /// Directories the `Include` patterns look for files in, their listing
+    /// changes when a matching file is created.
+    pub include_dirs: Vec<PathBuf>,
+    /// Non fatal problems, like include cycles.
+    pub warnings: Vec<String>,
+}
+
+impl LoadedSshConfig {
+    /// Merges all blocks into a single `SshConfig`. Like `ssh_cfg`, a host
+    /// declared twice keeps the options of its last declaration.
+    pub fn to_ssh_config(&self) -> SshConfig {
+        let mut config = SshConfig::default();
+        for block in self.host_blocks() {
+            config.insert(block.host.clone(), block.config.clone());
+        }
+        config
+    }
+
+    /// Returns the last block declared for the given host.
+    pub fn get_block(&self, host: &str) -> Option<&SshConfigBlock> {
+        self.host_blocks().rev().find(|block| block.host == host)
+    }
+
+    fn host_blocks(&self) -> impl DoubleEndedIterator<Item = &SshConfigBlock> {
+        self.blocks
+            .iter()
+            .filter(|block| block.kind == BlockKind::Host)
+    }
+}
+
+/// Reads a SSH config file, following `Include` directives.
+pub struct SshConfigLoader {
+    home_dir: Option<PathBuf>,
+    /// Relative `Include` paths are resolved against this directory
+    /// (`~/.ssh`, as OpenSSH does for user configs).
+    include_dir: PathBuf,
+}
+
+struct LoadState {
+    loaded: LoadedSshConfig,
+    errors: Vec<ConfigError>,
+    current_block: Option<usize>,
+    include_stack: Vec<PathBuf>,
+}
+
+impl SshConfigLoader {
+    pub fn new() -> Result<SshConfigLoader> {
+        let home_dir =
+            dirs::home_dir().ok_or_else(|| format_err!("Could not get home directory"))?;
+
+        Ok(SshConfigLoader {
+            include_dir: home_dir.join(".ssh"),
+            home_dir: Some(home_dir),
+        })
+    }
+
+    pub fn default_config_path() -> Result<PathBuf> {
+        dirs::home_dir()
+            .map(|home| home.join(".ssh").join("config"))
+            .ok_or_else(|| format_err!("Could not get home directory"))
+    }
+
+    pub fn load(&self, path: &Path) -> Result<LoadedSshConfig> {
+        let contents = read_to_string(path)
+            .map_err(|e| format_err!("Failed to read `{}`: {}", path.display(), e))?;
+
+        self.parse(path, &contents)
+    }
+
+    /// Reads a config that is already in memory, as if it was the file at
+    /// `path`. Included files are still read from the disk.
+    pub fn parse(&self, path: &Path, contents: &str) -> Result<LoadedSshConfig> {
+        let mut state = LoadState {
+            loaded: LoadedSshConfig::default(),
+            errors: Vec::new(),
+            current_block: None,
+            include_stack: vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())],
+        };
+
+        self.parse_file(path, contents, &mut state);
+
+        if !state.errors.is_empty() {
+            return Err(ssh_cfg::Error::ConfigErrors {
+                errors: state.errors,
+            }
+            .into());
+        }
+
+        Ok(state.loaded)
+    }
+
+    fn parse_file(&self, path: &Path, contents: &str, state: &mut LoadState) {
+        state.loaded.files.push(path.to_path_buf());
+
+        let mut current_comment = String::new();
+
+        for line in contents.lines() {
+            let trimmed = line.trim();
+
+            if let Some(comment_text) = trimmed.strip_prefix('#') {
+                if !current_comment.is_empty() {
+                    current_comment.push('\n');
+                }
+                current_comment.push_str(comment_text.trim());
+                continue;
+            }
+
+            // Only consider content before the first `#`
+            let content = trimmed
+                .split_once('#')
+                .map_or(trimmed, |split| split.0)
+                .trim();
+
+            if content.is_empty() {
+                current_comment.clear();
+                continue;
+            }
+
+            let Some((key, value)) = split_key_value(content) else {
+                state.errors.push(ConfigError::KeyValueNotFound {
+                    line: content.to_string(),
+                });
+                continue;
+            };
+
+            let option_key = match key.parse::<SshOptionKey>() {
+                Ok(option_key) => option_key,
+                Err(error) => {
+                    state.errors.push(error);
+                    continue;
+                }
+            };
+
+            match option_key {
+                SshOptionKey::Host | SshOptionKey::Match => {
+                    state.loaded.blocks.push(SshConfigBlock {
+                        kind: match option_key {
+                            SshOptionKey::Host => BlockKind::Host,
+                            _ => BlockKind::Match,
+                        },
+                        host: value.to_string(),
+                        config: SshHostConfig::default(),
+                        comment: (!current_comment.is_empty())
+                            .then(|| std::mem::take(&mut current_comment)),
+                        source_file: path.to_path_buf(),
+                    });
+                    state.current_block = Some(state.loaded.blocks.len() - 1);
+                }
+                SshOptionKey::Include => {
+                    let include_paths = self.resolve_include(value, &mut state.loaded.include_dirs);
+                    for include_path in include_paths {
+                        self.include_file(&include_path, state);
+                    }
+                }
+                _ => match state.current_block {
+                    Some(idx) => {
+                        // Like OpenSSH, the first value of an option is the one used
+                        let config = &mut state.loaded.blocks[idx].config;
+                        if !config.contains_key(&option_key) {
+                            config.insert(option_key, value.to_string());
+                        }
+                    }
+                    None => state.errors.push(ConfigError::SshOptionBeforeHost {
+                        option: option_key,
+                        value: value.to_string(),
+                    }),
+                },
+            }
+        }
+    }
+
+    fn include_file(&self, path: &Path, state: &mut LoadState) {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        if state.include_stack.contains(&canonical) {
+            state.loaded.warnings.push(format!(
+                "Include cycle detected, skipping `{}`",
+                path.display()
+            ));
+            return;
+        }
+
+        if state.include_stack.len() >= MAX_INCLUDE_DEPTH {
+            state.loaded.warnings.push(format!(
+                "Too many nested includes, skipping `{}`",
+                path.display()
+            ));
+            return;
+        }
+
+        let contents = match read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                state.loaded.warnings.push(format!(
+                    "Failed to read included file `{}`: {}",
+                    path.display(),
+                    e
+                ));
+                return;
+            }
+        };
+
+        // Directives before the first `Host` of an included file apply to the
+        // block that contains the `Include`, which is restored afterwards.
+        let parent_block = state.current_block;
+        state.include_stack.push(canonical);
+        self.parse_file(path, &contents, state);
+        state.include_stack.pop();
+        state.current_block = parent_block;
+    }
+
+    /// Expands every pattern of an `Include` directive into existing files,
+    /// sorted like OpenSSH does with glob(3). The directory of each pattern
+    /// is added to `include_dirs`.
+    fn resolve_include(&self, value: &str, include_dirs: &mut Vec<PathBuf>) -> Vec<PathBuf> {
+        split_arguments(value)
+            .iter()
+            .flat_map(|pattern| {
+                let pattern = self.expand_path(pattern);
+                let dir = pattern_dir(&pattern);
+                if !include_dirs.contains(&dir) {
+                    include_dirs.push(dir);
+                }
+
+                match glob::glob(&pattern.to_string_lossy()) {
+                    Ok(paths) => paths
+                        .filter_map(|entry| entry.ok())
+                        .filter(|path| path.is_file())
+                        .collect(),
+                    Err(_) => Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    fn expand_path(&self, pattern: &str) -> PathBuf {
+        if let Some(home) = &self.home_dir {
+            if pattern == "~" {
+                return home.clone();
+            }
+            if let Some(rest) = pattern.strip_prefix("~/") {
+                return home.join(rest);
+            }
+        }
+
+        let path = PathBuf::from(pattern);
+        if path.is_absolute() {
+            path
+        } else {
+            self.include_dir.join(path)
+        }
+    }
+}
+
+/// Deepest directory of an include pattern without wildcards, e.g.
+/// `~/.ssh/projects` for `~/.ssh/projects/*/config`.
+fn pattern_dir(pattern: &Path) -> PathBuf {
+    pattern
+        .parent()
+        .unwrap_or(pattern)
+        .components()
+        .take_while(|component| {
+            !component
+                .as_os_str()
+                .to_string_lossy()
+                .contains(['*', '?', '['])
+        })
+        .collect()
+}
+
+/// Keys and values are separated by whitespace and/or a single `=`.
+pub(crate) fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    let key_end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let (key, rest) = line.split_at(key_end);
+    let rest = rest.trim_start();
+    let value = rest.strip_prefix('=').unwrap_or(rest).trim();
+
+    if key.is_empty() || value.is_empty() {
+        return None;
+    }
+
+    Some((key, value))
+}
+
+/// Splits a directive value on whitespace, honoring double quotes.
+pub(crate) fn split_arguments(value: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        args.push(current);
+    }
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn get_test_dir(name: &str) -> PathBuf {
+        let test_dir = std::env::temp_dir().join("fast_ssh_tests").join(format!(
+            "loader_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+        test_dir
+    }
+
+    fn loader_for(dir: &Path) -> SshConfigLoader {
+        SshConfigLoader {
+            home_dir: None,
+            include_dir: dir.to_path_buf(),
+        }
+    }
+
+    #[test]
+    fn test_split_key_value() {
+        assert_eq!(split_key_value("Host foo"), Some(("Host", "foo")));
+        assert_eq!(split_key_value("Port=22"), Some(("Port", "22")));
+        assert_eq!(split_key_value("Port = 22"), Some(("Port", "22")));
+        assert_eq!(
+            split_key_value("LocalCommand echo a=b"),
+            Some(("LocalCommand", "echo a=b"))
+        );
+        assert_eq!(split_key_value("Host"), None);
+    }
+
+    #[test]
+    fn test_split_arguments() {
+        assert_eq!(split_arguments("a b"), vec!["a", "b"]);
+        assert_eq!(
+            split_arguments("\"with space/*\"  other"),
+            vec!["with space/*", "other"]
+        );
+    }
+
+    #[test]
+    fn test_load_single_file_with_comments() {
+        let dir = get_test_dir("single");
+        let config = dir.join("config");
+        fs::write(
+            &config,
+            "# Main server\n# second line\nHost main\n    HostName 10.0.0.1\n\n# dropped\n\nHost other\n    User root\n",
+        )
+        .unwrap();
+
+        let loaded = loader_for(&dir).load(&config).unwrap();
+
+        assert_eq!(loaded.blocks.len(), 2);
+        assert_eq!(loaded.blocks[0].host, "main");
+        assert_eq!(
+            loaded.blocks[0].comment.as_deref(),
+            Some("Main server\nsecond line")
+        );
+        assert_eq!(loaded.blocks[1].comment, None);
+        assert_eq!(loaded.blocks[1].source_file, config);
+        assert_eq!(loaded.files, vec![config]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_load_follows_relative_glob_includes() {
+        let dir = get_test_dir("glob");
+        fs::create_dir_all(dir.join("config.d")).unwrap();
+        let config = dir.join("config");
+        fs::write(
+            &config,
+            "Include config.d/*\n\nHost root\n    HostName root.lan\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("config.d/b"),
+            "# Project B\nHost b/web\n    HostName b.lan\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("config.d/a"),
+            "# Project A\nHost a/web\n    HostName a.lan\n",
+        )
+        .unwrap();
+
+        let loaded = loader_for(&dir).load(&config).unwrap();
+        let hosts: Vec<&str> = loaded.blocks.iter().map(|b| b.host.as_str()).collect();
+
+        assert_eq!(hosts, vec!["a/web", "b/web", "root"]);
+        assert_eq!(loaded.blocks[0].comment.as_deref(), Some("Project A"));
+        assert_eq!(loaded.blocks[0].source_file, dir.join("config.d/a"));
+        assert_eq!(loaded.blocks[2].source_file, config);
+        assert_eq!(loaded.files.len(), 3);
+        assert_eq!(loaded.include_dirs, vec![dir.join("config.d")]);
+        assert_eq!(loaded.to_ssh_config().len(), 3);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_load_detects_include_cycles() {
+        let dir = get_test_dir("cycle");
+        let config = dir.join("config");
+        fs::write(&config, "Include other\nHost root\n    User me\n").unwrap();
+        fs::write(
+            dir.join("other"),
+            "Include config\nHost other\n    User you\n",
+        )
+        .unwrap();
+
+        let loaded = loader_for(&dir).load(&config).unwrap();
+
+        assert_eq!(loaded.blocks.len(), 2);
+        assert_eq!(loaded.warnings.len(), 1);
+        assert!(loaded.warnings[0].contains("cycle"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_include_inside_host_block() {
+        let dir = get_test_dir("nested");
+        let config = dir.join("config");
+        fs::write(&config, "Host root\n    Include extra\n    Port 2222\n").unwrap();
+        fs::write(dir.join("extra"), "User deploy\n").unwrap();
+
+        let loaded = loader_for(&dir).load(&config).unwrap();
+        let block = loaded.get_block("root").unwrap();
+
+        assert_eq!(block.config.get(&SshOptionKey::User).unwrap(), "deploy");
+        assert_eq!(block.config.get(&SshOptionKey::Port).unwrap(), "2222");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_missing_include_is_ignored() {
+        let dir = get_test_dir("missing");
+        let config = dir.join("config");
+        fs::write(
+            &config,
+            "Include does-not-exist/*\nHost root\n    User me\n",
+        )
+        .unwrap();
+
+        let loaded = loader_for(&dir).load(&config).unwrap();
+
+        assert_eq!(loaded.blocks.len(), 1);
+        assert!(loaded.warnings.is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_option_before_host_is_an_error() {
+        let dir = get_test_dir("before_host");
+        let config = dir.join("config");
+        fs::write(&config, "User me\nHost root\n").unwrap();
+
+        assert!(loader_for(&dir).load(&config).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,334 @@
+use ssh_cfg::SshOptionKey;
+use std::path::PathBuf;
+
+use crate::ssh_config_loader::{split_arguments, BlockKind, SshConfigBlock};
+
+/// An option that applies to a host, with the block it comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedOption {
+    pub key: SshOptionKey,
+    pub value: String,
+    /// `Host` or `Match` line of the block, e.g. `Host *.corp`
+    pub origin: String,
+    pub source_file: PathBuf,
+}
+
+/// The options ssh would use to connect to a host.
+#[derive(Debug, Default, PartialEq)]
+pub struct ResolvedConfig {
+    pub options: Vec<ResolvedOption>,
+    /// `Match` blocks whose criteria cannot be checked without connecting
+    /// (`exec`, `canonical`...), they are left out of the options
+    pub skipped: Vec<String>,
+}
+
+impl ResolvedConfig {
+    pub fn get(&self, key: SshOptionKey) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|option| option.key == key)
+            .map(|option| option.value.as_str())
+    }
+}
+
+/// Computes the options of `alias` the way OpenSSH does: blocks are read in
+/// order and the first value found for an option wins.
+pub fn resolve(blocks: &[SshConfigBlock], alias: &str) -> ResolvedConfig {
+    let mut resolved = ResolvedConfig::default();
+
+    for block in blocks {
+        let origin = match block.kind {
+            BlockKind::Host => format!("Host {}", block.host),
+            BlockKind::Match => format!("Match {}", block.host),
+        };
+
+        let applies = match block.kind {
+            BlockKind::Host => {
+                match_pattern_list(&block.host.replace(char::is_whitespace, ","), alias)
+            }
+            BlockKind::Match => match evaluate_match(&block.host, alias, &resolved) {
+                Some(matched) => matched,
+                None => {
+                    resolved.skipped.push(origin);
+                    continue;
+                }
+            },
+        };
+
+        if !applies {
+            continue;
+        }
+
+        for (key, value) in block.config.iter() {
+            if resolved.get(*key).is_none() {
+                resolved.options.push(ResolvedOption {
+                    key: *key,
+                    value: value.clone(),
+                    origin: origin.clone(),
+                    source_file: block.source_file.clone(),
+                });
+            }
+        }
+    }
+
+    resolved
+}
+
+/// Checks every criterion of a `Match` line. Returns `None` when one of them
+/// cannot be checked and none of the others failed.
+fn evaluate_match(criteria: &str, alias: &str, resolved: &ResolvedConfig) -> Option<bool> {
+    let args = split_arguments(criteria);
+    let mut args = args.iter();
+    let mut unknown = false;
+
+    while let Some(arg) = args.next() {
+        let (negated, criterion) = match arg.strip_prefix('!') {
+            Some(criterion) => (true, criterion),
+            None => (false, arg.as_str()),
+        };
+
+        let matched = match criterion.to_ascii_lowercase().as_str() {
+            "all" => Some(true),
+            "canonical" | "final" => None,
+            name => {
+                let value = args.next()?;
+                match name {
+                    "host" => {
+                        let host_name = resolved.get(SshOptionKey::Hostname).unwrap_or(alias);
+                        Some(match_pattern_list(value, host_name))
+                    }
+                    "originalhost" => Some(match_pattern_list(value, alias)),
+                    "user" => Some(match_pattern_list(
+                        value,
+                        &resolved
+                            .get(SshOptionKey::User)
+                            .map_or_else(local_user, str::to_string),
+                    )),
+                    "localuser" => Some(match_pattern_list(value, &local_user())),
+                    // exec, localnetwork, tagged... depend on the connection
+                    _ => None,
+                }
+            }
+        };
+
+        match matched {
+            Some(matched) if matched == negated => return Some(false),
+            Some(_) => {}
+            None => unknown = true,
+        }
+    }
+
+    (!unknown).then_some(true)
+}
+
+fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+/// Matches a comma separated list of patterns like OpenSSH: a negated
+/// pattern (`!pattern`) that matches rejects the value, otherwise one of the
+/// other patterns has to match.
+fn match_pattern_list(patterns: &str, value: &str) -> bool {
+    let value = value.to_lowercase();
+    let mut matched = false;
+
+    for pattern in patterns.split(',').filter(|pattern| !pattern.is_empty()) {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+
+        if match_pattern(&pattern.to_lowercase(), &value) {
+            if negated {
+                return false;
+            }
+            matched = true;
+        }
+    }
+
+    matched
+}
+
+/// Wildcard matching where `*` matches any sequence and `?` a single
+/// character.
+fn match_pattern(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    // Position of the last `*` and of the value when it was met
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut v) = (0, 0);
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, v));
+            p += 1;
+        } else if let Some((star_p, star_v)) = star {
+            // Let the `*` absorb one more character
+            p = star_p + 1;
+            v = star_v + 1;
+            star = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssh_cfg::SshHostConfig;
+
+    fn block(kind: BlockKind, host: &str, options: &[(SshOptionKey, &str)]) -> SshConfigBlock {
+        let mut config = SshHostConfig::default();
+        for (key, value) in options {
+            config.insert(*key, value.to_string());
+        }
+
+        SshConfigBlock {
+            kind,
+            host: host.to_string(),
+            config,
+            comment: None,
+            source_file: PathBuf::from("config"),
+        }
+    }
+
+    fn origins(resolved: &ResolvedConfig) -> Vec<(String, &str, &str)> {
+        resolved
+            .options
+            .iter()
+            .map(|option| {
+                (
+                    option.key.to_string(),
+                    option.value.as_str(),
+                    option.origin.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_match_pattern() {
+        assert!(match_pattern("*", "anything"));
+        assert!(match_pattern("*.corp", "db.corp"));
+        assert!(!match_pattern("*.corp", "db.corp.com"));
+        assert!(match_pattern("web-??", "web-01"));
+        assert!(!match_pattern("web-??", "web-1"));
+        assert!(match_pattern("a*b*c", "aXbYc"));
+        assert!(!match_pattern("a*b*c", "aXcYb"));
+    }
+
+    #[test]
+    fn test_match_pattern_list() {
+        assert!(match_pattern_list("*.corp,bastion", "BASTION"));
+        assert!(!match_pattern_list("*.corp,!db.corp", "db.corp"));
+        assert!(match_pattern_list("*.corp,!db.corp", "web.corp"));
+        // A negated pattern alone never matches
+        assert!(!match_pattern_list("!db", "web"));
+    }
+
+    #[test]
+    fn test_first_match_wins() {
+        let blocks = vec![
+            block(
+                BlockKind::Host,
+                "web.corp",
+                &[(SshOptionKey::User, "deploy")],
+            ),
+            block(
+                BlockKind::Host,
+                "*.corp",
+                &[
+                    (SshOptionKey::User, "admin"),
+                    (SshOptionKey::ProxyJump, "bastion"),
+                ],
+            ),
+            block(BlockKind::Host, "db.corp", &[(SshOptionKey::Port, "5432")]),
+            block(
+                BlockKind::Host,
+                "*",
+                &[
+                    (SshOptionKey::ServerAliveInterval, "30"),
+                    (SshOptionKey::User, "root"),
+                ],
+            ),
+        ];
+
+        let resolved = resolve(&blocks, "web.corp");
+        assert_eq!(
+            origins(&resolved),
+            vec![
+                ("User".to_string(), "deploy", "Host web.corp"),
+                ("ProxyJump".to_string(), "bastion", "Host *.corp"),
+                ("ServerAliveInterval".to_string(), "30", "Host *"),
+            ]
+        );
+        assert!(resolved.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_match_blocks() {
+        let blocks = vec![
+            block(
+                BlockKind::Host,
+                "prod/web other",
+                &[(SshOptionKey::Hostname, "web.corp")],
+            ),
+            block(
+                BlockKind::Match,
+                "host *.corp !user admin",
+                &[(SshOptionKey::ProxyJump, "bastion")],
+            ),
+            block(
+                BlockKind::Match,
+                "originalhost prod/* exec \"test -f /tmp/vpn\"",
+                &[(SshOptionKey::Port, "2222")],
+            ),
+            block(
+                BlockKind::Match,
+                "originalhost staging/*",
+                &[(SshOptionKey::Port, "22")],
+            ),
+            block(BlockKind::Match, "all", &[(SshOptionKey::User, "deploy")]),
+        ];
+
+        let resolved = resolve(&blocks, "prod/web");
+        assert_eq!(
+            origins(&resolved),
+            vec![
+                ("Hostname".to_string(), "web.corp", "Host prod/web other"),
+                (
+                    "ProxyJump".to_string(),
+                    "bastion",
+                    "Match host *.corp !user admin"
+                ),
+                ("User".to_string(), "deploy", "Match all"),
+            ]
+        );
+        assert_eq!(
+            resolved.skipped,
+            vec!["Match originalhost prod/* exec \"test -f /tmp/vpn\""]
+        );
+    }
+
+    #[test]
+    fn test_failed_criterion_wins_over_unknown_one() {
+        let blocks = vec![block(
+            BlockKind::Match,
+            "exec true originalhost staging/*",
+            &[(SshOptionKey::Port, "22")],
+        )];
+
+        let resolved = resolve(&blocks, "prod/web");
+        assert!(resolved.options.is_empty());
+        assert!(resolved.skipped.is_empty());
+    }
+}
@@ -1,86 +1,244 @@
-use crate::database::{FileDatabase, HostDatabaseEntry};
+use crate::database::{Favorite, FileDatabase, HostDatabaseEntry};
+use crate::host_metadata::HostMetadata;
+use crate::ssh_config_loader::{split_arguments, LoadedSshConfig, SshConfigBlock, SshConfigLoader};
 use anyhow::{format_err, Result};
-use ssh_cfg::{SshConfig, SshConfigParser, SshHostConfig};
-use std::collections::HashMap;
+use ssh_cfg::{SshConfig, SshHostConfig, SshOptionKey};
 use std::fmt::Debug;
-use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // Constants for group names
+pub const FAVORITES_GROUP: &str = "Favorites";
 pub const RECENTS_GROUP: &str = "Recents";
-const OTHERS_GROUP: &str = "Others";
+pub const OTHERS_GROUP: &str = "Others";
+/// Groups listing the hosts of a tag are named after it, e.g. `#postgres`
+pub const TAG_GROUP_PREFIX: &str = "#";
 const RECENTS_LIMIT: usize = 20;
 
-trait ConfigComments {
-    fn get_comments(&self) -> HashMap<String, String>;
-}
-
-impl ConfigComments for SshConfig {
-    fn get_comments(&self) -> HashMap<String, String> {
-        let mut comments = HashMap::new();
-
-        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        let config_path = PathBuf::from(home).join(".ssh/config");
-
-        if let Ok(contents) = read_to_string(config_path) {
-            let mut current_comment = String::new();
-
-            for line in contents.lines() {
-                let trimmed = line.trim();
-
-                if let Some(comment_text) = trimmed.strip_prefix('#') {
-                    if !current_comment.is_empty() {
-                        current_comment.push('\n');
-                    }
-                    current_comment.push_str(comment_text.trim());
-                } else if let Some(host) = trimmed.strip_prefix("Host ") {
-                    if !current_comment.is_empty() {
-                        comments.insert(host.trim().to_string(), std::mem::take(&mut current_comment));
-                    }
-                } else if trimmed.is_empty() {
-                    current_comment.clear();
-                }
-            }
-        }
-
-        comments
-    }
-}
-
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct SshGroupItem {
     pub name: String,
     pub full_name: String,
     pub connection_count: i64,
     pub last_used: i64,
     pub host_config: SshHostConfig,
+    /// Notes written above the `Host` line, without the metadata lines
     pub comment: Option<String>,
+    pub metadata: HostMetadata,
+    pub source_file: PathBuf,
+}
+
+impl SshGroupItem {
+    /// A host that was not read from a file, e.g. in tests: it has no
+    /// connection, notes nor metadata and is named as in its own group.
+    pub fn new(full_name: &str, options: &[(SshOptionKey, &str)]) -> SshGroupItem {
+        let mut host_config = SshHostConfig::default();
+        for (key, value) in options {
+            host_config.insert(*key, value.to_string());
+        }
+
+        SshGroupItem {
+            name: full_name
+                .rsplit_once('/')
+                .map_or(full_name, |(_, name)| name)
+                .to_string(),
+            full_name: full_name.to_string(),
+            connection_count: 0,
+            last_used: 0,
+            host_config,
+            comment: None,
+            metadata: HostMetadata::default(),
+            source_file: PathBuf::new(),
+        }
+    }
+
+    /// Name of the group the host is declared in, e.g. `client/prod` for
+    /// `client/prod/web1`.
+    #[inline]
+    pub fn group_name(&self) -> &str {
+        self.full_name
+            .rsplit_once('/')
+            .map_or(OTHERS_GROUP, |(group, _)| group)
+    }
+
+    /// Name given to ssh: the first pattern of the `Host` line, e.g. `web`
+    /// for `Host web web.example.com`.
+    pub fn ssh_name(&self) -> String {
+        split_arguments(&self.full_name)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| self.full_name.clone())
+    }
+
+    /// Whether the host is in the group or one of its subgroups.
+    #[inline]
+    pub fn in_group(&self, group: &str) -> bool {
+        is_in_group(self.group_name(), group)
+    }
+}
+
+/// Whether `group_name` is `group` or one of its subgroups, ignoring case.
+pub fn is_in_group(group_name: &str, group: &str) -> bool {
+    let group_name = group_name.to_lowercase();
+    let group = group.to_lowercase();
+    group_name == group || group_name.starts_with(&format!("{}/", group))
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct SshGroup {
     pub name: String,
     pub items: Vec<SshGroupItem>,
 }
 
+impl SshGroup {
+    /// Groups built by Fast-SSH, listing hosts that also are in their own group.
+    #[inline]
+    pub fn is_virtual(&self) -> bool {
+        self.name == FAVORITES_GROUP
+            || self.name == RECENTS_GROUP
+            || self.name.starts_with(TAG_GROUP_PREFIX)
+    }
+
+    /// Nesting level, 0 for top-level groups.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        match self.is_virtual() {
+            true => 0,
+            false => self.name.matches('/').count(),
+        }
+    }
+
+    /// Last part of the group path, e.g. `prod` for `client/prod`.
+    #[inline]
+    pub fn label(&self) -> &str {
+        match self.is_virtual() {
+            true => &self.name,
+            false => self.name.rsplit('/').next().unwrap_or(&self.name),
+        }
+    }
+}
+
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct SshConfigStore {
     pub config: SshConfig,
     pub groups: Vec<SshGroup>,
+    pub files: Vec<PathBuf>,
+    /// Directories the `Include` patterns look in, to watch for new files
+    pub include_dirs: Vec<PathBuf>,
+    /// Every `Host` and `Match` block, in the order ssh reads them
+    pub blocks: Vec<SshConfigBlock>,
+    /// Starred hosts, including the ones missing from the config
+    pub favorites: Vec<Favorite>,
+    /// Problems met while loading, e.g. an include cycle or an unreadable
+    /// database entry. Shown by the caller, nothing is printed here.
+    pub warnings: Vec<String>,
 }
 
 impl SshConfigStore {
-    pub async fn new(db: &FileDatabase) -> Result<SshConfigStore> {
-        let ssh_config = SshConfigParser::parse_home().await?;
+    pub fn new(db: &FileDatabase, config_path: Option<&Path>) -> Result<SshConfigStore> {
+        let config_path = match config_path {
+            Some(path) => path.to_path_buf(),
+            None => SshConfigLoader::default_config_path()?,
+        };
+        let loaded = SshConfigLoader::new()?.load(&config_path)?;
+        SshConfigStore::from_loaded(loaded, db)
+    }
+
+    /// Every host once, as listed in its own group.
+    pub fn items(&self) -> impl Iterator<Item = &SshGroupItem> {
+        self.groups
+            .iter()
+            .filter(|group| !group.is_virtual())
+            .flat_map(|group| {
+                group
+                    .items
+                    .iter()
+                    .filter(|item| item.group_name() == group.name)
+            })
+    }
+
+    #[inline]
+    pub fn find_item(&self, full_name: &str) -> Option<&SshGroupItem> {
+        self.items().find(|item| item.full_name == full_name)
+    }
+
+    /// Whether the group at `idx` has subgroups, which directly follow it.
+    #[inline]
+    pub fn has_subgroups(&self, idx: usize) -> bool {
+        match (self.groups.get(idx), self.groups.get(idx + 1)) {
+            (Some(group), Some(next)) => !group.is_virtual() && next.depth() > group.depth(),
+            _ => false,
+        }
+    }
+
+    #[inline]
+    pub fn is_favorite(&self, full_name: &str) -> bool {
+        self.favorites.iter().any(|f| f.host_key == full_name)
+    }
+
+    /// Looks for a favorite that is no longer in the config but whose host
+    /// seems to have been renamed: a single other host has the same HostName,
+    /// or else the same name in another group. Returns the old and new names.
+    pub fn find_renamed_favorite(&self, ignored: &[String]) -> Option<(String, String)> {
+        let items: Vec<&SshGroupItem> = self
+            .items()
+            .filter(|item| !self.is_favorite(&item.full_name))
+            .collect();
+
+        let single = |matches: Vec<&&SshGroupItem>| match matches.as_slice() {
+            [item] => Some(item.full_name.clone()),
+            _ => None,
+        };
 
-        let comments = ssh_config.get_comments();
+        self.favorites
+            .iter()
+            .filter(|f| !ignored.contains(&f.host_key) && self.find_item(&f.host_key).is_none())
+            .find_map(|favorite| {
+                let short_name = favorite
+                    .host_key
+                    .rsplit_once('/')
+                    .map_or(favorite.host_key.as_str(), |(_, name)| name);
+
+                let same_hostname = favorite.hostname.as_ref().and_then(|hostname| {
+                    single(
+                        items
+                            .iter()
+                            .filter(|item| {
+                                item.host_config.get(&SshOptionKey::Hostname) == Some(hostname)
+                            })
+                            .collect(),
+                    )
+                });
+                let same_name = || {
+                    single(
+                        items
+                            .iter()
+                            .filter(|item| item.name == short_name)
+                            .collect(),
+                    )
+                };
+
+                same_hostname
+                    .or_else(same_name)
+                    .map(|new_name| (favorite.host_key.clone(), new_name))
+            })
+    }
 
+    pub fn from_loaded(loaded: LoadedSshConfig, db: &FileDatabase) -> Result<SshConfigStore> {
         let mut scs = SshConfigStore {
-            config: ssh_config,
+            config: loaded.to_ssh_config(),
             groups: Vec::new(),
+            files: loaded.files.clone(),
+            include_dirs: loaded.include_dirs.clone(),
+            blocks: Vec::new(),
+            favorites: Vec::new(),
+            warnings: loaded.warnings.clone(),
         };
 
-        scs.create_ssh_groups(db, &comments);
+        scs.create_ssh_groups(db, &loaded);
+        scs.blocks = loaded.blocks;
 
         if scs.groups.is_empty() {
             return Err(format_err!("Your configuration file contains no entries (or only wildcards) ! Please add at least one."));
@@ -89,11 +247,7 @@ impl SshConfigStore {
         Ok(scs)
     }
 
-    fn create_ssh_groups(
-        &mut self,
-        db: &FileDatabase,
-        comments: &std::collections::HashMap<String, String>,
-    ) {
+    fn create_ssh_groups(&mut self, db: &FileDatabase, loaded: &LoadedSshConfig) {
         let mut groups: Vec<SshGroup> = vec![SshGroup {
             name: OTHERS_GROUP.to_string(),
             items: Vec::new(),
@@ -106,28 +260,48 @@ impl SshConfigStore {
             }
 
             let host_entry = db.get_host_values(key).unwrap_or_else(|e| {
-                eprintln!("Warning: Failed to get database entry for '{}': {}", key, e);
+                self.warnings.push(format!(
+                    "Failed to get database entry for '{}': {}",
+                    key, e
+                ));
                 HostDatabaseEntry {
                     connection_count: 0,
                     last_used_date: 0,
                 }
             });
 
+            let block = loaded.get_block(key);
+            let (metadata, comment) = block
+                .and_then(|b| b.comment.as_deref())
+                .map_or((HostMetadata::default(), None), HostMetadata::parse);
+
             let group_item = SshGroupItem {
                 connection_count: host_entry.connection_count,
                 last_used: host_entry.last_used_date,
                 full_name: key.to_string(),
                 host_config: value.clone(),
-                comment: comments.get(key).cloned(),
-                name: String::new(), // Temporary, will be set below
+                comment,
+                metadata,
+                source_file: block.map(|b| b.source_file.clone()).unwrap_or_default(),
+                name: key.to_string(), // Relative to each group, set below
             };
 
-            if let Some(slash_pos) = key.find('/') {
-                let (group_name, item_name) = key.split_at(slash_pos);
-                let item_name = &item_name[1..]; // Skip the '/'
+            if !key.contains('/') {
+                // Add to "Others" group (first in vec)
+                // Safe: "Others" group is always initialized at position 0
+                if let Some(others_group) = groups.first_mut() {
+                    others_group.items.push(group_item);
+                }
+                return;
+            }
+
+            // The host is listed in its group and in every parent group, named
+            // after its path from there (`client` lists `prod/web1`)
+            for (slash_pos, _) in key.match_indices('/') {
+                let group_name = &key[..slash_pos];
 
-                let mut group_item = group_item;
-                group_item.name = item_name.to_string();
+                let mut group_item = group_item.clone();
+                group_item.name = key[slash_pos + 1..].to_string();
 
                 // Find or create the group
                 if let Some(group) = groups.iter_mut().find(|g| g.name == group_name) {
@@ -138,29 +312,59 @@ impl SshConfigStore {
                         items: vec![group_item],
                     });
                 }
-            } else {
-                // Add to "Others" group (first in vec)
-                let mut group_item = group_item;
-                group_item.name = key.to_string();
-                // Safe: "Others" group is always initialized at position 0
-                if let Some(others_group) = groups.first_mut() {
-                    others_group.items.push(group_item);
-                }
             }
         });
 
-        groups.reverse();
+        // Top-level groups in reverse order of appearance, each one followed by
+        // its subgroups sorted by name
+        let mut order: Vec<&str> = Vec::new();
+        for group in groups.iter().rev().filter(|g| g.depth() == 0) {
+            order.push(&group.name);
+
+            let mut subgroups: Vec<&str> = groups
+                .iter()
+                .filter(|g| g.name.starts_with(&format!("{}/", group.name)))
+                .map(|g| g.name.as_str())
+                .collect();
+            subgroups.sort_by(|a, b| a.split('/').cmp(b.split('/')));
+            order.extend(subgroups);
+        }
+        let order: Vec<String> = order.into_iter().map(str::to_string).collect();
+
+        groups.sort_by_key(|g| order.iter().position(|name| *name == g.name));
         self.groups = groups.into_iter().filter(|g| !g.items.is_empty()).collect();
 
+        // Create a group for each tag, after the others
+        let mut tag_groups: Vec<SshGroup> = Vec::new();
+        for item in self.items() {
+            for tag in &item.metadata.tags {
+                let name = format!("{}{}", TAG_GROUP_PREFIX, tag.to_lowercase());
+
+                // Hosts come from several groups, show their full name
+                let mut tag_item = item.clone();
+                tag_item.name = item.full_name.clone();
+
+                match tag_groups.iter_mut().find(|g| g.name == name) {
+                    Some(group) => group.items.push(tag_item),
+                    None => tag_groups.push(SshGroup {
+                        name,
+                        items: vec![tag_item],
+                    }),
+                }
+            }
+        }
+        tag_groups.sort_by(|a, b| a.name.cmp(&b.name));
+        for group in &mut tag_groups {
+            group.items.sort_by_key(|item| item.name.to_lowercase());
+        }
+        self.groups.extend(tag_groups);
+
         // Create "Recents" group from used items
-        let mut all_used_items: Vec<SshGroupItem> = self
-            .groups
-            .iter()
-            .flat_map(|g| g.items.iter().filter(|i| i.last_used > 0).cloned())
-            .collect();
+        let mut all_used_items: Vec<SshGroupItem> =
+            self.items().filter(|i| i.last_used > 0).cloned().collect();
 
         if !all_used_items.is_empty() {
-            all_used_items.sort_unstable_by(|a, b| b.last_used.cmp(&a.last_used));
+            all_used_items.sort_unstable_by_key(|item| std::cmp::Reverse(item.last_used));
             all_used_items.truncate(RECENTS_LIMIT);
 
             self.groups.insert(
@@ -171,6 +375,28 @@ impl SshConfigStore {
                 },
             );
         }
+
+        // Create "Favorites" group, in the order chosen by the user
+        self.favorites = db.get_favorites().unwrap_or_else(|e| {
+            self.warnings.push(format!("Failed to get the favorites: {}", e));
+            Vec::new()
+        });
+
+        let favorite_items: Vec<SshGroupItem> = self
+            .favorites
+            .iter()
+            .filter_map(|favorite| self.find_item(&favorite.host_key).cloned())
+            .collect();
+
+        if !favorite_items.is_empty() {
+            self.groups.insert(
+                0,
+                SshGroup {
+                    name: FAVORITES_GROUP.to_string(),
+                    items: favorite_items,
+                },
+            );
+        }
     }
 }
 
@@ -202,6 +428,8 @@ mod tests {
             last_used: 1234567890,
             host_config: SshHostConfig::default(),
             comment: Some("Test server".to_string()),
+            metadata: Default::default(),
+            source_file: PathBuf::from("/home/user/.ssh/config"),
         };
 
         assert_eq!(item.name, "test-server");
@@ -209,6 +437,7 @@ mod tests {
         assert_eq!(item.connection_count, 5);
         assert_eq!(item.last_used, 1234567890);
         assert_eq!(item.comment, Some("Test server".to_string()));
+        assert_eq!(item.source_file, PathBuf::from("/home/user/.ssh/config"));
     }
 
     #[test]
@@ -220,11 +449,49 @@ mod tests {
             last_used: 0,
             host_config: SshHostConfig::default(),
             comment: None,
+            metadata: Default::default(),
+            source_file: PathBuf::new(),
         };
 
         assert_eq!(item.comment, None);
     }
 
+    #[test]
+    fn test_ssh_group_item_group_name() {
+        let mut item = SshGroupItem {
+            name: "web".to_string(),
+            full_name: "production/web".to_string(),
+            connection_count: 0,
+            last_used: 0,
+            host_config: SshHostConfig::default(),
+            comment: None,
+            metadata: Default::default(),
+            source_file: PathBuf::new(),
+        };
+        assert_eq!(item.group_name(), "production");
+
+        item.full_name = "web".to_string();
+        assert_eq!(item.group_name(), OTHERS_GROUP);
+    }
+
+    #[test]
+    fn test_ssh_group_item_ssh_name() {
+        let mut item = SshGroupItem {
+            name: "web".to_string(),
+            full_name: "prod/web web.example.com".to_string(),
+            connection_count: 0,
+            last_used: 0,
+            host_config: SshHostConfig::default(),
+            comment: None,
+            metadata: Default::default(),
+            source_file: PathBuf::new(),
+        };
+        assert_eq!(item.ssh_name(), "prod/web");
+
+        item.full_name = "\"my server\" other".to_string();
+        assert_eq!(item.ssh_name(), "my server");
+    }
+
     #[test]
     fn test_ssh_group_creation() {
         let group = SshGroup {
@@ -245,6 +512,8 @@ mod tests {
             last_used: 100,
             host_config: SshHostConfig::default(),
             comment: None,
+            metadata: Default::default(),
+            source_file: PathBuf::new(),
         };
 
         let item2 = SshGroupItem {
@@ -254,6 +523,8 @@ mod tests {
             last_used: 200,
             host_config: SshHostConfig::default(),
             comment: None,
+            metadata: Default::default(),
+            source_file: PathBuf::new(),
         };
 
         let group = SshGroup {
@@ -267,12 +538,210 @@ mod tests {
     }
 
     #[test]
-    fn test_get_comments_returns_hashmap() {
-        let config = SshConfig::default();
-        let comments = config.get_comments();
-        // Should return a HashMap (may contain actual comments from user's SSH config)
-        // Just verify it doesn't panic and returns a HashMap
-        let _ = comments.len();
+    fn test_groups_merged_from_included_files() {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("store_includes_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&test_dir);
+        std::fs::create_dir_all(test_dir.join("config.d")).unwrap();
+
+        let config_path = test_dir.join("config");
+        let included_path = test_dir.join("config.d/project");
+        std::fs::write(
+            &config_path,
+            format!(
+                "Include {}/*\n\nHost standalone\n    User me\n",
+                test_dir.join("config.d").display()
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            &included_path,
+            "# Web frontend\nHost project/web\n    HostName web.lan\n",
+        )
+        .unwrap();
+
+        let loaded = SshConfigLoader::new().unwrap().load(&config_path).unwrap();
+        let db = FileDatabase::new(test_dir.join("db.ron").to_str().unwrap()).unwrap();
+        let scs = SshConfigStore::from_loaded(loaded, &db).unwrap();
+
+        let project = scs.groups.iter().find(|g| g.name == "project").unwrap();
+        assert_eq!(project.items[0].name, "web");
+        assert_eq!(project.items[0].comment.as_deref(), Some("Web frontend"));
+        assert_eq!(project.items[0].source_file, included_path);
+
+        let others = scs.groups.iter().find(|g| g.name == OTHERS_GROUP).unwrap();
+        assert_eq!(others.items[0].source_file, config_path);
+        assert_eq!(scs.files.len(), 2);
+
+        let _ = std::fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_warnings_are_kept_in_the_store() {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("store_warnings_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&test_dir);
+        std::fs::create_dir_all(&test_dir).unwrap();
+
+        let config_path = test_dir.join("config");
+        std::fs::write(
+            &config_path,
+            format!("Include {}\n\nHost web\n    User me\n", config_path.display()),
+        )
+        .unwrap();
+
+        let db = FileDatabase::new(test_dir.join("db.ron").to_str().unwrap()).unwrap();
+        let scs = SshConfigStore::new(&db, Some(&config_path)).unwrap();
+
+        assert_eq!(scs.warnings.len(), 1);
+        assert!(scs.warnings[0].contains("cycle"));
+
+        let _ = std::fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_nested_groups() {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("store_nested_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&test_dir);
+        std::fs::create_dir_all(&test_dir).unwrap();
+
+        let config_path = test_dir.join("config");
+        std::fs::write(
+            &config_path,
+            "Host client/qa/web\nHost client/prod/web1\nHost client/prod-eu/web\nHost client/api\nHost lab/box\nHost solo\n",
+        )
+        .unwrap();
+
+        let loaded = SshConfigLoader::new().unwrap().load(&config_path).unwrap();
+        let db = FileDatabase::new(test_dir.join("db.ron").to_str().unwrap()).unwrap();
+        let scs = SshConfigStore::from_loaded(loaded, &db).unwrap();
+
+        // Each group is followed by its subgroups
+        let names: Vec<&str> = scs.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "lab",
+                "client",
+                "client/prod",
+                "client/prod-eu",
+                "client/qa",
+                OTHERS_GROUP
+            ]
+        );
+        assert!(scs.has_subgroups(1));
+        assert!(!scs.has_subgroups(2));
+        assert_eq!(scs.groups[2].depth(), 1);
+        assert_eq!(scs.groups[2].label(), "prod");
+
+        // Parent groups list the hosts beneath them, named from there
+        let mut client: Vec<&str> = scs.groups[1]
+            .items
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect();
+        client.sort();
+        assert_eq!(client, vec!["api", "prod-eu/web", "prod/web1", "qa/web"]);
+        assert_eq!(scs.groups[2].items[0].name, "web1");
+
+        // Every host is only listed once
+        assert_eq!(scs.items().count(), 6);
+        let item = scs.find_item("client/prod/web1").unwrap();
+        assert_eq!(item.group_name(), "client/prod");
+        assert!(item.in_group("Client"));
+        assert!(item.in_group("client/prod"));
+        assert!(!item.in_group("client/prod-eu"));
+        assert!(!item.in_group("clie"));
+
+        let _ = std::fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_favorites_group_and_renamed_favorites() {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("store_favorites_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&test_dir);
+        std::fs::create_dir_all(&test_dir).unwrap();
+
+        let config_path = test_dir.join("config");
+        std::fs::write(
+            &config_path,
+            "Host prod/web\n    HostName 10.0.0.1\n\nHost prod/db\n    HostName 10.0.0.2\n\nHost lab/cache\n",
+        )
+        .unwrap();
+
+        let db = FileDatabase::new(test_dir.join("db.ron").to_str().unwrap()).unwrap();
+        db.toggle_favorite("prod/db", Some("10.0.0.2")).unwrap();
+        db.toggle_favorite("prod/web", Some("10.0.0.1")).unwrap();
+        db.toggle_favorite("old/web", Some("10.0.0.1")).unwrap();
+        db.toggle_favorite("test/cache", None).unwrap();
+        db.toggle_favorite("gone", None).unwrap();
+
+        let loaded = SshConfigLoader::new().unwrap().load(&config_path).unwrap();
+        let scs = SshConfigStore::from_loaded(loaded, &db).unwrap();
+
+        // Favorites come first, in the user order, without the missing hosts
+        assert_eq!(scs.groups[0].name, FAVORITES_GROUP);
+        let names: Vec<&str> = scs.groups[0]
+            .items
+            .iter()
+            .map(|item| item.full_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["prod/db", "prod/web"]);
+        assert!(scs.find_item("prod/db").is_some());
+
+        // prod/web is already a favorite, so old/web is not matched by HostName
+        assert_eq!(
+            scs.find_renamed_favorite(&[]),
+            Some(("test/cache".to_string(), "lab/cache".to_string()))
+        );
+        assert_eq!(scs.find_renamed_favorite(&["test/cache".to_string()]), None);
+
+        let _ = std::fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_tag_groups() {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("store_tags_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&test_dir);
+        std::fs::create_dir_all(&test_dir).unwrap();
+
+        let config_path = test_dir.join("config");
+        std::fs::write(
+            &config_path,
+            "# Primary\n# @tags: prod, Postgres\n# @env: staging\nHost prod/db\n\n# @tags: postgres\nHost lab/db\n\nHost lab/box\n",
+        )
+        .unwrap();
+
+        let loaded = SshConfigLoader::new().unwrap().load(&config_path).unwrap();
+        let db = FileDatabase::new(test_dir.join("db.ron").to_str().unwrap()).unwrap();
+        let scs = SshConfigStore::from_loaded(loaded, &db).unwrap();
+
+        let item = scs.find_item("prod/db").unwrap();
+        assert_eq!(item.metadata.tags, vec!["prod", "Postgres"]);
+        assert_eq!(item.metadata.get("env"), Some("staging"));
+        assert_eq!(item.comment.as_deref(), Some("Primary"));
+
+        // Tag groups come last and are not counted as hosts
+        let names: Vec<&str> = scs.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["lab", "prod", "#postgres", "#prod"]);
+        assert!(scs.groups[2].is_virtual());
+        let postgres: Vec<&str> = scs.groups[2]
+            .items
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect();
+        assert_eq!(postgres, vec!["lab/db", "prod/db"]);
+        assert_eq!(scs.items().count(), 3);
+
+        let _ = std::fs::remove_dir_all(&test_dir);
     }
 
     #[test]
@@ -284,6 +753,8 @@ mod tests {
             last_used: 123,
             host_config: SshHostConfig::default(),
             comment: Some("comment".to_string()),
+            metadata: Default::default(),
+            source_file: PathBuf::new(),
         };
 
         let cloned = item.clone();
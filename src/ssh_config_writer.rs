@@ -0,0 +1,532 @@
+use anyhow::{format_err, Context, Result};
+use ssh_cfg::SshOptionKey;
+use std::fs::{self, OpenOptions, Permissions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::ssh_config_loader::split_key_value;
+
+// Indentation used for options when the block has none to copy
+const DEFAULT_INDENT: &str = "    ";
+
+/// A host as written by the editor. Options with an empty value are removed
+/// from the block, options that are not listed are left untouched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostEntry {
+    pub host: String,
+    pub options: Vec<(SshOptionKey, String)>,
+    pub notes: Option<String>,
+}
+
+/// Line ranges of a `Host` block inside a file.
+#[derive(Debug, PartialEq)]
+struct BlockRange {
+    /// First line of the notes written right above the `Host` line
+    notes_start: usize,
+    host_line: usize,
+    /// One past the last option line of the block
+    end: usize,
+}
+
+/// Rewrites the block of `host` with the values of `entry`, keeping every
+/// other line of the file as is.
+pub fn update_host(contents: &str, host: &str, entry: &HostEntry) -> Result<String> {
+    let mut lines = split_lines(contents);
+    let block = find_block(&lines, host)
+        .ok_or_else(|| format_err!("Host `{}` was not found in the file", host))?;
+
+    let indent = block_indent(&lines, &block);
+    let mut end = block.end;
+
+    for (key, value) in &entry.options {
+        let existing = (block.host_line + 1..end).find(|&idx| line_key(&lines[idx]) == Some(*key));
+
+        match (existing, value.is_empty()) {
+            (Some(idx), true) => {
+                lines.remove(idx);
+                end -= 1;
+            }
+            (Some(idx), false) => lines[idx] = replace_value(&lines[idx], value),
+            (None, false) => {
+                lines.insert(end, format!("{}{} {}", indent, option_name(*key), value));
+                end += 1;
+            }
+            (None, true) => {}
+        }
+    }
+
+    lines[block.host_line] = replace_value(&lines[block.host_line], &entry.host);
+    lines.splice(
+        block.notes_start..block.host_line,
+        notes_lines(&entry.notes),
+    );
+
+    Ok(join_lines(lines, contents))
+}
+
+/// Adds a new block after the block of `after_host`, or at the end of the
+/// file when it is `None`.
+pub fn insert_host(contents: &str, after_host: Option<&str>, entry: &HostEntry) -> Result<String> {
+    let mut lines = split_lines(contents);
+
+    let position = match after_host {
+        Some(after_host) => {
+            find_block(&lines, after_host)
+                .ok_or_else(|| format_err!("Host `{}` was not found in the file", after_host))?
+                .end
+        }
+        None => lines.len(),
+    };
+
+    let mut new_lines = Vec::new();
+    if position > 0 && !lines[position - 1].trim().is_empty() {
+        new_lines.push(String::new());
+    }
+    new_lines.extend(notes_lines(&entry.notes));
+    new_lines.push(format!("Host {}", entry.host));
+    new_lines.extend(
+        entry
+            .options
+            .iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| format!("{}{} {}", DEFAULT_INDENT, option_name(*key), value)),
+    );
+    if position < lines.len() && !lines[position].trim().is_empty() {
+        new_lines.push(String::new());
+    }
+
+    lines.splice(position..position, new_lines);
+    Ok(join_lines(lines, contents))
+}
+
+/// Removes the block of `host` along with its notes.
+pub fn delete_host(contents: &str, host: &str) -> Result<String> {
+    let mut lines = split_lines(contents);
+    let block = find_block(&lines, host)
+        .ok_or_else(|| format_err!("Host `{}` was not found in the file", host))?;
+
+    lines.drain(block.notes_start..block.end);
+
+    // Avoid leaving two blank lines where the block was
+    let start = block.notes_start;
+    if start == lines.len() {
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+    } else if lines[start].trim().is_empty() && (start == 0 || lines[start - 1].trim().is_empty()) {
+        lines.remove(start);
+    }
+
+    Ok(join_lines(lines, contents))
+}
+
+/// Copies the file to `<file>.bak` then replaces its contents. A symlink is
+/// followed, so that the file it points to is the one replaced.
+pub fn write_with_backup(path: &Path, contents: &str) -> Result<()> {
+    let target =
+        fs::canonicalize(path).with_context(|| format!("Could not read {}", path.display()))?;
+    let permissions = fs::metadata(&target)
+        .with_context(|| format!("Could not read {}", path.display()))?
+        .permissions();
+
+    let mut backup = target.as_os_str().to_owned();
+    backup.push(".bak");
+
+    fs::copy(&target, &backup).with_context(|| format!("Could not back up {}", path.display()))?;
+
+    // Write next to the file then rename, so a failure never truncates it
+    let mut tmp = target.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    write_new_file(&tmp, contents, &permissions)
+        .and_then(|()| fs::set_permissions(&tmp, permissions))
+        .and_then(|()| fs::rename(&tmp, &target))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+        .with_context(|| format!("Could not write {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Creates the file with the given permissions from the start, so that the
+/// contents are never readable by more users than the original file.
+fn write_new_file(path: &Path, contents: &str, permissions: &Permissions) -> io::Result<()> {
+    // A file left by an interrupted write would keep its own permissions
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(permissions.mode());
+    }
+    #[cfg(not(unix))]
+    let _ = permissions;
+
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+fn find_block(lines: &[String], host: &str) -> Option<BlockRange> {
+    let host_line = lines.iter().rposition(|line| {
+        line_key(line) == Some(SshOptionKey::Host)
+            && split_key_value(line_content(line)).is_some_and(|(_, value)| value == host)
+    })?;
+
+    let mut end = (host_line + 1..lines.len())
+        .find(|&idx| {
+            matches!(
+                line_key(&lines[idx]),
+                Some(SshOptionKey::Host) | Some(SshOptionKey::Match)
+            )
+        })
+        .unwrap_or(lines.len());
+
+    // Blank lines and the notes of the next block are not part of this one
+    while end > host_line + 1 && line_content(&lines[end - 1]).is_empty() {
+        end -= 1;
+    }
+
+    let mut notes_start = host_line;
+    while notes_start > 0 && lines[notes_start - 1].trim().starts_with('#') {
+        notes_start -= 1;
+    }
+
+    Some(BlockRange {
+        notes_start,
+        host_line,
+        end,
+    })
+}
+
+fn block_indent(lines: &[String], block: &BlockRange) -> String {
+    lines[block.host_line + 1..block.end]
+        .iter()
+        .find(|line| !line_content(line).is_empty())
+        .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+        .unwrap_or_else(|| DEFAULT_INDENT.to_string())
+}
+
+/// Content of the line without its comment.
+fn line_content(line: &str) -> &str {
+    let trimmed = line.trim();
+    trimmed
+        .split_once('#')
+        .map_or(trimmed, |split| split.0)
+        .trim()
+}
+
+fn line_key(line: &str) -> Option<SshOptionKey> {
+    let (key, _) = split_key_value(line_content(line))?;
+    key.parse().ok()
+}
+
+/// Replaces the value of a `Key value` line, keeping its indentation,
+/// separator and trailing comment.
+fn replace_value(line: &str, value: &str) -> String {
+    let content = line_content(line);
+    let Some((_, old_value)) = split_key_value(content) else {
+        return line.to_string();
+    };
+
+    // The value is a suffix of the content, which starts after the indentation
+    let content_start = line.len() - line.trim_start().len();
+    let end = content_start + content.len();
+    let start = end - old_value.len();
+
+    format!("{}{}{}", &line[..start], value, &line[end..])
+}
+
+/// `ssh_cfg` parses `HostName` as `Hostname`, write it the usual way.
+fn option_name(key: SshOptionKey) -> String {
+    match key {
+        SshOptionKey::Hostname => "HostName".to_string(),
+        key => key.to_string(),
+    }
+}
+
+fn notes_lines(notes: &Option<String>) -> Vec<String> {
+    notes
+        .iter()
+        .flat_map(|notes| notes.lines())
+        .map(|line| format!("# {}", line.trim()))
+        .collect()
+}
+
+fn split_lines(contents: &str) -> Vec<String> {
+    contents.lines().map(str::to_string).collect()
+}
+
+fn join_lines(lines: Vec<String>, original: &str) -> String {
+    let mut contents = lines.join("\n");
+    if !lines.is_empty() && (original.is_empty() || original.ends_with('\n')) {
+        contents.push('\n');
+    }
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+Host *
+    ServerAliveInterval 30
+
+# Main web server
+Host prod/web   # frontend
+    HostName 10.0.0.1
+    User root
+    # keep this
+    Port 22
+
+# Database
+Host prod/db
+\tHostName 10.0.0.2
+";
+
+    fn entry(host: &str, options: &[(SshOptionKey, &str)], notes: Option<&str>) -> HostEntry {
+        HostEntry {
+            host: host.to_string(),
+            options: options
+                .iter()
+                .map(|(key, value)| (*key, value.to_string()))
+                .collect(),
+            notes: notes.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_find_block() {
+        let lines = split_lines(CONFIG);
+
+        assert_eq!(
+            find_block(&lines, "prod/web"),
+            Some(BlockRange {
+                notes_start: 3,
+                host_line: 4,
+                end: 9
+            })
+        );
+        assert_eq!(
+            find_block(&lines, "prod/db"),
+            Some(BlockRange {
+                notes_start: 10,
+                host_line: 11,
+                end: 13
+            })
+        );
+        assert_eq!(find_block(&lines, "prod"), None);
+    }
+
+    #[test]
+    fn test_replace_value() {
+        assert_eq!(replace_value("    User root", "admin"), "    User admin");
+        assert_eq!(replace_value("Port=22 # ssh", "2222"), "Port=2222 # ssh");
+        assert_eq!(
+            replace_value("Host prod/web   # frontend", "prod/www"),
+            "Host prod/www   # frontend"
+        );
+    }
+
+    #[test]
+    fn test_update_host_keeps_unrelated_lines() {
+        let updated = update_host(
+            CONFIG,
+            "prod/web",
+            &entry(
+                "prod/www",
+                &[
+                    (SshOptionKey::Hostname, "10.0.0.10"),
+                    (SshOptionKey::User, ""),
+                    (SshOptionKey::IdentityFile, "~/.ssh/id_web"),
+                ],
+                Some("Main web server\nSecond line"),
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            updated,
+            "\
+Host *
+    ServerAliveInterval 30
+
+# Main web server
+# Second line
+Host prod/www   # frontend
+    HostName 10.0.0.10
+    # keep this
+    Port 22
+    IdentityFile ~/.ssh/id_web
+
+# Database
+Host prod/db
+\tHostName 10.0.0.2
+"
+        );
+    }
+
+    #[test]
+    fn test_update_host_copies_indentation_and_removes_notes() {
+        let updated = update_host(
+            CONFIG,
+            "prod/db",
+            &entry("prod/db", &[(SshOptionKey::User, "postgres")], None),
+        )
+        .unwrap();
+
+        assert!(updated.ends_with("\n\nHost prod/db\n\tHostName 10.0.0.2\n\tUser postgres\n"));
+    }
+
+    #[test]
+    fn test_update_missing_host() {
+        assert!(update_host(CONFIG, "nope", &entry("nope", &[], None)).is_err());
+    }
+
+    #[test]
+    fn test_insert_host_at_end() {
+        let updated = insert_host(
+            CONFIG,
+            None,
+            &entry(
+                "staging/web",
+                &[
+                    (SshOptionKey::Hostname, "10.0.1.1"),
+                    (SshOptionKey::Port, ""),
+                ],
+                Some("Staging"),
+            ),
+        )
+        .unwrap();
+
+        assert!(updated.starts_with(CONFIG));
+        assert!(updated.ends_with("\n\n# Staging\nHost staging/web\n    HostName 10.0.1.1\n"));
+    }
+
+    #[test]
+    fn test_insert_host_after_block() {
+        let updated = insert_host(
+            CONFIG,
+            Some("prod/web"),
+            &entry("prod/web-copy", &[(SshOptionKey::User, "root")], None),
+        )
+        .unwrap();
+
+        assert!(updated.contains(
+            "    Port 22\n\nHost prod/web-copy\n    User root\n\n# Database\nHost prod/db\n"
+        ));
+    }
+
+    #[test]
+    fn test_delete_host() {
+        let updated = delete_host(CONFIG, "prod/web").unwrap();
+
+        assert_eq!(
+            updated,
+            "\
+Host *
+    ServerAliveInterval 30
+
+# Database
+Host prod/db
+\tHostName 10.0.0.2
+"
+        );
+
+        let updated = delete_host(CONFIG, "prod/db").unwrap();
+        assert!(updated.ends_with("    Port 22\n"));
+    }
+
+    #[test]
+    fn test_write_with_backup() {
+        let test_dir = std::env::temp_dir().join("fast_ssh_tests");
+        fs::create_dir_all(&test_dir).unwrap();
+        let path = test_dir.join(format!("writer_{}", std::process::id()));
+        let backup = test_dir.join(format!("writer_{}.bak", std::process::id()));
+        fs::write(&path, "old").unwrap();
+
+        write_with_backup(&path, "new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "old");
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&backup);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_with_backup_follows_symlink() {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("writer_symlink_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+        let target = test_dir.join("dotfiles_config");
+        let link = test_dir.join("config");
+        fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_with_backup(&link, "new").unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(
+            fs::read_to_string(test_dir.join("dotfiles_config.bak")).unwrap(),
+            "old"
+        );
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_with_backup_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("writer_permissions_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+        let path = test_dir.join("config");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_with_backup(&path, "new").unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_temporary_file_created_with_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("writer_tmp_permissions_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+        let tmp = test_dir.join("config.tmp");
+        // Left by an interrupted write
+        fs::write(&tmp, "stale").unwrap();
+        fs::set_permissions(&tmp, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_new_file(&tmp, "new", &fs::Permissions::from_mode(0o600)).unwrap();
+
+        let mode = fs::metadata(&tmp).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_to_string(&tmp).unwrap(), "new");
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
@@ -0,0 +1,210 @@
+use rustbreak::RustbreakError;
+use ssh_cfg::SshOptionKey;
+use sublime_fuzzy::best_match;
+
+use crate::{
+    database::{FileDatabase, HistoryFilter},
+    ssh_config_store::{SshConfigStore, SshGroupItem},
+};
+
+// Share of the score of a name matched against itself that the closest host
+// needs to be suggested, so that `db` is not taken for `database`
+const MIN_RENAME_SCORE: f64 = 0.75;
+
+/// What the database still records for a host that is no longer in the SSH
+/// config.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct StaleEntry {
+    pub key: String,
+    pub connection_count: i64,
+    pub last_used_date: i64,
+    /// Number of launches in the history
+    pub launches: usize,
+    /// Host of the config it was likely renamed to
+    pub rename: Option<String>,
+    /// Whether the database already records something for that host, in
+    /// which case renaming merges both entries
+    pub merges_existing: bool,
+}
+
+/// Lists the database keys matching no host, with a rename suggestion when
+/// one seems likely.
+pub fn find_stale_entries(
+    db: &FileDatabase,
+    scs: &SshConfigStore,
+) -> Result<Vec<StaleEntry>, RustbreakError> {
+    let items: Vec<&SshGroupItem> = scs.items().collect();
+    let stored_keys = db.stored_keys()?;
+    let mut entries = Vec::new();
+
+    for key in stored_keys.iter().cloned() {
+        if items.iter().any(|item| item.full_name == key) {
+            continue;
+        }
+
+        let values = db.get_host_values(&key)?;
+        let launches = db
+            .get_history(&HistoryFilter {
+                host: Some(key.clone()),
+                ..Default::default()
+            })?
+            .len();
+        let hostname = db.get_hostname(&key)?;
+        let rename = suggest_rename(&key, hostname.as_deref(), &items);
+
+        entries.push(StaleEntry {
+            merges_existing: rename
+                .as_ref()
+                .is_some_and(|new_key| stored_keys.contains(new_key)),
+            rename,
+            key,
+            connection_count: values.connection_count,
+            last_used_date: values.last_used_date,
+            launches,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Host the key was likely renamed to: the only one with the same HostName,
+/// or else the one whose name is the closest fuzzy match, if close enough.
+fn suggest_rename(key: &str, hostname: Option<&str>, items: &[&SshGroupItem]) -> Option<String> {
+    let same_hostname: Vec<&&SshGroupItem> = items
+        .iter()
+        .filter(|item| {
+            hostname.is_some()
+                && item
+                    .host_config
+                    .get(&SshOptionKey::Hostname)
+                    .map(String::as_str)
+                    == hostname
+        })
+        .collect();
+    if let [item] = same_hostname.as_slice() {
+        return Some(item.full_name.clone());
+    }
+
+    // Only the alias is compared, so that moving a host to another group is
+    // found too
+    let short_name = key.rsplit_once('/').map_or(key, |(_, name)| name);
+    let min_score = best_match(short_name, short_name).map_or(0, |m| m.score()) as f64;
+    items
+        .iter()
+        .filter_map(|item| best_match(short_name, &item.name).map(|m| (m.score(), item)))
+        .filter(|(score, _)| *score as f64 >= min_score * MIN_RENAME_SCORE)
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, item)| item.full_name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::HistoryEntry;
+    use crate::ssh_config_loader::SshConfigLoader;
+    use std::fs;
+
+    fn test_item(full_name: &str, hostname: &str) -> SshGroupItem {
+        SshGroupItem::new(full_name, &[(SshOptionKey::Hostname, hostname)])
+    }
+
+    #[test]
+    fn test_suggest_rename() {
+        let web = test_item("prod/web-server", "10.0.0.1");
+        let db = test_item("prod/database", "10.0.0.2");
+        let replica = test_item("prod/replica", "10.0.0.2");
+        let items = vec![&web, &db, &replica];
+
+        // The HostName wins over the name
+        assert_eq!(
+            suggest_rename("old/web", Some("10.0.0.2"), &[&web, &db]),
+            Some("prod/database".to_string())
+        );
+        assert_eq!(
+            suggest_rename("old/web", None, &items),
+            Some("prod/web-server".to_string())
+        );
+
+        // Two hosts share the HostName, the name decides
+        assert_eq!(
+            suggest_rename("staging/database", Some("10.0.0.2"), &items),
+            Some("prod/database".to_string())
+        );
+        assert_eq!(suggest_rename("mail", Some("10.0.0.9"), &items), None);
+
+        // A few letters in common are not enough
+        assert_eq!(suggest_rename("staging/db", None, &items), None);
+        assert_eq!(suggest_rename("staging/db", Some("10.0.0.2"), &items), None);
+    }
+
+    #[test]
+    fn test_find_stale_entries() {
+        let test_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("stale_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&test_dir);
+        fs::create_dir_all(&test_dir).unwrap();
+
+        let config_path = test_dir.join("config");
+        fs::write(
+            &config_path,
+            "Host prod/web\n    HostName 10.0.0.1\nHost new/db\n    HostName 10.0.0.2\n",
+        )
+        .unwrap();
+
+        let db = FileDatabase::new(test_dir.join("db.ron").to_str().unwrap()).unwrap();
+        db.record_connection("prod/web", Some("10.0.0.1"), 100)
+            .unwrap();
+        db.record_connection("old/db", Some("10.0.0.2"), 200)
+            .unwrap();
+        db.record_connection("old/web", Some("10.0.0.1"), 50)
+            .unwrap();
+        db.add_history_entry(HistoryEntry {
+            host_key: "gone".to_string(),
+            action: "ssh".to_string(),
+            started_at: 300,
+            ended_at: 360,
+            exit_code: Some(0),
+            command_line: "ssh gone".to_string(),
+        })
+        .unwrap();
+
+        let loaded = SshConfigLoader::new().unwrap().load(&config_path).unwrap();
+        let scs = SshConfigStore::from_loaded(loaded, &db).unwrap();
+
+        let entries = find_stale_entries(&db, &scs).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                StaleEntry {
+                    key: "gone".to_string(),
+                    connection_count: 0,
+                    last_used_date: 0,
+                    launches: 1,
+                    rename: None,
+                    merges_existing: false,
+                },
+                StaleEntry {
+                    key: "old/db".to_string(),
+                    connection_count: 1,
+                    last_used_date: 200,
+                    launches: 0,
+                    rename: Some("new/db".to_string()),
+                    merges_existing: false,
+                },
+                // `prod/web` has its own counts, they are added together
+                StaleEntry {
+                    key: "old/web".to_string(),
+                    connection_count: 1,
+                    last_used_date: 50,
+                    launches: 0,
+                    rename: Some("prod/web".to_string()),
+                    merges_existing: true,
+                },
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+}
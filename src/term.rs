@@ -2,21 +2,65 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::io::Stdout;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Stdout, Write};
 use tui::{backend::CrosstermBackend, Terminal};
 
-pub fn init_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>, Box<dyn std::error::Error>> {
+pub type TerminalBackend = CrosstermBackend<TerminalOutput>;
+
+/// Where the TUI is drawn. In pick mode it goes to the terminal itself, so
+/// that stdout only gets the picked host.
+pub enum TerminalOutput {
+    Stdout(Stdout),
+    Tty(File),
+}
+
+impl TerminalOutput {
+    #[inline]
+    pub fn stdout() -> TerminalOutput {
+        TerminalOutput::Stdout(io::stdout())
+    }
+
+    pub fn tty() -> io::Result<TerminalOutput> {
+        let path = match cfg!(windows) {
+            true => "CONOUT$",
+            false => "/dev/tty",
+        };
+
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(TerminalOutput::Tty(file))
+    }
+}
+
+impl Write for TerminalOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TerminalOutput::Stdout(stdout) => stdout.write(buf),
+            TerminalOutput::Tty(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TerminalOutput::Stdout(stdout) => stdout.flush(),
+            TerminalOutput::Tty(file) => file.flush(),
+        }
+    }
+}
+
+pub fn init_terminal(
+    mut output: TerminalOutput,
+) -> Result<Terminal<TerminalBackend>, Box<dyn std::error::Error>> {
     enable_raw_mode()?;
 
-    let mut stdout = std::io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
+    execute!(output, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(output);
     let terminal = Terminal::new(backend)?;
     Ok(terminal)
 }
 
 pub fn restore_terminal(
-    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    terminal: &mut Terminal<TerminalBackend>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     disable_raw_mode()?;
     execute!(
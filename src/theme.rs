@@ -34,7 +34,8 @@ macro_rules! def_theme_struct_with_defaults {
 def_theme_struct_with_defaults!(
     text_primary => Color::White,
     text_secondary => Color::Magenta,
-    border_color => Color::Magenta
+    border_color => Color::Magenta,
+    error_color => Color::Red
 );
 
 fn hex_to_color(hex: &str) -> Option<Color> {
@@ -145,6 +146,7 @@ mod tests {
             text_primary: Some(Color::Rgb(255, 0, 0)),
             text_secondary: Some(Color::Rgb(0, 255, 0)),
             border_color: Some(Color::Rgb(0, 0, 255)),
+            error_color: None,
         };
         assert_eq!(theme.text_primary(), Color::Rgb(255, 0, 0));
         assert_eq!(theme.text_secondary(), Color::Rgb(0, 255, 0));
@@ -157,6 +159,7 @@ mod tests {
             text_primary: Some(Color::Rgb(255, 0, 0)),
             text_secondary: None,
             border_color: None,
+            error_color: None,
         };
         assert_eq!(theme.text_primary(), Color::Rgb(255, 0, 0));
         assert_eq!(theme.text_secondary(), Color::Magenta); // Falls back to default
@@ -0,0 +1,636 @@
+use anyhow::{format_err, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+use crate::launcher;
+use fast_ssh::{SshConfigStore, SshGroupItem};
+
+/// Kind of forwarding, the `-L`, `-R` and `-D` options of ssh.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelKind {
+    #[default]
+    #[serde(alias = "L")]
+    Local,
+    #[serde(alias = "R")]
+    Remote,
+    #[serde(alias = "D")]
+    Dynamic,
+}
+
+impl TunnelKind {
+    #[inline]
+    pub fn flag(&self) -> &'static str {
+        match self {
+            TunnelKind::Local => "-L",
+            TunnelKind::Remote => "-R",
+            TunnelKind::Dynamic => "-D",
+        }
+    }
+}
+
+/// A tunnel defined in the `tunnels` section of the config, e.g. a local
+/// port forwarded to a database only reachable from a host.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TunnelConfig {
+    pub name: String,
+    /// Host of the SSH config the tunnel goes through
+    pub host: String,
+    #[serde(default)]
+    pub kind: TunnelKind,
+    /// Port listened on: on this machine for local and dynamic tunnels, on
+    /// the host for remote ones
+    pub port: u16,
+    /// `host:port` the connections are forwarded to, unused by dynamic tunnels
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+impl TunnelConfig {
+    /// Forwarding option given to ssh, e.g. `-L 5433:localhost:5432`.
+    pub fn forward_args(&self) -> [String; 2] {
+        let spec = match (&self.kind, &self.target) {
+            (TunnelKind::Dynamic, _) | (_, None) => self.port.to_string(),
+            (_, Some(target)) => format!("{}:{}", self.port, target),
+        };
+
+        [self.kind.flag().to_string(), spec]
+    }
+
+    /// Whether the port is listened on by this machine.
+    #[inline]
+    pub fn is_port_local(&self) -> bool {
+        self.kind != TunnelKind::Remote
+    }
+}
+
+/// Checks the names and targets of the tunnels.
+pub fn validate_tunnels(tunnels: &[TunnelConfig]) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for (idx, tunnel) in tunnels.iter().enumerate() {
+        if tunnel.name.trim().is_empty() {
+            errors.push(format!("Tunnel #{} has no name", idx + 1));
+        } else if tunnel.name.contains(['/', '\\']) {
+            errors.push(format!("Tunnel `{}` cannot contain a slash", tunnel.name));
+        } else if tunnels[..idx].iter().any(|other| other.name == tunnel.name) {
+            errors.push(format!("Tunnel `{}` is defined twice", tunnel.name));
+        }
+
+        if tunnel.port == 0 {
+            errors.push(format!("Tunnel `{}` has no port", tunnel.name));
+        }
+
+        let has_valid_target = tunnel.target.as_ref().is_some_and(|target| {
+            target
+                .rsplit_once(':')
+                .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+        });
+        match tunnel.kind {
+            TunnelKind::Dynamic if tunnel.target.is_some() => errors.push(format!(
+                "Dynamic tunnel `{}` cannot have a target",
+                tunnel.name
+            )),
+            TunnelKind::Local | TunnelKind::Remote if !has_valid_target => errors.push(format!(
+                "Tunnel `{}` needs a `host:port` target",
+                tunnel.name
+            )),
+            _ => {}
+        }
+    }
+
+    errors
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TunnelStatus {
+    Stopped,
+    /// ssh is running with the given PID
+    Running(u32),
+    /// ssh exited with the given code, `None` if it was terminated by a signal
+    Exited(Option<i32>),
+    /// The tunnel is stopped and another program listens on its port
+    PortInUse,
+}
+
+/// Starts and stops the tunnels as background ssh processes. Their PID and
+/// command line are written to a file so a later run of Fast-SSH can stop
+/// them, and not another program that was given the same PID since.
+pub struct TunnelManager {
+    pid_dir: PathBuf,
+    children: HashMap<String, Child>,
+    /// Tunnels started by an earlier run, with their PID and command line
+    attached: HashMap<String, (u32, Vec<String>)>,
+    exited: HashMap<String, Option<i32>>,
+}
+
+impl TunnelManager {
+    /// Reattaches to the tunnels whose PID file points to their still running
+    /// ssh process, the other PID files are removed.
+    pub fn new(pid_dir: PathBuf) -> TunnelManager {
+        let mut attached = HashMap::new();
+
+        for entry in fs::read_dir(&pid_dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|extension| extension != "pid") {
+                continue;
+            }
+            let Some(name) = path
+                .file_stem()
+                .map(|name| name.to_string_lossy().to_string())
+            else {
+                continue;
+            };
+
+            match fs::read_to_string(&path).ok().and_then(parse_pid_file) {
+                Some((pid, command_line)) if is_process_running(pid, &command_line) => {
+                    attached.insert(name, (pid, command_line));
+                }
+                _ => {
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+
+        TunnelManager {
+            pid_dir,
+            children: HashMap::new(),
+            attached,
+            exited: HashMap::new(),
+        }
+    }
+
+    /// Current state of the tunnel, noticing the ssh processes that exited.
+    pub fn status(&mut self, tunnel: &TunnelConfig) -> TunnelStatus {
+        if let Some(child) = self.children.get_mut(&tunnel.name) {
+            match child.try_wait() {
+                Ok(None) => return TunnelStatus::Running(child.id()),
+                Ok(Some(status)) => {
+                    self.exited.insert(tunnel.name.clone(), status.code());
+                }
+                Err(_) => {
+                    self.exited.insert(tunnel.name.clone(), None);
+                }
+            }
+            self.children.remove(&tunnel.name);
+            self.remove_pid_file(&tunnel.name);
+        }
+
+        if let Some((pid, command_line)) = self.attached.get(&tunnel.name) {
+            if is_process_running(*pid, command_line) {
+                return TunnelStatus::Running(*pid);
+            }
+            self.attached.remove(&tunnel.name);
+            self.remove_pid_file(&tunnel.name);
+        }
+
+        if tunnel.is_port_local() && is_port_in_use(tunnel.port) {
+            return TunnelStatus::PortInUse;
+        }
+
+        match self.exited.get(&tunnel.name) {
+            Some(code) => TunnelStatus::Exited(*code),
+            None => TunnelStatus::Stopped,
+        }
+    }
+
+    /// Starts ssh in the background to open the tunnel through its host of
+    /// the SSH config, returns its PID.
+    pub fn start(
+        &mut self,
+        tunnel: &TunnelConfig,
+        scs: &SshConfigStore,
+        ssh_config_path: Option<&Path>,
+    ) -> Result<u32> {
+        let host = scs
+            .find_item(&tunnel.host)
+            .ok_or_else(|| format_err!("Unknown host {} of tunnel {}", tunnel.host, tunnel.name))?;
+
+        self.spawn(tunnel, ssh_command(tunnel, host, ssh_config_path))
+    }
+
+    fn spawn(&mut self, tunnel: &TunnelConfig, mut command: Command) -> Result<u32> {
+        match self.status(tunnel) {
+            TunnelStatus::Running(_) => {
+                return Err(format_err!("Tunnel {} is already running", tunnel.name));
+            }
+            TunnelStatus::PortInUse => {
+                return Err(format_err!(
+                    "Port {} of tunnel {} is already in use",
+                    tunnel.port,
+                    tunnel.name
+                ));
+            }
+            TunnelStatus::Stopped | TunnelStatus::Exited(_) => {}
+        }
+
+        let command_line = command_line(&command);
+        let child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Could not start the tunnel {}", tunnel.name))?;
+        let pid = child.id();
+
+        fs::create_dir_all(&self.pid_dir)
+            .and_then(|_| {
+                let contents = format!("{}\n{}\n", pid, command_line.join("\n"));
+                fs::write(self.pid_file(&tunnel.name), contents)
+            })
+            .with_context(|| format!("Could not write the PID file of {}", tunnel.name))?;
+
+        self.exited.remove(&tunnel.name);
+        self.children.insert(tunnel.name.clone(), child);
+        Ok(pid)
+    }
+
+    /// Applies the tunnels of a reloaded config to the running ones: removed
+    /// tunnels are stopped, changed ones are started again with their new
+    /// options. Returns the names of the tunnels stopped or restarted.
+    pub fn apply_config(
+        &mut self,
+        old: &[TunnelConfig],
+        new: &[TunnelConfig],
+        scs: &SshConfigStore,
+        ssh_config_path: Option<&Path>,
+    ) -> Result<Vec<String>> {
+        let mut changed = Vec::new();
+
+        for tunnel in old {
+            if !matches!(self.status(tunnel), TunnelStatus::Running(_)) {
+                continue;
+            }
+
+            match new.iter().find(|new_tunnel| new_tunnel.name == tunnel.name) {
+                Some(new_tunnel) if new_tunnel == tunnel => continue,
+                Some(new_tunnel) => {
+                    self.stop(tunnel)?;
+                    self.start(new_tunnel, scs, ssh_config_path)?;
+                }
+                None => self.stop(tunnel)?,
+            }
+            changed.push(tunnel.name.clone());
+        }
+
+        Ok(changed)
+    }
+
+    /// Stops the ssh process of the tunnel, whichever run started it.
+    pub fn stop(&mut self, tunnel: &TunnelConfig) -> Result<()> {
+        if let Some(mut child) = self.children.remove(&tunnel.name) {
+            let _ = child.kill();
+            let status = child.wait()?;
+            self.exited.insert(tunnel.name.clone(), status.code());
+        } else if let Some((pid, command_line)) = self.attached.remove(&tunnel.name) {
+            // Nothing to stop if the PID now belongs to another program
+            if is_process_running(pid, &command_line) {
+                kill_process(pid)?;
+            }
+            self.exited.insert(tunnel.name.clone(), None);
+        }
+
+        self.remove_pid_file(&tunnel.name);
+        Ok(())
+    }
+
+    #[inline]
+    fn pid_file(&self, name: &str) -> PathBuf {
+        self.pid_dir.join(format!("{}.pid", name))
+    }
+
+    fn remove_pid_file(&self, name: &str) {
+        let _ = fs::remove_file(self.pid_file(name));
+    }
+}
+
+/// Builds the ssh process holding the tunnel open, without a terminal.
+fn ssh_command(
+    tunnel: &TunnelConfig,
+    host: &SshGroupItem,
+    ssh_config_path: Option<&Path>,
+) -> Command {
+    // Exit instead of running without the forwarding when it fails
+    let [flag, spec] = tunnel.forward_args();
+    let options = [
+        "-N",
+        "-o",
+        "ExitOnForwardFailure=yes",
+        "-o",
+        "BatchMode=yes",
+        &flag,
+        &spec,
+    ];
+
+    let mut command = Command::new("ssh");
+    command.args(launcher::ssh_args(host, &options, ssh_config_path));
+    command
+}
+
+/// Program and arguments of the command, as the system lists them.
+fn command_line(command: &Command) -> Vec<String> {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect()
+}
+
+/// Reads a PID file: the PID on the first line, then one argument of the
+/// command line per line.
+fn parse_pid_file(contents: String) -> Option<(u32, Vec<String>)> {
+    let mut lines = contents.lines();
+    let pid = lines.next()?.trim().parse::<u32>().ok()?;
+    let command_line: Vec<String> = lines.map(str::to_string).collect();
+
+    // Files written before the command line was recorded cannot be checked
+    match command_line.is_empty() {
+        true => None,
+        false => Some((pid, command_line)),
+    }
+}
+
+/// Whether another program listens on the local port.
+fn is_port_in_use(port: u16) -> bool {
+    TcpListener::bind(("127.0.0.1", port)).is_err()
+}
+
+/// Whether the process runs the given command line, and so was not replaced
+/// by another program reusing its PID.
+#[cfg(target_os = "linux")]
+fn is_process_running(pid: u32, command_line: &[String]) -> bool {
+    fs::read(format!("/proc/{}/cmdline", pid)).is_ok_and(|cmdline| {
+        String::from_utf8_lossy(&cmdline)
+            .split_terminator('\0')
+            .eq(command_line.iter().map(String::as_str))
+    })
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_process_running(pid: u32, command_line: &[String]) -> bool {
+    Command::new("ps")
+        .args(["-o", "command=", "-p", &pid.to_string()])
+        .output()
+        .is_ok_and(|output| {
+            output.status.success()
+                && String::from_utf8_lossy(&output.stdout).trim() == command_line.join(" ")
+        })
+}
+
+/// Only the program is checked, the arguments are not listed by `tasklist`.
+#[cfg(not(unix))]
+fn is_process_running(pid: u32, command_line: &[String]) -> bool {
+    let Some(program) = command_line.first() else {
+        return false;
+    };
+    let image = Path::new(program).with_extension("exe");
+    let image = image.file_name().unwrap_or_default().to_string_lossy();
+
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid)])
+        .args(["/FI", &format!("IMAGENAME eq {}", image), "/NH"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+}
+
+fn kill_process(pid: u32) -> Result<()> {
+    let mut command = match cfg!(unix) {
+        true => Command::new("kill"),
+        false => {
+            let mut command = Command::new("taskkill");
+            command.args(["/F", "/PID"]);
+            command
+        }
+    };
+
+    let status = command
+        .arg(pid.to_string())
+        .stderr(Stdio::null())
+        .status()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(format_err!("Could not stop the process {}", pid)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fast_ssh::ssh_config_loader::SshConfigLoader;
+    use fast_ssh::FileDatabase;
+
+    fn store() -> SshConfigStore {
+        let loaded = SshConfigLoader::new()
+            .unwrap()
+            .parse(
+                Path::new("config"),
+                "Host prod/db \"db server\"\n    HostName 10.0.0.2\n",
+            )
+            .unwrap();
+        SshConfigStore::from_loaded(loaded, &FileDatabase::in_memory().unwrap()).unwrap()
+    }
+
+    fn tunnel(name: &str, kind: TunnelKind, port: u16, target: Option<&str>) -> TunnelConfig {
+        TunnelConfig {
+            name: name.to_string(),
+            host: "prod/db \"db server\"".to_string(),
+            kind,
+            port,
+            target: target.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_forward_args() {
+        let local = tunnel("db", TunnelKind::Local, 5433, Some("localhost:5432"));
+        assert_eq!(local.forward_args(), ["-L", "5433:localhost:5432"]);
+
+        let dynamic = tunnel("socks", TunnelKind::Dynamic, 1080, None);
+        assert_eq!(dynamic.forward_args(), ["-D", "1080"]);
+        assert!(!tunnel("back", TunnelKind::Remote, 8080, None).is_port_local());
+    }
+
+    #[test]
+    fn test_parse_and_validate() {
+        let tunnels: Vec<TunnelConfig> = serde_yaml::from_str(
+            "- name: db\n  host: prod/db\n  port: 5433\n  target: localhost:5432\n- name: socks\n  host: bastion\n  kind: D\n  port: 1080",
+        )
+        .unwrap();
+        assert_eq!(tunnels[0].kind, TunnelKind::Local);
+        assert_eq!(tunnels[1].kind, TunnelKind::Dynamic);
+        assert!(validate_tunnels(&tunnels).is_empty());
+
+        let invalid = vec![
+            tunnel("db", TunnelKind::Local, 5433, Some("localhost")),
+            tunnel("db", TunnelKind::Dynamic, 0, Some("localhost:22")),
+        ];
+        assert_eq!(
+            validate_tunnels(&invalid),
+            vec![
+                "Tunnel `db` needs a `host:port` target",
+                "Tunnel `db` is defined twice",
+                "Tunnel `db` has no port",
+                "Dynamic tunnel `db` cannot have a target",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ssh_command() {
+        let scs = store();
+        let db = tunnel("db", TunnelKind::Local, 5433, Some("localhost:5432"));
+        let host = scs.find_item(&db.host).unwrap();
+
+        let command = ssh_command(&db, host, Some(Path::new("/tmp/config")));
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "-F",
+                "/tmp/config",
+                "-N",
+                "-o",
+                "ExitOnForwardFailure=yes",
+                "-o",
+                "BatchMode=yes",
+                "-L",
+                "5433:localhost:5432",
+                "prod/db"
+            ]
+        );
+
+        let mut manager = TunnelManager::new(
+            std::env::temp_dir()
+                .join("fast_ssh_tests")
+                .join("tunnels_unknown"),
+        );
+        let unknown = TunnelConfig {
+            host: "staging/db".to_string(),
+            ..db
+        };
+        let error = manager.start(&unknown, &scs, None).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown host staging/db of tunnel db");
+    }
+
+    #[test]
+    fn test_port_in_use() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let pid_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("tunnels_port_{}", std::process::id()));
+        let mut manager = TunnelManager::new(pid_dir);
+
+        let db = tunnel("db", TunnelKind::Local, port, Some("localhost:5432"));
+        assert_eq!(manager.status(&db), TunnelStatus::PortInUse);
+        let error = manager.start(&db, &store(), None).unwrap_err();
+        assert!(error.to_string().contains("already in use"), "{}", error);
+
+        drop(listener);
+        assert_eq!(manager.status(&db), TunnelStatus::Stopped);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reattach_and_stop() {
+        let pid_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("tunnels_pid_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&pid_dir);
+
+        // A remote tunnel, so that its port is not checked
+        let db = tunnel("db", TunnelKind::Remote, 5433, Some("localhost:5432"));
+        let mut command = Command::new("sleep");
+        command.arg("30");
+
+        let mut manager = TunnelManager::new(pid_dir.clone());
+        let pid = manager.spawn(&db, command).unwrap();
+        assert_eq!(manager.status(&db), TunnelStatus::Running(pid));
+        assert!(pid_dir.join("db.pid").exists());
+
+        // The command line is only listed once the forked process ran it
+        let command_line = ["sleep".to_string(), "30".to_string()];
+        for _ in 0..100 {
+            if is_process_running(pid, &command_line) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        // A later run finds the tunnel through its PID file and can stop it
+        let mut later = TunnelManager::new(pid_dir.clone());
+        assert_eq!(later.status(&db), TunnelStatus::Running(pid));
+        later.stop(&db).unwrap();
+        assert!(!pid_dir.join("db.pid").exists());
+        assert_eq!(later.status(&db), TunnelStatus::Exited(None));
+
+        // The signal is sent by another manager, the process takes a moment
+        // to exit
+        for _ in 0..100 {
+            if manager.status(&db) != TunnelStatus::Running(pid) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(manager.status(&db), TunnelStatus::Exited(None));
+
+        let _ = fs::remove_dir_all(&pid_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reused_pid_is_not_attached() {
+        let pid_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("tunnels_reused_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&pid_dir);
+        fs::create_dir_all(&pid_dir).unwrap();
+
+        // The PID of a running process that is not the recorded ssh, and a
+        // file written before the command line was recorded
+        let pid = std::process::id();
+        let ssh = "ssh\n-N\n-R\n5433:localhost:5432\nprod/db";
+        fs::write(pid_dir.join("db.pid"), format!("{}\n{}\n", pid, ssh)).unwrap();
+        fs::write(pid_dir.join("web.pid"), format!("{}\n", pid)).unwrap();
+
+        let mut manager = TunnelManager::new(pid_dir.clone());
+        let db = tunnel("db", TunnelKind::Remote, 5433, Some("localhost:5432"));
+        assert_eq!(manager.status(&db), TunnelStatus::Stopped);
+        assert!(!pid_dir.join("db.pid").exists());
+        assert!(!pid_dir.join("web.pid").exists());
+
+        let _ = fs::remove_dir_all(&pid_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_apply_config_stops_removed_tunnels() {
+        let pid_dir = std::env::temp_dir()
+            .join("fast_ssh_tests")
+            .join(format!("tunnels_apply_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&pid_dir);
+
+        let db = tunnel("db", TunnelKind::Remote, 5434, Some("localhost:5432"));
+        let web = tunnel("web", TunnelKind::Remote, 8081, Some("localhost:80"));
+        let mut manager = TunnelManager::new(pid_dir.clone());
+        for tunnel in [&db, &web] {
+            let mut command = Command::new("sleep");
+            command.arg("30");
+            manager.spawn(tunnel, command).unwrap();
+        }
+
+        let (old, new) = ([db.clone(), web.clone()], [web.clone()]);
+        let changed = manager.apply_config(&old, &new, &store(), None).unwrap();
+
+        assert_eq!(changed, ["db"]);
+        assert!(matches!(manager.status(&db), TunnelStatus::Exited(_)));
+        assert!(matches!(manager.status(&web), TunnelStatus::Running(_)));
+
+        manager.stop(&web).unwrap();
+        let _ = fs::remove_dir_all(&pid_dir);
+    }
+}
@@ -0,0 +1,223 @@
+use tui::{backend::Backend, Frame};
+
+use crate::app::{App, AppState};
+use crate::layout::create_layout;
+use crate::widgets::{
+    command_palette_widget::CommandPaletteWidget, config_widget::ConfigWidget,
+    group_tree_widget::GroupTreeWidget, groups_widget::GroupsWidget, help_widget::HelpWidget,
+    history_widget::HistoryWidget, host_editor_widget::HostEditorWidget, hosts_widget::HostsWidget,
+    prune_widget::PruneWidget, run_prompt_widget::RunPromptWidget,
+    run_results_widget::RunResultsWidget, shortcuts_widget::ShortcutsWidget,
+    status_widget::StatusWidget, tunnels_widget::TunnelsWidget, version_widget::VersionWidget,
+};
+
+/// Draws the whole TUI, popups last so they cover the panels.
+pub fn draw<B: Backend>(app: &mut App, frame: &mut Frame<B>) {
+    let layout = create_layout(app, frame);
+
+    match app.state {
+        AppState::Normal => GroupsWidget::render(app, layout.groups_area, frame),
+        AppState::Searching => app.searcher.render(app, layout.groups_area, frame),
+    };
+
+    if let Some(group_tree_area) = layout.group_tree_area {
+        GroupTreeWidget::render(app, group_tree_area, frame);
+    }
+
+    HelpWidget::render(app, layout.help_area, frame);
+    HostsWidget::render(app, layout.hosts_area, frame);
+    if app.show_run_results {
+        RunResultsWidget::render(app, layout.config_area, frame);
+    } else if app.show_history {
+        HistoryWidget::render(app, layout.config_area, frame);
+    } else {
+        ConfigWidget::render(app, layout.config_area, frame);
+    }
+    StatusWidget::render(app, layout.status_area, frame);
+    VersionWidget::render(app, layout.version_area, frame);
+
+    if let Some(shortcuts_area) = layout.shortcuts_area {
+        ShortcutsWidget::render(app, shortcuts_area, frame);
+    }
+
+    HostEditorWidget::render(app, frame.size(), frame);
+    CommandPaletteWidget::render(app, frame.size(), frame);
+    RunPromptWidget::render(app, frame.size(), frame);
+    TunnelsWidget::render(app, frame.size(), frame);
+    PruneWidget::render(app, frame.size(), frame);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Launch;
+    use crate::config::Config;
+    use crate::input_handler::handle_key;
+    use crate::set_config;
+    use crossterm::event::{KeyCode, KeyEvent};
+    use fast_ssh::ssh_config_loader::SshConfigLoader;
+    use fast_ssh::{FileDatabase, SshConfigStore};
+    use std::fs;
+    use std::path::Path;
+    use tui::{backend::TestBackend, Terminal};
+
+    const SSH_CONFIG: &str = "\
+# Main database
+Host prod/db
+    HostName 10.0.0.2
+    User postgres
+
+Host prod/web
+    HostName 10.0.0.1
+    User deploy
+
+Host staging/web
+    HostName 10.0.1.1
+
+Host bastion
+    HostName bastion.example.com
+    Port 2222
+";
+
+    /// An app built in memory, drawn on a `TestBackend` after every key as
+    /// the event loop does.
+    struct Harness {
+        app: App,
+        terminal: Terminal<TestBackend>,
+    }
+
+    impl Harness {
+        fn new() -> Harness {
+            // The keys and panels of the user config must not change the screens
+            set_config(Config::default());
+
+            let db = FileDatabase::in_memory().unwrap();
+            let loaded = SshConfigLoader::new()
+                .unwrap()
+                .parse(Path::new("config"), SSH_CONFIG)
+                .unwrap();
+            let scs = SshConfigStore::from_loaded(loaded, &db).unwrap();
+            let data_dir = std::env::temp_dir().join("fast_ssh_tests").join("ui");
+
+            let mut app = App::from_store(scs, db, data_dir);
+            app.host_state.select(Some(0));
+
+            let mut harness = Harness {
+                app,
+                terminal: Terminal::new(TestBackend::new(100, 30)).unwrap(),
+            };
+            harness.draw();
+            harness
+        }
+
+        fn draw(&mut self) {
+            let app = &mut self.app;
+            self.terminal.draw(|frame| draw(app, frame)).unwrap();
+        }
+
+        fn press(&mut self, keys: &[KeyCode]) {
+            for key in keys {
+                handle_key(&mut self.app, KeyEvent::from(*key));
+                self.draw();
+            }
+        }
+
+        fn type_text(&mut self, text: &str) {
+            let keys: Vec<KeyCode> = text.chars().map(KeyCode::Char).collect();
+            self.press(&keys);
+        }
+
+        /// Text of the last drawn screen, without trailing spaces.
+        fn screen(&self) -> String {
+            let buffer = self.terminal.backend().buffer();
+            let mut screen = String::new();
+
+            for y in 0..buffer.area.height {
+                let line: String = (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol.as_str())
+                    .collect();
+                screen.push_str(line.trim_end());
+                screen.push('\n');
+            }
+
+            screen
+        }
+
+        fn selected_host(&self) -> Option<&str> {
+            self.app
+                .get_selected_item()
+                .map(|item| item.full_name.as_str())
+        }
+
+        /// Compares the screen to `src/snapshots/<name>.txt`, which is
+        /// written instead when `UPDATE_SNAPSHOTS` is set.
+        fn assert_snapshot(&self, name: &str) {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("src")
+                .join("snapshots")
+                .join(format!("{}.txt", name));
+            let screen = self.screen();
+
+            if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(&path, &screen).unwrap();
+                return;
+            }
+
+            let expected = fs::read_to_string(&path).unwrap_or_default();
+            assert!(
+                screen == expected,
+                "Screen differs from {}, run the tests with UPDATE_SNAPSHOTS=1 to accept it:\n{}",
+                path.display(),
+                screen
+            );
+        }
+    }
+
+    #[test]
+    fn test_navigation_flow() {
+        let mut harness = Harness::new();
+        harness.assert_snapshot("navigation_start");
+
+        assert_eq!(harness.selected_host(), Some("staging/web"));
+
+        harness.press(&[KeyCode::Char('l'), KeyCode::Char('j')]);
+        assert_eq!(harness.app.get_selected_group().unwrap().name, "prod");
+        assert_eq!(harness.selected_host(), Some("prod/web"));
+        harness.assert_snapshot("navigation_next_group");
+
+        harness.press(&[KeyCode::Char('h'), KeyCode::Enter]);
+        assert_eq!(harness.selected_host(), Some("staging/web"));
+        assert_eq!(harness.app.launch, Some(Launch::Ssh));
+    }
+
+    #[test]
+    fn test_search_flow() {
+        let mut harness = Harness::new();
+
+        harness.press(&[KeyCode::Char('s')]);
+        harness.type_text("web");
+        harness.assert_snapshot("search_typing");
+
+        harness.press(&[KeyCode::Enter, KeyCode::Char('n')]);
+        assert!(harness.app.searcher.is_committed());
+        harness.assert_snapshot("search_committed");
+
+        harness.press(&[KeyCode::Esc]);
+        assert!(matches!(harness.app.state, AppState::Normal));
+        assert!(harness.app.launch.is_none());
+    }
+
+    #[test]
+    fn test_help_flow() {
+        let mut harness = Harness::new();
+
+        harness.press(&[KeyCode::Char('?')]);
+        assert!(harness.app.show_help);
+        harness.assert_snapshot("help_open");
+
+        harness.press(&[KeyCode::Char('?')]);
+        assert!(!harness.app.show_help);
+        assert!(!harness.screen().contains("Toggle Shortcuts Panel"));
+    }
+}
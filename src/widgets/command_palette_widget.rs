@@ -0,0 +1,82 @@
+use super::{block, popup};
+use crate::{app::App, get_config, get_theme};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+// Number of entries shown at once
+const VISIBLE_ENTRIES: u16 = 12;
+
+pub struct CommandPaletteWidget {}
+
+impl CommandPaletteWidget {
+    pub fn render<B: Backend>(app: &App, area: Rect, frame: &mut Frame<B>) {
+        let Some(palette) = &app.palette else {
+            return;
+        };
+        let theme = get_theme();
+        let config = get_config();
+
+        let entries = palette.entries(&config.actions);
+        let label_width = entries
+            .iter()
+            .map(|entry| entry.label(&config.actions).len())
+            .max()
+            .unwrap_or(0);
+
+        let items: Vec<ListItem> = entries
+            .iter()
+            .map(|entry| {
+                ListItem::new(Spans::from(vec![
+                    Span::styled(
+                        format!(
+                            "{:<width$}  ",
+                            entry.label(&config.actions),
+                            width = label_width
+                        ),
+                        Style::default().fg(theme.text_primary()),
+                    ),
+                    Span::styled(
+                        entry.keys(&config.keybindings),
+                        Style::default().fg(theme.text_secondary()),
+                    ),
+                ]))
+            })
+            .collect();
+
+        // Filter line, a separator line and the entries, inside the borders
+        let popup_area = popup::centered_area(area, VISIBLE_ENTRIES + 4);
+        let block = block::new(" Command Palette ");
+        let inner_area = block.inner(popup_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(2), Constraint::Min(0)].as_ref())
+            .split(inner_area);
+
+        let filter = Paragraph::new(Spans::from(vec![
+            Span::styled("> ", Style::default().fg(theme.text_secondary())),
+            Span::styled(
+                format!("{}_", palette.filter),
+                Style::default().fg(theme.text_primary()),
+            ),
+        ]));
+
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+
+        let mut state = ListState::default();
+        state.select((!entries.is_empty()).then_some(palette.selected));
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(block, popup_area);
+        frame.render_widget(filter, chunks[0]);
+        frame.render_stateful_widget(list, chunks[1], &mut state);
+    }
+}
@@ -1,11 +1,10 @@
 use crate::{
     app::{App, ConfigDisplayMode as ConfigMode},
     get_theme,
-    ssh_config_store::SshGroupItem,
 };
-use std::io::Stdout;
+use fast_ssh::{ssh_config_resolver, SshGroupItem};
 use tui::{
-    backend::CrosstermBackend,
+    backend::Backend,
     layout::Rect,
     style::{Modifier, Style},
     text::{Span, Spans},
@@ -18,12 +17,13 @@ use super::block;
 pub struct ConfigWidget {}
 
 impl ConfigWidget {
-    pub fn render(app: &App, area: Rect, frame: &mut Frame<CrosstermBackend<Stdout>>) {
+    pub fn render<B: Backend>(app: &App, area: Rect, frame: &mut Frame<B>) {
         let block = block::new(" Configuration ");
 
         let paragraph = match app.config_display_mode {
             ConfigMode::Selected => ConfigWidget::get_paragraph_for_selected_mode(app, block),
             ConfigMode::Global => ConfigWidget::get_paragraph_for_global_mode(app, block),
+            ConfigMode::Resolved => ConfigWidget::get_paragraph_for_resolved_mode(app, block),
         };
 
         frame.render_widget(paragraph.scroll((app.config_paragraph_offset, 0)), area);
@@ -33,7 +33,7 @@ impl ConfigWidget {
         let spans: Vec<Spans> = app
             .get_all_items()
             .iter()
-            .flat_map(|item| ConfigWidget::ssh_group_item_to_spans(item))
+            .flat_map(|item| ConfigWidget::ssh_group_item_to_spans(app, item))
             .collect();
 
         Paragraph::new(spans)
@@ -52,7 +52,7 @@ impl ConfigWidget {
         let config = &app.get_selected_item();
 
         if let Some(config) = config {
-            spans = ConfigWidget::ssh_group_item_to_spans(config);
+            spans = ConfigWidget::ssh_group_item_to_spans(app, config);
         }
 
         Paragraph::new(spans)
@@ -60,22 +60,83 @@ impl ConfigWidget {
             .wrap(Wrap { trim: false })
     }
 
-    fn ssh_group_item_to_spans(config: &SshGroupItem) -> Vec<Spans<'_>> {
+    fn get_paragraph_for_resolved_mode<'a>(app: &'a App, block: Block<'a>) -> Paragraph<'a> {
+        let theme = get_theme();
+
+        let Some(item) = app.get_selected_item() else {
+            return ConfigWidget::get_paragraph_for_selected_mode(app, block);
+        };
+
+        let alias = item.ssh_name();
+        let resolved = ssh_config_resolver::resolve(&app.scs.blocks, &alias);
+
+        let mut spans = vec![Spans::from(vec![
+            Span::styled("Resolved ", Style::default().fg(theme.text_primary())),
+            Span::styled(alias, Style::default().fg(theme.text_secondary())),
+        ])];
+
+        for option in resolved.options {
+            let mut origin = option.origin;
+            if app.scs.files.first() != Some(&option.source_file) {
+                origin = format!("{} ({})", origin, option.source_file.display());
+            }
+
+            spans.push(Spans::from(vec![
+                Span::styled("  ", Style::default().fg(theme.text_primary())),
+                Span::styled(
+                    option.key.to_string(),
+                    Style::default().fg(theme.text_primary()),
+                ),
+                Span::styled(" ", Style::default().fg(theme.text_secondary())),
+                Span::styled(option.value, Style::default().fg(theme.text_secondary())),
+                Span::styled(
+                    format!("  # {}", origin),
+                    Style::default()
+                        .fg(theme.text_secondary())
+                        .add_modifier(Modifier::ITALIC),
+                ),
+            ]));
+        }
+
+        if !resolved.skipped.is_empty() {
+            spans.push(Spans::from(Span::styled(
+                "  Not evaluated",
+                Style::default().fg(theme.text_primary()),
+            )));
+
+            for origin in resolved.skipped {
+                spans.push(Spans::from(vec![
+                    Span::styled("    ", Style::default().fg(theme.text_primary())),
+                    Span::styled(origin, Style::default().fg(theme.text_secondary())),
+                ]));
+            }
+        }
+
+        Paragraph::new(spans)
+            .block(block)
+            .wrap(Wrap { trim: false })
+    }
+
+    fn ssh_group_item_to_spans<'a>(app: &App, config: &'a SshGroupItem) -> Vec<Spans<'a>> {
         let theme = get_theme();
         let mut spans = Vec::new();
 
         // Use split_once for safer string parsing
-        if let Some((group, host)) = config.full_name.split_once('/') {
+        if let Some((group, host)) = config.full_name.rsplit_once('/') {
+            // Breadcrumbs of the nested groups, e.g. `client > prod`
+            let breadcrumbs = group
+                .split('/')
+                .map(|group| group.replace('_', " "))
+                .collect::<Vec<_>>()
+                .join(" > ");
+
             spans.push(Spans::from(vec![
                 Span::styled("Host ", Style::default().fg(theme.text_primary())),
                 Span::styled(host, Style::default().fg(theme.text_secondary())),
             ]));
             spans.push(Spans::from(vec![
                 Span::styled("  Group ", Style::default().fg(theme.text_primary())),
-                Span::styled(
-                    group.replace('_', " "),
-                    Style::default().fg(theme.text_secondary()),
-                ),
+                Span::styled(breadcrumbs, Style::default().fg(theme.text_secondary())),
             ]));
         } else {
             spans.push(Spans::from(vec![
@@ -87,6 +148,37 @@ impl ConfigWidget {
             ]));
         }
 
+        // Only mention the file when the host comes from an included one
+        if app.scs.files.first() != Some(&config.source_file) {
+            spans.push(Spans::from(vec![
+                Span::styled("  File ", Style::default().fg(theme.text_primary())),
+                Span::styled(
+                    config.source_file.display().to_string(),
+                    Style::default().fg(theme.text_secondary()),
+                ),
+            ]));
+        }
+
+        if !config.metadata.tags.is_empty() {
+            spans.push(Spans::from(vec![
+                Span::styled("  Tags ", Style::default().fg(theme.text_primary())),
+                Span::styled(
+                    config.metadata.tags.join(", "),
+                    Style::default().fg(theme.text_secondary()),
+                ),
+            ]));
+        }
+
+        for (key, value) in &config.metadata.fields {
+            spans.push(Spans::from(vec![
+                Span::styled(
+                    format!("  @{} ", key),
+                    Style::default().fg(theme.text_primary()),
+                ),
+                Span::styled(value, Style::default().fg(theme.text_secondary())),
+            ]));
+        }
+
         config.host_config.iter().for_each(|(key, value)| {
             spans.push(Spans::from(vec![
                 Span::styled("  ", Style::default().fg(theme.text_primary())),
@@ -97,9 +189,10 @@ impl ConfigWidget {
         });
 
         if let Some(comment) = &config.comment {
-            spans.push(Spans::from(vec![
-                Span::styled("  Notes", Style::default().fg(theme.text_primary())),
-            ]));
+            spans.push(Spans::from(vec![Span::styled(
+                "  Notes",
+                Style::default().fg(theme.text_primary()),
+            )]));
 
             for line in comment.lines() {
                 spans.push(Spans::from(vec![
@@ -0,0 +1,57 @@
+use super::block;
+use crate::{app::App, get_theme};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{List, ListItem, ListState},
+    Frame,
+};
+
+pub struct GroupTreeWidget {}
+
+impl GroupTreeWidget {
+    pub fn render<B: Backend>(app: &App, area: Rect, frame: &mut Frame<B>) {
+        let theme = get_theme();
+        let visible_groups = app.visible_groups();
+
+        let items: Vec<ListItem> = visible_groups
+            .iter()
+            .filter_map(|idx| app.scs.groups.get(*idx).map(|group| (*idx, group)))
+            .map(|(idx, group)| {
+                let marker = match app.scs.has_subgroups(idx) {
+                    true if app.expanded_groups.contains(&group.name) => "▾ ",
+                    true => "▸ ",
+                    false => "  ",
+                };
+
+                ListItem::new(Spans::from(vec![
+                    Span::raw("  ".repeat(group.depth())),
+                    Span::styled(marker, Style::default().fg(theme.text_primary())),
+                    Span::styled(
+                        group.label().to_string(),
+                        Style::default().fg(theme.text_secondary()),
+                    ),
+                    Span::styled(
+                        format!(" ({})", group.items.len()),
+                        Style::default().fg(theme.text_primary()),
+                    ),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block::new(" Groups "))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED));
+
+        let mut state = ListState::default();
+        state.select(
+            visible_groups
+                .iter()
+                .position(|idx| *idx == app.selected_group),
+        );
+
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
@@ -1,23 +1,23 @@
 use super::block;
 use crate::{get_theme, App};
-use std::io::Stdout;
 use tui::layout::Rect;
 use tui::style::{Modifier, Style};
 use tui::text::{Span, Spans};
 use tui::widgets::Tabs;
-use tui::{backend::CrosstermBackend, Frame};
+use tui::{backend::Backend, Frame};
 
 pub struct GroupsWidget {}
 
 impl GroupsWidget {
-    pub fn render(app: &App, area: Rect, frame: &mut Frame<CrosstermBackend<Stdout>>) {
+    pub fn render<B: Backend>(app: &App, area: Rect, frame: &mut Frame<B>) {
         let theme = get_theme();
         let block = block::new(" Groups ");
 
-        let mut titles: Vec<Spans> = app
-            .scs
-            .groups
+        // Subgroups get a tab once their parent is expanded
+        let visible_groups = app.visible_groups();
+        let mut titles: Vec<Spans> = visible_groups
             .iter()
+            .filter_map(|idx| app.scs.groups.get(*idx))
             .map(|t| {
                 Spans::from(Span::styled(
                     t.name.to_string(),
@@ -26,9 +26,12 @@ impl GroupsWidget {
             })
             .collect();
 
-        // Rotate to show selected group first (safe: bounds checked above)
-        if !titles.is_empty() && app.selected_group < titles.len() {
-            titles.rotate_left(app.selected_group);
+        // Rotate to show selected group first
+        if let Some(selected) = visible_groups
+            .iter()
+            .position(|idx| *idx == app.selected_group)
+        {
+            titles.rotate_left(selected);
         }
 
         let tabs = Tabs::new(titles)
@@ -1,19 +1,20 @@
 use crate::app::App;
-use crate::get_theme;
-use std::io::Stdout;
-use tui::{
-    backend::CrosstermBackend, layout::Rect, style::Style, text::Spans, widgets::Paragraph, Frame,
-};
+use crate::keybindings::Action;
+use crate::{get_config, get_theme};
+use tui::{backend::Backend, layout::Rect, style::Style, text::Spans, widgets::Paragraph, Frame};
 
 use super::block;
 
 pub struct HelpWidget {}
 
 impl HelpWidget {
-    pub fn render(_app: &App, area: Rect, frame: &mut Frame<CrosstermBackend<Stdout>>) {
+    pub fn render<B: Backend>(_app: &App, area: Rect, frame: &mut Frame<B>) {
         let block = block::new("");
 
-        let help_span = Spans::from("'?' Show help");
+        let help_span = Spans::from(format!(
+            "'{}' Show help",
+            get_config().keybindings.describe(Action::ToggleHelp)
+        ));
 
         let paragraph = Paragraph::new(help_span)
             .block(block)
@@ -0,0 +1,74 @@
+use super::{block, hosts_widget::HostsWidget};
+use crate::{app::App, get_theme};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    style::Style,
+    widgets::{Cell, Row, Table},
+    Frame,
+};
+
+pub struct HistoryWidget {}
+
+impl HistoryWidget {
+    pub fn render<B: Backend>(app: &mut App, area: Rect, frame: &mut Frame<B>) {
+        let theme = get_theme();
+
+        // Show the selected host history, or every launch if none is selected
+        let title = match app.get_selected_item() {
+            Some(item) => format!(" History of {} ", item.full_name),
+            None => " History ".to_string(),
+        };
+
+        let entries = app.history();
+
+        let header = Row::new(
+            ["Date", "Action", "Duration", "Exit"]
+                .iter()
+                .map(|h| Cell::from(*h).style(Style::default().fg(theme.text_secondary()))),
+        )
+        .height(1)
+        .bottom_margin(1);
+
+        let rows = entries.iter().map(|entry| {
+            Row::new([
+                Cell::from(HostsWidget::format_timestamp(entry.started_at)),
+                Cell::from(entry.action.clone()),
+                Cell::from(HistoryWidget::format_duration(entry.duration())),
+                Cell::from(HistoryWidget::format_exit_code(entry.exit_code)),
+            ])
+        });
+
+        let table = Table::new(rows)
+            .header(header)
+            .block(block::new(&title))
+            .style(Style::default().fg(theme.text_primary()))
+            .widths(&[
+                Constraint::Percentage(35),
+                Constraint::Percentage(25),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+            ]);
+
+        frame.render_widget(table, area);
+    }
+
+    pub fn format_duration(secs: i64) -> String {
+        let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+
+        if hours > 0 {
+            format!("{}h{:02}m", hours, minutes)
+        } else if minutes > 0 {
+            format!("{}m{:02}s", minutes, seconds)
+        } else {
+            format!("{}s", seconds)
+        }
+    }
+
+    pub fn format_exit_code(exit_code: Option<i32>) -> String {
+        match exit_code {
+            Some(code) => code.to_string(),
+            None => "signal".to_string(),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use super::{block, popup};
+use crate::{
+    app::App,
+    get_theme,
+    host_editor::{EditorField, EditorMode},
+};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+// Width of the field labels column
+const LABEL_WIDTH: usize = 14;
+
+pub struct HostEditorWidget {}
+
+impl HostEditorWidget {
+    pub fn render<B: Backend>(app: &App, area: Rect, frame: &mut Frame<B>) {
+        let Some(editor) = &app.host_editor else {
+            return;
+        };
+        let theme = get_theme();
+
+        let title = match editor.mode {
+            EditorMode::Add => " Add Host ",
+            EditorMode::Edit => " Edit Host ",
+            EditorMode::Duplicate => " Duplicate Host ",
+        };
+
+        let mut text: Vec<Spans> = EditorField::ALL
+            .iter()
+            .map(|field| {
+                let focused = *field == editor.focused_field();
+                let mut value = editor.value(*field).to_string();
+                if focused {
+                    value.push('_');
+                }
+
+                let label_style = match focused {
+                    true => Style::default()
+                        .fg(theme.text_secondary())
+                        .add_modifier(Modifier::BOLD),
+                    false => Style::default().fg(theme.text_secondary()),
+                };
+
+                Spans::from(vec![
+                    Span::styled(
+                        format!("{:<width$}", field.label(), width = LABEL_WIDTH),
+                        label_style,
+                    ),
+                    Span::styled(value, Style::default().fg(theme.text_primary())),
+                ])
+            })
+            .collect();
+
+        text.push(Spans::from(""));
+        text.push(match &editor.error {
+            Some(error) => Spans::from(Span::styled(
+                error.as_str(),
+                Style::default().fg(theme.error_color()),
+            )),
+            None => Spans::from(Span::styled(
+                "Tab/Up/Down: Move  Enter: Save  Esc: Cancel",
+                Style::default().fg(theme.text_secondary()),
+            )),
+        });
+
+        let popup_area = popup::centered_area(area, text.len() as u16 + 2);
+        let paragraph = Paragraph::new(text)
+            .block(block::new(title))
+            .alignment(Alignment::Left);
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(paragraph, popup_area);
+    }
+}
@@ -1,27 +1,58 @@
 use super::block;
-use crate::{app::App, get_theme, ssh_config_store::SshGroupItem};
-use chrono::{DateTime, Utc};
-use std::{
-    io::Stdout,
-    time::{Duration, UNIX_EPOCH},
+use crate::{
+    app::{App, AppState},
+    get_theme,
+    reachability::{ProbeStatus, ReachabilityChecker},
 };
+use chrono::{DateTime, Utc};
+use fast_ssh::SshGroupItem;
+use std::time::{Duration, UNIX_EPOCH};
 use tui::{
-    backend::CrosstermBackend,
+    backend::Backend,
     layout::{Constraint, Rect},
-    style::Style,
+    style::{Modifier, Style},
+    text::{Span, Spans},
     widgets::{Cell, Row, Table},
     Frame,
 };
 
+// Shown before the name of starred hosts
+const FAVORITE_MARK: &str = "★ ";
+
+// Shown before the name of the hosts picked to run a command on
+const SELECTION_MARK: &str = "✓ ";
+
 pub struct HostsWidget {}
 
 impl HostsWidget {
-    pub fn render(app: &mut App, area: Rect, frame: &mut Frame<CrosstermBackend<Stdout>>) {
+    pub fn render<B: Backend>(app: &mut App, area: Rect, frame: &mut Frame<B>) {
         let theme = get_theme();
-        let block = block::new(" Hosts ");
+        let title = match app.selected_hosts.len() {
+            0 => " Hosts ".to_string(),
+            count => format!(" Hosts ({} selected) ", count),
+        };
+        let block = block::new(&title);
         let header = HostsWidget::create_header();
-        let items = app.get_items_based_on_mode();
-        let rows = HostsWidget::create_rows_from_items(&items);
+        let (items, highlights): (Vec<&SshGroupItem>, Vec<Vec<usize>>) = match app.state {
+            AppState::Searching => app
+                .searcher
+                .search(app)
+                .into_iter()
+                .map(|result| (result.item, result.name_indices))
+                .unzip(),
+            AppState::Normal => {
+                let items = app.get_items_based_on_mode();
+                let highlights = vec![Vec::new(); items.len()];
+                (items, highlights)
+            }
+        };
+
+        // Check the listed hosts, results show up on the next frames
+        for item in &items {
+            app.reachability.probe(item, &app.scs.blocks);
+        }
+
+        let rows = HostsWidget::create_rows_from_items(&items, &highlights, app);
 
         // Ensure selection is within bounds
         if app.host_state.selected().unwrap_or(0) >= items.len() && !items.is_empty() {
@@ -35,9 +66,10 @@ impl HostsWidget {
             .style(Style::default().fg(theme.text_secondary()))
             .highlight_symbol(">> ")
             .widths(&[
-                Constraint::Percentage(50),
-                Constraint::Percentage(30),
-                Constraint::Percentage(20),
+                Constraint::Percentage(45),
+                Constraint::Percentage(25),
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
             ]);
 
         frame.render_stateful_widget(t, area, &mut app.host_state);
@@ -45,7 +77,7 @@ impl HostsWidget {
 
     fn create_header() -> Row<'static> {
         let theme = get_theme();
-        const HEADERS: [&str; 3] = ["Host", "Last Used", "# of Conn"];
+        const HEADERS: [&str; 4] = ["Host", "Last Used", "# of Conn", "Status"];
         let header_cells = HEADERS
             .iter()
             .map(|h| Cell::from(*h).style(Style::default().fg(theme.text_secondary())));
@@ -56,17 +88,31 @@ impl HostsWidget {
             .bottom_margin(1)
     }
 
-    fn create_rows_from_items(items: &[&SshGroupItem]) -> Vec<Row<'static>> {
+    fn create_rows_from_items(
+        items: &[&SshGroupItem],
+        highlights: &[Vec<usize>],
+        app: &App,
+    ) -> Vec<Row<'static>> {
         let style = Style::default();
         items
             .iter()
-            .map(|item| {
+            .zip(highlights)
+            .map(|(item, highlight)| {
                 let timestamp_str = HostsWidget::format_last_used_date(item);
 
+                let mut name = HostsWidget::highlight_name(&item.name, highlight);
+                if app.scs.is_favorite(&item.full_name) {
+                    name.0.insert(0, Span::raw(FAVORITE_MARK));
+                }
+                if app.selected_hosts.contains(&item.full_name) {
+                    name.0.insert(0, Span::raw(SELECTION_MARK));
+                }
+
                 let cells = [
-                    Cell::from(item.name.to_string()).style(style),
+                    Cell::from(name).style(style),
                     Cell::from(timestamp_str).style(style),
                     Cell::from(item.connection_count.to_string()).style(style),
+                    HostsWidget::status_cell(&app.reachability, item),
                 ];
 
                 Row::new(cells).height(1).bottom_margin(1)
@@ -74,13 +120,54 @@ impl HostsWidget {
             .collect::<Vec<Row<'static>>>()
     }
 
-    fn format_last_used_date(item: &SshGroupItem) -> String {
-        if item.last_used <= 0 {
+    fn status_cell(reachability: &ReachabilityChecker, item: &SshGroupItem) -> Cell<'static> {
+        if !reachability.is_enabled() {
+            return Cell::from("");
+        }
+
+        match reachability.status(&item.full_name) {
+            Some(ProbeStatus::Up(latency)) => Cell::from(format!("{} ms", latency.as_millis())),
+            Some(ProbeStatus::Down) => {
+                Cell::from("down").style(Style::default().fg(get_theme().error_color()))
+            }
+            Some(ProbeStatus::Unknown) => Cell::from("-"),
+            Some(ProbeStatus::Checking) | None => Cell::from("..."),
+        }
+    }
+
+    /// Emphasizes the characters matched by the search query.
+    fn highlight_name(name: &str, highlight: &[usize]) -> Spans<'static> {
+        if highlight.is_empty() {
+            return Spans::from(name.to_string());
+        }
+
+        let match_style = Style::default()
+            .fg(get_theme().text_primary())
+            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+        let spans = name
+            .chars()
+            .enumerate()
+            .map(|(idx, c)| match highlight.contains(&idx) {
+                true => Span::styled(c.to_string(), match_style),
+                false => Span::raw(c.to_string()),
+            })
+            .collect::<Vec<Span>>();
+
+        Spans::from(spans)
+    }
+
+    pub fn format_last_used_date(item: &SshGroupItem) -> String {
+        HostsWidget::format_timestamp(item.last_used)
+    }
+
+    pub fn format_timestamp(timestamp: i64) -> String {
+        if timestamp <= 0 {
             return "Never".to_string();
         }
-        
+
         // Safely convert i64 to u64, handling potential negative values
-        match item.last_used.try_into() {
+        match timestamp.try_into() {
             Ok(secs) => {
                 let d = UNIX_EPOCH + Duration::from_secs(secs);
                 let dt = DateTime::<Utc>::from(d);
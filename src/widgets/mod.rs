@@ -1,7 +1,17 @@
 pub mod block;
+pub mod command_palette_widget;
 pub mod config_widget;
+pub mod group_tree_widget;
 pub mod groups_widget;
 pub mod help_widget;
+pub mod history_widget;
+pub mod host_editor_widget;
 pub mod hosts_widget;
+pub mod popup;
+pub mod prune_widget;
+pub mod run_prompt_widget;
+pub mod run_results_widget;
 pub mod shortcuts_widget;
+pub mod status_widget;
+pub mod tunnels_widget;
 pub mod version_widget;
@@ -0,0 +1,14 @@
+use tui::layout::Rect;
+
+/// Area of the given height centered in `area`, two thirds of its width.
+pub fn centered_area(area: Rect, height: u16) -> Rect {
+    let width = (area.width * 2 / 3).max(50).min(area.width);
+    let height = height.min(area.height);
+
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
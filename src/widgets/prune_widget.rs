@@ -0,0 +1,108 @@
+use super::{block, hosts_widget::HostsWidget, popup};
+use crate::{app::App, get_config, get_theme, keybindings::Action};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Cell, Clear, Paragraph, Row, Table},
+    Frame,
+};
+
+// Number of entries shown at once
+const VISIBLE_ENTRIES: u16 = 12;
+
+pub struct PruneWidget {}
+
+impl PruneWidget {
+    pub fn render<B: Backend>(app: &mut App, area: Rect, frame: &mut Frame<B>) {
+        if app.prune_panel.is_none() {
+            return;
+        }
+        let theme = get_theme();
+
+        let rows: Vec<Row> =
+            app.stale_entries
+                .iter()
+                .map(|entry| {
+                    let rename = match &entry.rename {
+                        Some(new_key) if entry.merges_existing => {
+                            Cell::from(format!("{} (merge)", new_key))
+                                .style(Style::default().fg(theme.text_primary()))
+                        }
+                        Some(new_key) => Cell::from(new_key.clone())
+                            .style(Style::default().fg(theme.text_primary())),
+                        None => Cell::from("-"),
+                    };
+
+                    Row::new([
+                        Cell::from(entry.key.clone()),
+                        Cell::from(entry.connection_count.to_string()),
+                        Cell::from(entry.launches.to_string()),
+                        Cell::from(HostsWidget::format_timestamp(entry.last_used_date)),
+                        rename,
+                    ])
+                })
+                .collect();
+
+        let header = Row::new(
+            [
+                "Entry",
+                "Connections",
+                "Launches",
+                "Last Used",
+                "Renamed To?",
+            ]
+            .iter()
+            .map(|h| Cell::from(*h).style(Style::default().fg(theme.text_primary()))),
+        )
+        .bottom_margin(1);
+
+        let table = Table::new(rows)
+            .header(header)
+            .style(Style::default().fg(theme.text_secondary()))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ")
+            .widths(&[
+                Constraint::Percentage(25),
+                Constraint::Percentage(12),
+                Constraint::Percentage(10),
+                Constraint::Percentage(18),
+                Constraint::Percentage(35),
+            ]);
+
+        let bindings = &get_config().keybindings;
+        let help = match app.stale_entries.is_empty() {
+            true => "Every entry of the database matches a host of the SSH config".to_string(),
+            false => format!(
+                "{}: Merge into Renamed Host  {}: Delete  {}: Close",
+                bindings.describe(Action::MergeStaleEntry),
+                bindings.describe(Action::DeleteStaleEntry),
+                bindings.describe(Action::ClosePanel)
+            ),
+        };
+
+        // Header, entries and help line, inside the borders
+        let popup_area = popup::centered_area(area, VISIBLE_ENTRIES + 6);
+        let block = block::new(" Database Clean-Up ");
+        let inner_area = block.inner(popup_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(inner_area);
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(block, popup_area);
+        if let Some(state) = &mut app.prune_panel {
+            frame.render_stateful_widget(table, chunks[0], state);
+        }
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                help,
+                Style::default().fg(theme.text_secondary()),
+            )),
+            chunks[1],
+        );
+    }
+}
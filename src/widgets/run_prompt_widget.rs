@@ -0,0 +1,48 @@
+use super::{block, popup};
+use crate::{app::App, get_theme};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+pub struct RunPromptWidget {}
+
+impl RunPromptWidget {
+    pub fn render<B: Backend>(app: &App, area: Rect, frame: &mut Frame<B>) {
+        let Some(command) = &app.run_prompt else {
+            return;
+        };
+        let theme = get_theme();
+
+        let targets = app.run_targets();
+        let title = match targets.as_slice() {
+            [host] => format!(" Run on {} ", host.full_name),
+            targets => format!(" Run on {} hosts ", targets.len()),
+        };
+
+        let text = vec![
+            Spans::from(vec![
+                Span::styled("$ ", Style::default().fg(theme.text_secondary())),
+                Span::styled(
+                    format!("{}_", command),
+                    Style::default().fg(theme.text_primary()),
+                ),
+            ]),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                "Enter: Run  Esc: Cancel",
+                Style::default().fg(theme.text_secondary()),
+            )),
+        ];
+
+        let popup_area = popup::centered_area(area, text.len() as u16 + 2);
+        let paragraph = Paragraph::new(text).block(block::new(&title));
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(paragraph, popup_area);
+    }
+}
@@ -0,0 +1,80 @@
+use super::block;
+use crate::{
+    app::App,
+    get_theme,
+    parallel_run::{OutputStream, RunStatus},
+};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
+
+pub struct RunResultsWidget {}
+
+impl RunResultsWidget {
+    pub fn render<B: Backend>(app: &App, area: Rect, frame: &mut Frame<B>) {
+        let Some(run) = &app.parallel_run else {
+            return;
+        };
+        let theme = get_theme();
+        let runs = run.runs();
+
+        let done = runs.iter().filter(|run| run.status.is_done()).count();
+        let failed = runs
+            .iter()
+            .filter(|run| run.status.is_done() && !run.status.is_success())
+            .count();
+        let title = format!(
+            " {} ({}/{} done, {} failed) ",
+            run.command,
+            done,
+            runs.len(),
+            failed
+        );
+
+        let mut spans = Vec::new();
+        for host_run in runs {
+            let (status, status_style) = match &host_run.status {
+                RunStatus::Queued => ("queued".to_string(), theme.text_secondary()),
+                RunStatus::Running => ("running".to_string(), theme.text_secondary()),
+                RunStatus::Finished(Some(0)) => ("exit 0".to_string(), theme.text_primary()),
+                RunStatus::Finished(Some(code)) => (format!("exit {}", code), theme.error_color()),
+                RunStatus::Finished(None) => ("signal".to_string(), theme.error_color()),
+                RunStatus::Failed(error) => (error.clone(), theme.error_color()),
+            };
+
+            spans.push(Spans::from(vec![
+                Span::styled(
+                    host_run.host,
+                    Style::default()
+                        .fg(theme.text_primary())
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("  "),
+                Span::styled(status, Style::default().fg(status_style)),
+            ]));
+
+            for (stream, line) in host_run.output {
+                let style = match stream {
+                    OutputStream::Stdout => Style::default().fg(theme.text_secondary()),
+                    OutputStream::Stderr => Style::default().fg(theme.error_color()),
+                };
+                spans.push(Spans::from(vec![
+                    Span::raw("  "),
+                    Span::styled(line, style),
+                ]));
+            }
+        }
+
+        let paragraph = Paragraph::new(spans)
+            .block(block::new(&title))
+            .wrap(Wrap { trim: false })
+            .scroll((app.config_paragraph_offset, 0));
+
+        frame.render_widget(paragraph, area);
+    }
+}
@@ -1,7 +1,10 @@
-use crate::{get_theme, App};
-use std::io::Stdout;
+use crate::{
+    get_config, get_theme,
+    keybindings::{Action, Section},
+    App,
+};
 use tui::{
-    backend::CrosstermBackend,
+    backend::Backend,
     layout::Rect,
     style::Style,
     text::Spans,
@@ -14,40 +17,10 @@ use super::block;
 pub struct ShortcutsWidget {}
 
 impl ShortcutsWidget {
-    pub fn render(_app: &App, area: Rect, frame: &mut Frame<CrosstermBackend<Stdout>>) {
+    pub fn render<B: Backend>(_app: &App, area: Rect, frame: &mut Frame<B>) {
         let block = block::new(" Help ");
 
-        let text = vec![
-            Spans::from("=== General ==="),
-            Spans::from("'?': Toggle Shortcuts Panel"),
-            Spans::from("'q': Exit Fast-SSH"),
-            Spans::from(""),
-            Spans::from("=== Navigation ==="),
-            Spans::from("'Tab/BackTab': Change Group"),
-            Spans::from("'Left/Right Arrow or h/l': Change Group"),
-            Spans::from("'Space': Select Recents Group"),
-            Spans::from("'Up/Down Arrow or k/j': Navigate Hosts"),
-            Spans::from("'gg': Jump to First Host"),
-            Spans::from("'G': Jump to Last Host"),
-            Spans::from("'Ctrl+d': Scroll Half Page Down"),
-            Spans::from("'Ctrl+u': Scroll Half Page Up"),
-            Spans::from(""),
-            Spans::from("=== Actions ==="),
-            Spans::from("'Enter': Open Selected SSH Connection"),
-            Spans::from("'K': Copy SSH Key and Exit"),
-            Spans::from("'C': Copy Files and Exit"),
-            Spans::from("'c': Switch Config Display Mode"),
-            Spans::from("'PageUp/Down': Scroll Configuration"),
-            Spans::from(""),
-            Spans::from("=== Search Mode ==="),
-            Spans::from("'s' or '/': Enable Search Mode"),
-            Spans::from("'Enter': Commit Search (press again to connect)"),
-            Spans::from("'Esc' or 'q': Exit Search Mode"),
-            Spans::from("'n/N': Next/Previous Match (after commit)"),
-            Spans::from("'Alt+j/k': Navigate Matches (after commit)"),
-        ];
-
-        let paragraph = Paragraph::new(text)
+        let paragraph = Paragraph::new(ShortcutsWidget::create_lines())
             .alignment(tui::layout::Alignment::Left)
             .block(block)
             .style(Style::default().fg(get_theme().text_secondary()))
@@ -55,4 +28,47 @@ impl ShortcutsWidget {
 
         frame.render_widget(paragraph, area);
     }
+
+    /// One line per action, grouped by section, showing the active bindings.
+    fn create_lines() -> Vec<Spans<'static>> {
+        let bindings = &get_config().keybindings;
+        let mut text = Vec::new();
+
+        for section in Section::ALL {
+            if !text.is_empty() {
+                text.push(Spans::from(""));
+            }
+            text.push(Spans::from(format!("=== {} ===", section.title())));
+
+            if section == Section::Search {
+                text.push(Spans::from(
+                    "'Enter': Commit Search (press again to connect)",
+                ));
+            }
+
+            for action in Action::ALL
+                .iter()
+                .filter(|action| action.section() == section)
+            {
+                let keys = bindings.describe(*action);
+                if !keys.is_empty() {
+                    text.push(Spans::from(format!("'{}': {}", keys, action.description())));
+                }
+            }
+        }
+
+        let config = get_config();
+        if !config.actions.is_empty() {
+            text.push(Spans::from(""));
+            text.push(Spans::from("=== Custom Actions ==="));
+        }
+        for (idx, action) in config.actions.iter().enumerate() {
+            text.push(match bindings.custom_key(idx) {
+                Some(key) => Spans::from(format!("'{}': {}", key, action.name)),
+                None => Spans::from(action.name.clone()),
+            });
+        }
+
+        text
+    }
 }
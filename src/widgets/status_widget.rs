@@ -0,0 +1,19 @@
+use crate::app::App;
+use crate::get_theme;
+use tui::{backend::Backend, layout::Rect, style::Style, text::Spans, widgets::Paragraph, Frame};
+
+pub struct StatusWidget {}
+
+impl StatusWidget {
+    pub fn render<B: Backend>(app: &App, area: Rect, frame: &mut Frame<B>) {
+        let Some(message) = &app.status_message else {
+            return;
+        };
+
+        let paragraph = Paragraph::new(Spans::from(message.as_str()))
+            .style(Style::default().fg(get_theme().text_secondary()))
+            .alignment(tui::layout::Alignment::Left);
+
+        frame.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,103 @@
+use super::{block, popup};
+use crate::{app::App, get_config, get_theme, keybindings::Action, tunnels::TunnelStatus};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Cell, Clear, Paragraph, Row, Table},
+    Frame,
+};
+
+// Number of tunnels shown at once
+const VISIBLE_TUNNELS: u16 = 10;
+
+pub struct TunnelsWidget {}
+
+impl TunnelsWidget {
+    pub fn render<B: Backend>(app: &mut App, area: Rect, frame: &mut Frame<B>) {
+        if app.tunnels_panel.is_none() {
+            return;
+        }
+        let theme = get_theme();
+        let tunnels = &get_config().tunnels;
+
+        let rows: Vec<Row> = tunnels
+            .iter()
+            .map(|tunnel| {
+                let status = match app.tunnels.status(tunnel) {
+                    TunnelStatus::Stopped => Cell::from("stopped"),
+                    TunnelStatus::Running(pid) => Cell::from(format!("running (PID {})", pid))
+                        .style(Style::default().fg(theme.text_primary())),
+                    TunnelStatus::Exited(Some(code)) => {
+                        Cell::from(format!("exited with code {}", code))
+                            .style(Style::default().fg(theme.error_color()))
+                    }
+                    TunnelStatus::Exited(None) => Cell::from("stopped by a signal"),
+                    TunnelStatus::PortInUse => {
+                        Cell::from("port in use").style(Style::default().fg(theme.error_color()))
+                    }
+                };
+
+                Row::new([
+                    Cell::from(tunnel.name.clone()),
+                    Cell::from(tunnel.host.clone()),
+                    Cell::from(tunnel.forward_args().join(" ")),
+                    status,
+                ])
+            })
+            .collect();
+
+        let header = Row::new(
+            ["Name", "Host", "Forward", "Status"]
+                .iter()
+                .map(|h| Cell::from(*h).style(Style::default().fg(theme.text_primary()))),
+        )
+        .bottom_margin(1);
+
+        let table = Table::new(rows)
+            .header(header)
+            .style(Style::default().fg(theme.text_secondary()))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ")
+            .widths(&[
+                Constraint::Percentage(20),
+                Constraint::Percentage(25),
+                Constraint::Percentage(30),
+                Constraint::Percentage(25),
+            ]);
+
+        let bindings = &get_config().keybindings;
+        let help = match tunnels.is_empty() {
+            true => "No tunnel defined, see the `tunnels` section of the configuration".to_string(),
+            false => format!(
+                "{}: Start/Stop  {}: Close",
+                bindings.describe(Action::ToggleTunnel),
+                bindings.describe(Action::ClosePanel)
+            ),
+        };
+
+        // Header, tunnels and help line, inside the borders
+        let popup_area = popup::centered_area(area, VISIBLE_TUNNELS + 6);
+        let block = block::new(" Tunnels ");
+        let inner_area = block.inner(popup_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(inner_area);
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(block, popup_area);
+        if let Some(state) = &mut app.tunnels_panel {
+            frame.render_stateful_widget(table, chunks[0], state);
+        }
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                help,
+                Style::default().fg(theme.text_secondary()),
+            )),
+            chunks[1],
+        );
+    }
+}
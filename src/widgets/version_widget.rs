@@ -1,14 +1,11 @@
 use crate::app::App;
 use crate::get_theme;
-use std::io::Stdout;
-use tui::{
-    backend::CrosstermBackend, layout::Rect, style::Style, text::Spans, widgets::Paragraph, Frame,
-};
+use tui::{backend::Backend, layout::Rect, style::Style, text::Spans, widgets::Paragraph, Frame};
 
 pub struct VersionWidget {}
 
 impl VersionWidget {
-    pub fn render(_app: &App, area: Rect, frame: &mut Frame<CrosstermBackend<Stdout>>) {
+    pub fn render<B: Backend>(_app: &App, area: Rect, frame: &mut Frame<B>) {
         let version = env!("CARGO_PKG_VERSION");
         let version_text = format!("v{}", version);
 